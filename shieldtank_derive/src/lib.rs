@@ -1,14 +1,254 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{
+    Attribute, Data, DeriveInput, Expr, Fields, GenericArgument, LitStr, PathArguments, Type,
+    parse_macro_input,
+};
+
+/// Maps the LDtk field instances of an entity onto a struct.
+///
+/// Container attributes:
+/// - `#[ldtk(entity = "Player")]`: only insert on LDtk entities with this identifier.
+///
+/// Field attributes:
+/// - `#[ldtk(rename = "Identifier")]`: read from this LDtk field instead of the Rust field name.
+/// - `#[ldtk(default)]` or `#[ldtk(default = expr)]`: used when the field is missing. Not allowed
+///   on `Option<T>` fields.
+///
+/// `Option<T>` fields are `None` when the field is missing, and fail like any other field
+/// when it holds a value of another type.
+#[proc_macro_derive(LdtkFields, attributes(ldtk))]
+pub fn derive_ldtk_fields(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    ldtk_fields(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Maps LDtk enum values onto the unit variants of a Rust enum, so it can be used as a field of
+/// an `LdtkFields` struct.
+///
+/// Variant attributes:
+/// - `#[ldtk(rename = "Value")]`: match this LDtk enum value instead of the variant name.
+#[proc_macro_derive(LdtkEnum, attributes(ldtk))]
+pub fn derive_ldtk_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    ldtk_enum(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+#[derive(Default)]
+struct LdtkAttributes {
+    entity: Option<LitStr>,
+    rename: Option<LitStr>,
+    default: Option<Option<Expr>>,
+}
+
+impl LdtkAttributes {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut ldtk_attributes = Self::default();
+
+        attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("ldtk"))
+            .try_for_each(|attr| {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("entity") {
+                        ldtk_attributes.entity = Some(meta.value()?.parse()?);
+                    } else if meta.path.is_ident("rename") {
+                        ldtk_attributes.rename = Some(meta.value()?.parse()?);
+                    } else if meta.path.is_ident("default") {
+                        let expr = match meta.input.peek(syn::Token![=]) {
+                            true => Some(meta.value()?.parse()?),
+                            false => None,
+                        };
+                        ldtk_attributes.default = Some(expr);
+                    } else {
+                        return Err(meta.error("unsupported ldtk attribute"));
+                    }
+                    Ok(())
+                })
+            })?;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+        Ok(ldtk_attributes)
     }
 }
+
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+
+    match arguments.args.first()? {
+        GenericArgument::Type(inner) => Some(inner),
+        _ => None,
+    }
+}
+
+fn ldtk_fields(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let container_attributes = LdtkAttributes::parse(&input.attrs)?;
+    let entity_identifier = match container_attributes.entity {
+        Some(entity) => quote! { ::core::option::Option::Some(#entity) },
+        None => quote! { ::core::option::Option::None },
+    };
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.span(),
+            "LdtkFields can only be derived for structs with named fields",
+        ));
+    };
+
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new(
+            data.fields.span(),
+            "LdtkFields can only be derived for structs with named fields",
+        ));
+    };
+
+    let field_initializers = fields
+        .named
+        .iter()
+        .map(|field| -> syn::Result<TokenStream2> {
+            let ident = field.ident.as_ref().expect("named field");
+            let ty = &field.ty;
+            let attributes = LdtkAttributes::parse(&field.attrs)?;
+
+            if let Some(entity) = attributes.entity {
+                return Err(syn::Error::new(
+                    entity.span(),
+                    "`entity` is only supported on the struct",
+                ));
+            }
+
+            let identifier = attributes
+                .rename
+                .unwrap_or_else(|| LitStr::new(&ident.to_string(), ident.span()));
+
+            let initializer = match (option_inner(ty), attributes.default) {
+                (Some(_), Some(_)) => {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`default` is not supported on `Option` fields, which are `None` when \
+                         the field is missing",
+                    ));
+                }
+                (Some(inner), None) => quote! {
+                    field_instances.get_optional_field::<#inner>(#identifier)?
+                },
+                (None, Some(Some(default))) => quote! {
+                    field_instances.get_field_or_else::<#ty>(#identifier, || #default)?
+                },
+                (None, Some(None)) => quote! {
+                    field_instances.get_field_or_else::<#ty>(
+                        #identifier,
+                        ::core::default::Default::default,
+                    )?
+                },
+                (None, None) => quote! {
+                    field_instances.get_field::<#ty>(#identifier)?
+                },
+            };
+
+            Ok(quote! { #ident: #initializer })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        impl #impl_generics ::shieldtank::component::ldtk_fields::LdtkFields
+            for #name #ty_generics #where_clause
+        {
+            const ENTITY_IDENTIFIER: ::core::option::Option<&'static str> = #entity_identifier;
+
+            fn from_field_instances(
+                field_instances: &::shieldtank::component::field_instances::ShieldtankFieldInstances,
+            ) -> ::core::result::Result<Self, ::shieldtank::error::LdtkFieldError> {
+                ::core::result::Result::Ok(Self {
+                    #(#field_initializers,)*
+                })
+            }
+        }
+    })
+}
+
+fn ldtk_enum(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.span(),
+            "LdtkEnum can only be derived for enums",
+        ));
+    };
+
+    let match_arms = data
+        .variants
+        .iter()
+        .map(|variant| -> syn::Result<TokenStream2> {
+            if !matches!(variant.fields, Fields::Unit) {
+                return Err(syn::Error::new(
+                    variant.span(),
+                    "LdtkEnum only supports unit variants",
+                ));
+            }
+
+            let ident = &variant.ident;
+            let attributes = LdtkAttributes::parse(&variant.attrs)?;
+            let value = attributes
+                .rename
+                .unwrap_or_else(|| LitStr::new(&ident.to_string(), ident.span()));
+
+            Ok(quote! { #value => ::core::option::Option::Some(Self::#ident) })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let expected = LitStr::new(&format!("{name} enum value"), name.span());
+
+    Ok(quote! {
+        impl #impl_generics ::shieldtank::component::ldtk_fields::FromLdtkField
+            for #name #ty_generics #where_clause
+        {
+            fn expected() -> ::std::borrow::Cow<'static, str> {
+                ::std::borrow::Cow::Borrowed(#expected)
+            }
+
+            fn from_ldtk_field(
+                field: &::shieldtank::bevy_ldtk_asset::field_instance::FieldInstance,
+            ) -> ::core::option::Option<Self> {
+                match ::shieldtank::component::ldtk_fields::enum_value(field)? {
+                    #(#match_arms,)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+
+            fn from_ldtk_array(
+                field: &::shieldtank::bevy_ldtk_asset::field_instance::FieldInstance,
+            ) -> ::core::option::Option<::std::vec::Vec<Self>> {
+                ::shieldtank::component::ldtk_fields::enum_values(field)?
+                    .map(|value| match value {
+                        #(#match_arms,)*
+                        _ => ::core::option::Option::None,
+                    })
+                    .collect()
+            }
+        }
+    })
+}
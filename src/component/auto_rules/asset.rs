@@ -0,0 +1,499 @@
+//! Reads the auto-layer rules of a project from its `.ldtk` file, since `bevy_ldtk_asset` doesn't
+//! expose them: its layer definitions have no rules, its layer instances no seeds, and its
+//! tileset definitions no spacing. Only those fields are read here, and only from projects of the
+//! JSON version `bevy_ldtk_asset` loads, so a schema change fails loudly instead of drifting.
+
+use bevy_asset::io::Reader;
+use bevy_asset::{Asset, AssetLoader, LoadContext};
+use bevy_math::{I64Vec2, UVec2, Vec2};
+use bevy_platform::collections::HashMap;
+use bevy_reflect::TypePath;
+use serde::Deserialize;
+
+use crate::error::ShieldtankError;
+use crate::shieldtank_error;
+
+use super::rule::{AutoRule, AutoRuleChecker, AutoRuleGroup, AutoRuleTile};
+
+/// The auto-layer rules of one layer definition.
+#[derive(Clone, Debug, Default)]
+pub struct LdtkLayerAutoRules {
+    pub groups: Vec<AutoRuleGroup>,
+    /// The identifier of the IntGrid layer the rules read, when it isn't the layer itself.
+    pub source_layer: Option<String>,
+}
+
+#[derive(Debug, Default, Asset, TypePath)]
+pub struct LdtkAutoRules {
+    /// Keyed by layer definition identifier.
+    pub layers: HashMap<String, LdtkLayerAutoRules>,
+    /// The seed of each layer instance, keyed by level and layer identifier.
+    pub seeds: HashMap<(String, String), i64>,
+}
+
+impl LdtkAutoRules {
+    fn new(defs: &DefsJson) -> Self {
+        let tilesets: HashMap<i64, &TilesetDefJson> = defs
+            .tilesets
+            .iter()
+            .map(|tileset| (tileset.uid, tileset))
+            .collect();
+
+        let identifiers: HashMap<i64, &str> = defs
+            .layers
+            .iter()
+            .map(|layer| (layer.uid, layer.identifier.as_str()))
+            .collect();
+
+        let layers = defs
+            .layers
+            .iter()
+            .filter(|layer| !layer.auto_rule_groups.is_empty())
+            .filter_map(|layer| {
+                let tileset_uid = layer.tileset_def_uid.or(layer.auto_tileset_def_uid)?;
+                let tileset = tilesets.get(&tileset_uid)?;
+
+                let groups = layer
+                    .auto_rule_groups
+                    .iter()
+                    .map(|group| AutoRuleGroup {
+                        active: group.active,
+                        rules: group
+                            .rules
+                            .iter()
+                            .map(|rule| rule.to_auto_rule(tileset))
+                            .collect(),
+                    })
+                    .collect();
+
+                let source_layer = layer
+                    .auto_source_layer_def_uid
+                    .filter(|uid| *uid != layer.uid)
+                    .and_then(|uid| identifiers.get(&uid))
+                    .map(|identifier| identifier.to_string());
+
+                let rules = LdtkLayerAutoRules {
+                    groups,
+                    source_layer,
+                };
+
+                Some((layer.identifier.clone(), rules))
+            })
+            .collect();
+
+        Self {
+            layers,
+            seeds: HashMap::new(),
+        }
+    }
+
+    fn add_seeds(&mut self, level: &LevelJson) {
+        level
+            .layer_instances
+            .iter()
+            .flatten()
+            .for_each(|layer_instance| {
+                let key = (level.identifier.clone(), layer_instance.identifier.clone());
+                self.seeds.insert(key, layer_instance.seed);
+            });
+    }
+
+    pub(crate) fn seed(&self, level: &str, layer: &str) -> i64 {
+        self.seeds
+            .get(&(level.to_string(), layer.to_string()))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Loads [LdtkAutoRules] from `.ldtk` files. It has no extensions, so only loads requesting
+/// [LdtkAutoRules] use it, and `bevy_ldtk_asset` keeps loading the projects themselves.
+#[derive(Default, TypePath)]
+pub struct LdtkAutoRulesLoader;
+
+impl AssetLoader for LdtkAutoRulesLoader {
+    type Asset = LdtkAutoRules;
+    type Settings = ();
+    type Error = ShieldtankError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes).await?;
+
+        let project: ProjectJson = serde_json::from_slice(&bytes)?;
+        project.check_version()?;
+
+        let mut auto_rules = LdtkAutoRules::new(&project.defs);
+
+        let levels = project
+            .levels
+            .iter()
+            .chain(project.worlds.iter().flat_map(|world| world.levels.iter()));
+
+        for level in levels {
+            match (&level.layer_instances, &level.external_rel_path) {
+                (Some(_), _) => auto_rules.add_seeds(level),
+                (None, Some(external_rel_path)) => {
+                    let path = load_context
+                        .path()
+                        .resolve_embed(external_rel_path)
+                        .map_err(|e| shieldtank_error!("bad external level path: {e}"))?;
+
+                    let bytes = load_context.read_asset_bytes(path).await?;
+                    let level: LevelJson = serde_json::from_slice(&bytes)?;
+
+                    auto_rules.add_seeds(&level);
+                }
+                (None, None) => {}
+            }
+        }
+
+        Ok(auto_rules)
+    }
+}
+
+/// The LDtk JSON version `bevy_ldtk_asset` loads.
+const SUPPORTED_VERSION: &str = "1.5.3";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectJson {
+    json_version: String,
+    defs: DefsJson,
+    #[serde(default)]
+    levels: Vec<LevelJson>,
+    #[serde(default)]
+    worlds: Vec<WorldJson>,
+}
+
+impl ProjectJson {
+    fn check_version(&self) -> Result<(), ShieldtankError> {
+        match self.json_version == SUPPORTED_VERSION {
+            true => Ok(()),
+            false => Err(shieldtank_error!(
+                "auto-layer rules are read from LDtk {SUPPORTED_VERSION} projects, not {}",
+                self.json_version
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct WorldJson {
+    #[serde(default)]
+    levels: Vec<LevelJson>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LevelJson {
+    identifier: String,
+    external_rel_path: Option<String>,
+    layer_instances: Option<Vec<LayerInstanceJson>>,
+}
+
+#[derive(Deserialize)]
+struct LayerInstanceJson {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(default)]
+    seed: i64,
+}
+
+#[derive(Deserialize)]
+struct DefsJson {
+    #[serde(default)]
+    layers: Vec<LayerDefJson>,
+    #[serde(default)]
+    tilesets: Vec<TilesetDefJson>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LayerDefJson {
+    identifier: String,
+    uid: i64,
+    #[serde(default)]
+    auto_rule_groups: Vec<RuleGroupJson>,
+    auto_source_layer_def_uid: Option<i64>,
+    tileset_def_uid: Option<i64>,
+    auto_tileset_def_uid: Option<i64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TilesetDefJson {
+    uid: i64,
+    #[serde(rename = "__cWid")]
+    c_wid: i64,
+    tile_grid_size: i64,
+    #[serde(default)]
+    spacing: i64,
+    #[serde(default)]
+    padding: i64,
+}
+
+impl TilesetDefJson {
+    fn tile_column_row(&self, tile_id: i64) -> I64Vec2 {
+        let c_wid = self.c_wid.max(1);
+        I64Vec2::new(tile_id % c_wid, tile_id / c_wid)
+    }
+
+    fn tile_source(&self, tile_id: i64) -> UVec2 {
+        let step = self.tile_grid_size + self.spacing;
+        (I64Vec2::splat(self.padding) + self.tile_column_row(tile_id) * step).as_uvec2()
+    }
+
+    /// The tiles of a tile rectangle, as a stamp.
+    fn stamp(&self, tile_ids: &[i64]) -> Vec<AutoRuleTile> {
+        let corner = tile_ids
+            .iter()
+            .map(|tile_id| self.tile_column_row(*tile_id))
+            .reduce(I64Vec2::min)
+            .unwrap_or_default();
+
+        tile_ids
+            .iter()
+            .map(|tile_id| AutoRuleTile {
+                cell: self.tile_column_row(*tile_id) - corner,
+                source: self.tile_source(*tile_id),
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct RuleGroupJson {
+    active: bool,
+    #[serde(default)]
+    rules: Vec<RuleJson>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RuleJson {
+    uid: i64,
+    active: bool,
+    size: i64,
+    pattern: Vec<i64>,
+    #[serde(default)]
+    tile_rects_ids: Vec<Vec<i64>>,
+    /// Replaced by `tileRectsIds` in newer projects.
+    #[serde(default)]
+    tile_ids: Vec<i64>,
+    #[serde(default)]
+    tile_mode: String,
+    #[serde(default)]
+    pivot_x: f32,
+    #[serde(default)]
+    pivot_y: f32,
+    #[serde(default)]
+    tile_x_offset: i64,
+    #[serde(default)]
+    tile_y_offset: i64,
+    chance: f32,
+    #[serde(default = "default_alpha")]
+    alpha: f32,
+    x_modulo: i64,
+    y_modulo: i64,
+    #[serde(default)]
+    x_offset: i64,
+    #[serde(default)]
+    y_offset: i64,
+    #[serde(default)]
+    checker: String,
+    flip_x: bool,
+    flip_y: bool,
+    break_on_match: bool,
+    out_of_bounds_value: Option<i64>,
+}
+
+fn default_alpha() -> f32 {
+    1.0
+}
+
+impl RuleJson {
+    fn to_auto_rule(&self, tileset: &TilesetDefJson) -> AutoRule {
+        let tile_rects: Vec<Vec<i64>> = match self.tile_rects_ids.is_empty() {
+            true => self.tile_ids.iter().map(|tile_id| vec![*tile_id]).collect(),
+            false => self.tile_rects_ids.clone(),
+        };
+
+        let tiles = match self.tile_mode.as_str() {
+            "Stamp" => tile_rects
+                .iter()
+                .map(|tile_ids| tileset.stamp(tile_ids))
+                .collect(),
+            _ => tile_rects
+                .iter()
+                .flatten()
+                .map(|tile_id| vec![AutoRuleTile::new(tileset.tile_source(*tile_id))])
+                .collect(),
+        };
+
+        let checker = match self.checker.as_str() {
+            "Horizontal" => AutoRuleChecker::Horizontal,
+            "Vertical" => AutoRuleChecker::Vertical,
+            _ => AutoRuleChecker::None,
+        };
+
+        AutoRule {
+            uid: self.uid,
+            active: self.active,
+            size: self.size,
+            pattern: self.pattern.clone(),
+            tiles,
+            pivot: Vec2::new(self.pivot_x, self.pivot_y),
+            tile_offset: I64Vec2::new(self.tile_x_offset, self.tile_y_offset),
+            chance: self.chance,
+            opacity: self.alpha,
+            x_modulo: self.x_modulo,
+            y_modulo: self.y_modulo,
+            x_offset: self.x_offset,
+            y_offset: self.y_offset,
+            checker,
+            flip_x: self.flip_x,
+            flip_y: self.flip_y,
+            break_on_match: self.break_on_match,
+            out_of_bounds: self.out_of_bounds_value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECT: &str = r#"{
+        "jsonVersion": "1.5.3",
+        "defs": {
+            "layers": [
+                {
+                    "identifier": "Walls",
+                    "uid": 1,
+                    "autoRuleGroups": [],
+                    "autoSourceLayerDefUid": null,
+                    "tilesetDefUid": null,
+                    "autoTilesetDefUid": null
+                },
+                {
+                    "identifier": "Decor",
+                    "uid": 2,
+                    "autoRuleGroups": [{
+                        "active": true,
+                        "rules": [{
+                            "uid": 7,
+                            "active": true,
+                            "size": 1,
+                            "pattern": [1],
+                            "tileRectsIds": [[5, 6, 9, 10]],
+                            "tileMode": "Stamp",
+                            "pivotX": 0.5,
+                            "pivotY": 1,
+                            "chance": 0.5,
+                            "xModulo": 2,
+                            "yModulo": 1,
+                            "checker": "Vertical",
+                            "flipX": true,
+                            "flipY": false,
+                            "breakOnMatch": true,
+                            "outOfBoundsValue": null
+                        }]
+                    }],
+                    "autoSourceLayerDefUid": 1,
+                    "tilesetDefUid": null,
+                    "autoTilesetDefUid": 3
+                }
+            ],
+            "tilesets": [
+                { "uid": 3, "__cWid": 4, "tileGridSize": 8, "spacing": 1, "padding": 2 }
+            ]
+        },
+        "levels": [{
+            "identifier": "Level_0",
+            "externalRelPath": null,
+            "layerInstances": [
+                { "__identifier": "Decor", "seed": 1234 },
+                { "__identifier": "Walls", "seed": 42 }
+            ]
+        }]
+    }"#;
+
+    fn load() -> LdtkAutoRules {
+        let project: ProjectJson = serde_json::from_str(PROJECT).unwrap();
+        project.check_version().unwrap();
+
+        let mut auto_rules = LdtkAutoRules::new(&project.defs);
+        project
+            .levels
+            .iter()
+            .for_each(|level| auto_rules.add_seeds(level));
+
+        auto_rules
+    }
+
+    #[test]
+    fn reads_rules_of_layers_with_a_tileset() {
+        let auto_rules = load();
+
+        assert!(!auto_rules.layers.contains_key("Walls"));
+
+        let decor = &auto_rules.layers["Decor"];
+        assert_eq!(decor.source_layer.as_deref(), Some("Walls"));
+        assert_eq!(decor.groups.len(), 1);
+
+        let rule = &decor.groups[0].rules[0];
+        assert_eq!(rule.uid, 7);
+        assert_eq!(rule.pattern, vec![1]);
+        assert_eq!(rule.pivot, Vec2::new(0.5, 1.0));
+        assert_eq!((rule.x_modulo, rule.y_modulo), (2, 1));
+        assert_eq!(rule.checker, AutoRuleChecker::Vertical);
+        assert!(rule.flip_x && !rule.flip_y && rule.break_on_match);
+        assert_eq!(rule.opacity, 1.0);
+    }
+
+    #[test]
+    fn stamps_keep_the_layout_of_their_tile_rectangle() {
+        let auto_rules = load();
+        let rule = &auto_rules.layers["Decor"].groups[0].rules[0];
+
+        assert_eq!(rule.tiles.len(), 1);
+
+        let cells: Vec<(I64Vec2, UVec2)> = rule.tiles[0]
+            .iter()
+            .map(|tile| (tile.cell, tile.source))
+            .collect();
+
+        assert_eq!(
+            cells,
+            vec![
+                (I64Vec2::new(0, 0), UVec2::new(11, 11)),
+                (I64Vec2::new(1, 0), UVec2::new(20, 11)),
+                (I64Vec2::new(0, 1), UVec2::new(11, 20)),
+                (I64Vec2::new(1, 1), UVec2::new(20, 20)),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_seeds_of_layer_instances() {
+        let auto_rules = load();
+
+        assert_eq!(auto_rules.seed("Level_0", "Decor"), 1234);
+        assert_eq!(auto_rules.seed("Level_0", "Walls"), 42);
+        assert_eq!(auto_rules.seed("Level_1", "Decor"), 0);
+    }
+
+    #[test]
+    fn rejects_other_json_versions() {
+        let project = PROJECT.replace("1.5.3", "1.5.4");
+        let project: ProjectJson = serde_json::from_str(&project).unwrap();
+
+        assert!(project.check_version().is_err());
+    }
+}
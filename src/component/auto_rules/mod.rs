@@ -0,0 +1,264 @@
+//! Runtime evaluation of LDtk auto-layer rules.
+//!
+//! The rules of each layer are read from its project's `.ldtk` file into [LdtkAutoRules], or
+//! replaced with [ShieldtankAutoRules]. When grid values are edited, the tiles of [LdtkLayerTiles]
+//! are regenerated around the edited cells, keeping the tiles LDtk baked everywhere else.
+//!
+//! Perlin noise, random tile offsets and IntGrid value groups in patterns aren't evaluated.
+
+use bevy_app::Plugin;
+use bevy_asset::{AsAssetId, AssetApp, AssetEvent, AssetId, AssetServer, Assets, Handle};
+use bevy_ecs::change_detection::DetectChanges;
+use bevy_ecs::component::Component;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::hierarchy::{ChildOf, Children};
+use bevy_ecs::message::MessageReader;
+use bevy_ecs::query::Changed;
+use bevy_ecs::schedule::IntoScheduleConfigs;
+use bevy_ecs::system::{Commands, Query, Res};
+use bevy_ecs::world::Ref;
+use bevy_ldtk_asset::layer::LayerInstance;
+use bevy_ldtk_asset::level::Level as LevelAsset;
+use bevy_ldtk_asset::prelude::LdtkAsset;
+use bevy_math::I64Vec2;
+use bevy_platform::collections::{HashMap, HashSet};
+use bevy_reflect::Reflect;
+
+use asset::{LdtkAutoRules, LdtkAutoRulesLoader};
+use rule::{AutoRuleChecker, AutoRuleGroup, AutoRuleSet, AutoRuleTile};
+
+use super::grid_edits::{GridValueChanged, ShieldtankGridEdits};
+use super::grid_values::{ShieldtankGridValues, grid_values_system};
+use super::layer::ShieldtankLayer;
+use super::layer_definition::ShieldtankLayerDefinition;
+use super::layer_tiles::{LdtkLayerTiles, ShieldtankLayerTile, layer_tile_system};
+use super::level::ShieldtankLevel;
+use super::shieldtank_component::ShieldtankComponentSystemSet;
+
+pub mod asset;
+pub mod rule;
+
+/// Replaces the auto-layer rules a layer has in its project. They're evaluated against the
+/// layer's own grid values, and regenerate all of its tiles when added or changed.
+///
+/// Groups and rules are in LDtk's order, where earlier rules are drawn on top of later ones.
+#[derive(Clone, Debug, Default, Component, Reflect)]
+pub struct ShieldtankAutoRules {
+    pub groups: Vec<AutoRuleGroup>,
+    pub seed: i64,
+}
+
+impl ShieldtankAutoRules {
+    pub fn with_group(mut self, group: AutoRuleGroup) -> Self {
+        self.groups.push(group);
+        self
+    }
+
+    pub fn with_seed(mut self, seed: i64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    fn set(&self) -> AutoRuleSet<'_> {
+        AutoRuleSet {
+            groups: &self.groups,
+            seed: self.seed,
+        }
+    }
+
+    /// The tiles of a single cell, in drawing order.
+    pub fn evaluate(
+        &self,
+        grid_values: &ShieldtankGridValues,
+        grid_cell_size: u32,
+        grid: I64Vec2,
+    ) -> Vec<ShieldtankLayerTile> {
+        self.set().evaluate(grid_values, grid_cell_size, grid)
+    }
+
+    /// The tiles of every cell of the layer, in drawing order.
+    pub fn generate(
+        &self,
+        grid_values: &ShieldtankGridValues,
+        grid_cell_size: u32,
+    ) -> Vec<ShieldtankLayerTile> {
+        self.set().generate(grid_values, grid_cell_size)
+    }
+}
+
+/// The rules of the project a layer's definition comes from.
+#[derive(Component)]
+pub(crate) struct LdtkAutoRulesHandle(Handle<LdtkAutoRules>);
+
+fn auto_rules_load_system(
+    query: Query<(Entity, &ShieldtankLayerDefinition), Changed<ShieldtankLayerDefinition>>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    query.iter().for_each(|(entity, layer_definition)| {
+        let Some(path) = asset_server.get_path(layer_definition.as_asset_id()) else {
+            return;
+        };
+
+        let handle = asset_server.load::<LdtkAutoRules>(path.without_label().into_owned());
+
+        commands.entity(entity).insert(LdtkAutoRulesHandle(handle));
+    });
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn auto_rules_system(
+    mut query: Query<(
+        Entity,
+        &ShieldtankLayer,
+        &mut LdtkLayerTiles,
+        Option<Ref<ShieldtankAutoRules>>,
+        Option<&LdtkAutoRulesHandle>,
+        Option<&ChildOf>,
+    )>,
+    layer_query: Query<&ShieldtankLayer>,
+    source_query: Query<(Ref<ShieldtankGridValues>, &ShieldtankGridEdits)>,
+    level_query: Query<&ShieldtankLevel>,
+    children_query: Query<&Children>,
+    layer_assets: Res<Assets<LayerInstance>>,
+    level_assets: Res<Assets<LevelAsset>>,
+    auto_rules_assets: Res<Assets<LdtkAutoRules>>,
+    mut changed: MessageReader<GridValueChanged>,
+    mut asset_events: MessageReader<AssetEvent<LdtkAutoRules>>,
+) {
+    let mut edited: HashMap<Entity, HashSet<I64Vec2>> = HashMap::new();
+    changed.read().for_each(|message| {
+        edited
+            .entry(message.layer)
+            .or_default()
+            .insert(message.grid);
+    });
+
+    let reloaded: HashSet<AssetId<LdtkAutoRules>> = asset_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+
+    let identifier_of = |layer: Entity| {
+        let component = layer_query.get(layer).ok()?;
+        let asset = layer_assets.get(component.as_asset_id())?;
+        Some(asset.identifier.as_str())
+    };
+
+    query.iter_mut().for_each(
+        |(entity, component, mut layer_tiles, override_rules, handle, child_of)| {
+            let Some(layer_asset) = layer_assets.get(component.as_asset_id()) else {
+                return;
+            };
+
+            let (rule_set, source, regenerate_all, rules_reloaded) = match &override_rules {
+                Some(override_rules) => (
+                    override_rules.set(),
+                    entity,
+                    override_rules.is_changed(),
+                    false,
+                ),
+                None => {
+                    let Some(handle) = handle else {
+                        return;
+                    };
+
+                    let Some(auto_rules) = auto_rules_assets.get(&handle.0) else {
+                        return;
+                    };
+
+                    let Some(layer_rules) = auto_rules.layers.get(&layer_asset.identifier) else {
+                        return;
+                    };
+
+                    let level_identifier = child_of
+                        .and_then(|child_of| level_query.get(child_of.parent()).ok())
+                        .and_then(|level| level_assets.get(level.as_asset_id()))
+                        .map(|level| level.get_identifier())
+                        .unwrap_or_default();
+
+                    let seed = auto_rules.seed(level_identifier, &layer_asset.identifier);
+
+                    let source = match &layer_rules.source_layer {
+                        Some(source_layer) => {
+                            let sibling = child_of
+                                .and_then(|child_of| children_query.get(child_of.parent()).ok())
+                                .and_then(|children| {
+                                    children.iter().copied().find(|sibling| {
+                                        identifier_of(*sibling) == Some(source_layer.as_str())
+                                    })
+                                });
+
+                            let Some(sibling) = sibling else {
+                                return;
+                            };
+
+                            sibling
+                        }
+                        None => entity,
+                    };
+
+                    let rule_set = AutoRuleSet {
+                        groups: &layer_rules.groups,
+                        seed,
+                    };
+
+                    (rule_set, source, false, reloaded.contains(&handle.0.id()))
+                }
+            };
+
+            let Ok((grid_values, edits)) = source_query.get(source) else {
+                return;
+            };
+
+            let grid_cell_size = layer_tiles.grid_cell_size;
+
+            if regenerate_all {
+                layer_tiles.tiles = rule_set.generate(&grid_values, grid_cell_size);
+                return;
+            }
+
+            let mut cells = edited.get(&source).cloned().unwrap_or_default();
+
+            // Tiles replaced from the asset, reloaded rules and grid values rebuilt without
+            // messages all need every edit redone.
+            if layer_tiles.is_changed()
+                || rules_reloaded
+                || (grid_values.is_changed() && !edited.contains_key(&source))
+            {
+                cells.extend(edits.iter().map(|(grid, _)| grid));
+            }
+
+            if cells.is_empty() {
+                return;
+            }
+
+            rule_set.regenerate(&grid_values, grid_cell_size, &mut layer_tiles.tiles, &cells);
+        },
+    );
+}
+
+pub struct AutoRulesPlugin;
+impl Plugin for AutoRulesPlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.init_asset::<LdtkAutoRules>();
+        app.register_asset_loader(LdtkAutoRulesLoader);
+        app.register_type::<AutoRuleChecker>();
+        app.register_type::<AutoRuleTile>();
+        app.register_type::<rule::AutoRule>();
+        app.register_type::<AutoRuleGroup>();
+        app.register_type::<ShieldtankAutoRules>();
+        app.add_systems(
+            ShieldtankComponentSystemSet,
+            (
+                auto_rules_load_system,
+                auto_rules_system
+                    .after(grid_values_system)
+                    .before(layer_tile_system),
+            ),
+        );
+    }
+}
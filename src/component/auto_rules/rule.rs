@@ -0,0 +1,707 @@
+use std::cmp::Reverse;
+
+use bevy_math::{I64Vec2, UVec2, Vec2};
+use bevy_platform::collections::HashSet;
+use bevy_reflect::Reflect;
+
+use crate::component::grid_values::ShieldtankGridValues;
+use crate::component::layer_tiles::ShieldtankLayerTile;
+
+/// A pattern entry matching any non-empty cell. Negated, it matches empty cells.
+pub const ANY_VALUE: i64 = 1000001;
+
+/// Staggers the modulo of a rule on alternating rows or columns.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum AutoRuleChecker {
+    #[default]
+    None,
+    Horizontal,
+    Vertical,
+}
+
+/// A tile drawn by a rule, at a cell relative to the top left of its stamp.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub struct AutoRuleTile {
+    pub cell: I64Vec2,
+    /// The top left pixel of the tile in the tileset.
+    pub source: UVec2,
+}
+
+impl AutoRuleTile {
+    pub fn new(source: UVec2) -> Self {
+        Self {
+            cell: I64Vec2::ZERO,
+            source,
+        }
+    }
+}
+
+/// A single auto-layer rule, following the fields of LDtk's rule definitions.
+#[derive(Clone, Debug, PartialEq, Reflect)]
+pub struct AutoRule {
+    /// Added to the layer's seed for the random chance and tile picks of this rule.
+    pub uid: i64,
+    pub active: bool,
+    /// The odd width of the square pattern.
+    pub size: i64,
+    /// Row major, `size * size` entries. `0` matches anything, a value `v` requires the cell to
+    /// hold `v` and `-v` requires it not to. See also [ANY_VALUE].
+    pub pattern: Vec<i64>,
+    /// One of these is picked per match. Choices of several tiles are drawn as a stamp.
+    pub tiles: Vec<Vec<AutoRuleTile>>,
+    /// Where stamps are anchored on the matched cell, from `(0, 0)` at their top left to `(1, 1)`
+    /// at their bottom right.
+    pub pivot: Vec2,
+    /// In pixels.
+    pub tile_offset: I64Vec2,
+    /// From `0.0` to `1.0`.
+    pub chance: f32,
+    pub opacity: f32,
+    pub x_modulo: i64,
+    pub y_modulo: i64,
+    pub x_offset: i64,
+    pub y_offset: i64,
+    pub checker: AutoRuleChecker,
+    /// Also try the pattern mirrored horizontally, flipping the tiles when that matches.
+    pub flip_x: bool,
+    /// Also try the pattern mirrored vertically, flipping the tiles when that matches.
+    pub flip_y: bool,
+    /// Skips all following rules for a cell matched by this rule.
+    pub break_on_match: bool,
+    /// The value of cells outside of the layer. Patterns reaching outside of the layer never
+    /// match when `None`.
+    pub out_of_bounds: Option<i64>,
+}
+
+impl Default for AutoRule {
+    fn default() -> Self {
+        Self {
+            uid: 0,
+            active: true,
+            size: 1,
+            pattern: vec![0],
+            tiles: vec![],
+            pivot: Vec2::ZERO,
+            tile_offset: I64Vec2::ZERO,
+            chance: 1.0,
+            opacity: 1.0,
+            x_modulo: 1,
+            y_modulo: 1,
+            x_offset: 0,
+            y_offset: 0,
+            checker: AutoRuleChecker::None,
+            flip_x: false,
+            flip_y: false,
+            break_on_match: true,
+            out_of_bounds: None,
+        }
+    }
+}
+
+impl AutoRule {
+    pub fn is_valid(&self) -> bool {
+        self.size > 0
+            && self.size % 2 == 1
+            && self.pattern.len() as i64 == self.size * self.size
+            && !self.tiles.is_empty()
+            && self.tiles.iter().all(|choice| !choice.is_empty())
+            && self.x_modulo > 0
+            && self.y_modulo > 0
+    }
+
+    fn radius(&self) -> i64 {
+        self.size / 2
+    }
+
+    /// How many cells away from the matched cell the drawn tiles can land.
+    fn reach(&self, grid_cell_size: u32) -> i64 {
+        let stamp = self
+            .tiles
+            .iter()
+            .flatten()
+            .map(|tile| tile.cell.max_element())
+            .max()
+            .unwrap_or(0);
+
+        let grid_cell_size = (grid_cell_size as i64).max(1);
+        let offset = (self.tile_offset.abs().max_element() + grid_cell_size - 1) / grid_cell_size;
+
+        stamp + offset
+    }
+
+    fn cell_value(&self, grid_values: &ShieldtankGridValues, grid: I64Vec2) -> Option<Option<i64>> {
+        let size = grid_values.size();
+
+        if grid.cmplt(I64Vec2::ZERO).any() || grid.cmpge(size).any() {
+            return self
+                .out_of_bounds
+                .map(|value| (value != 0).then_some(value));
+        }
+
+        Some(grid_values.get(grid).map(|grid_value| grid_value.value))
+    }
+
+    pub(crate) fn matches(
+        &self,
+        grid_values: &ShieldtankGridValues,
+        grid: I64Vec2,
+        flip_x: bool,
+        flip_y: bool,
+    ) -> bool {
+        let radius = self.radius();
+
+        self.pattern
+            .iter()
+            .enumerate()
+            .filter(|(_, constraint)| **constraint != 0)
+            .all(|(index, constraint)| {
+                let index = index as i64;
+                let mut offset = I64Vec2::new(index % self.size, index / self.size) - radius;
+
+                if flip_x {
+                    offset.x = -offset.x;
+                }
+
+                if flip_y {
+                    offset.y = -offset.y;
+                }
+
+                let Some(value) = self.cell_value(grid_values, grid + offset) else {
+                    return false;
+                };
+
+                match *constraint {
+                    ANY_VALUE => value.is_some(),
+                    constraint if constraint == -ANY_VALUE => value.is_none(),
+                    constraint if constraint > 0 => value == Some(constraint),
+                    constraint => value != Some(-constraint),
+                }
+            })
+    }
+
+    fn matches_modulo(&self, grid: I64Vec2) -> bool {
+        let mut shifted = grid - I64Vec2::new(self.x_offset, self.y_offset);
+
+        match self.checker {
+            AutoRuleChecker::None => {}
+            AutoRuleChecker::Horizontal => {
+                shifted.x += (grid.y / self.y_modulo).rem_euclid(2) * (self.x_modulo / 2);
+            }
+            AutoRuleChecker::Vertical => {
+                shifted.y += (grid.x / self.x_modulo).rem_euclid(2) * (self.y_modulo / 2);
+            }
+        }
+
+        shifted.x.rem_euclid(self.x_modulo) == 0 && shifted.y.rem_euclid(self.y_modulo) == 0
+    }
+
+    /// The tiles this rule draws for a cell, if it matches.
+    pub(crate) fn apply(
+        &self,
+        grid_values: &ShieldtankGridValues,
+        grid_cell_size: u32,
+        seed: i64,
+        grid: I64Vec2,
+    ) -> Option<Vec<ShieldtankLayerTile>> {
+        if !self.matches_modulo(grid) {
+            return None;
+        }
+
+        let seed = seed.wrapping_add(self.uid);
+
+        if self.chance <= 0.0
+            || (self.chance < 1.0
+                && rand_seed_coords(seed, grid, 100) as f32 >= self.chance * 100.0)
+        {
+            return None;
+        }
+
+        let (flip_x, flip_y) = [(false, false), (true, false), (false, true), (true, true)]
+            .into_iter()
+            .filter(|(flip_x, flip_y)| (!flip_x || self.flip_x) && (!flip_y || self.flip_y))
+            .find(|(flip_x, flip_y)| self.matches(grid_values, grid, *flip_x, *flip_y))?;
+
+        let pick = rand_seed_coords(seed, grid, self.tiles.len() as i64)
+            .rem_euclid(self.tiles.len() as i64);
+        let choice = &self.tiles[pick as usize];
+
+        let stamp_size = choice
+            .iter()
+            .fold(I64Vec2::ONE, |size, tile| size.max(tile.cell + 1));
+        let anchor = ((stamp_size - 1).as_vec2() * self.pivot)
+            .round()
+            .as_i64vec2();
+
+        let tiles = choice
+            .iter()
+            .map(|tile| {
+                let mut cell = tile.cell;
+
+                if flip_x {
+                    cell.x = stamp_size.x - 1 - cell.x;
+                }
+
+                if flip_y {
+                    cell.y = stamp_size.y - 1 - cell.y;
+                }
+
+                ShieldtankLayerTile {
+                    opacity: self.opacity,
+                    flip_x,
+                    flip_y,
+                    offset: (grid + cell - anchor) * grid_cell_size as i64 + self.tile_offset,
+                    source: tile.source,
+                    size: UVec2::splat(grid_cell_size),
+                }
+            })
+            .collect();
+
+        Some(tiles)
+    }
+}
+
+/// Hashes a cell into `0..max` the way LDtk's `randSeedCoords` does in the editor, including the
+/// float rounding of its JavaScript build.
+fn rand_seed_coords(seed: i64, grid: I64Vec2, max: i64) -> i64 {
+    let to_int32 = |value: f64| value as i128 as u32 as i32;
+
+    let hash = seed as f64 + grid.x as f64 * 374761393.0 + grid.y as f64 * 668265263.0;
+    let hash = to_int32(hash);
+    let hash = (hash ^ (hash >> 13)) as f64 * 1274126177.0;
+    let hash = to_int32(hash);
+    let hash = hash ^ (hash >> 16);
+
+    hash as i64 % max.max(1)
+}
+
+#[derive(Clone, Debug, PartialEq, Reflect)]
+pub struct AutoRuleGroup {
+    pub active: bool,
+    pub rules: Vec<AutoRule>,
+}
+
+impl Default for AutoRuleGroup {
+    fn default() -> Self {
+        Self {
+            active: true,
+            rules: vec![],
+        }
+    }
+}
+
+/// Rule groups paired with the seed of the layer they're evaluated for.
+#[derive(Clone, Copy)]
+pub(crate) struct AutoRuleSet<'a> {
+    pub(crate) groups: &'a [AutoRuleGroup],
+    pub(crate) seed: i64,
+}
+
+impl AutoRuleSet<'_> {
+    fn rules(&self) -> impl Iterator<Item = &AutoRule> {
+        self.groups
+            .iter()
+            .filter(|group| group.active)
+            .flat_map(|group| group.rules.iter())
+            .filter(|rule| rule.active && rule.is_valid())
+    }
+
+    /// The tiles of a cell, each with the priority of its rule. Lower priorities are drawn on top.
+    fn evaluate_cell(
+        &self,
+        grid_values: &ShieldtankGridValues,
+        grid_cell_size: u32,
+        grid: I64Vec2,
+    ) -> Vec<(usize, ShieldtankLayerTile)> {
+        let mut tiles = vec![];
+
+        for (priority, rule) in self.rules().enumerate() {
+            if let Some(rule_tiles) = rule.apply(grid_values, grid_cell_size, self.seed, grid) {
+                tiles.extend(rule_tiles.into_iter().map(|tile| (priority, tile)));
+
+                if rule.break_on_match {
+                    break;
+                }
+            }
+        }
+
+        tiles
+    }
+
+    /// The tiles of a single cell, in drawing order.
+    pub(crate) fn evaluate(
+        &self,
+        grid_values: &ShieldtankGridValues,
+        grid_cell_size: u32,
+        grid: I64Vec2,
+    ) -> Vec<ShieldtankLayerTile> {
+        in_drawing_order(self.evaluate_cell(grid_values, grid_cell_size, grid))
+    }
+
+    /// The tiles of every cell of the layer, in drawing order.
+    pub(crate) fn generate(
+        &self,
+        grid_values: &ShieldtankGridValues,
+        grid_cell_size: u32,
+    ) -> Vec<ShieldtankLayerTile> {
+        let size = grid_values.size();
+
+        let tiles = (0..size.y)
+            .flat_map(|y| (0..size.x).map(move |x| I64Vec2::new(x, y)))
+            .flat_map(|grid| self.evaluate_cell(grid_values, grid_cell_size, grid))
+            .collect();
+
+        in_drawing_order(tiles)
+    }
+
+    /// Replaces the tiles of every cell whose rules can see one of the edited cells.
+    pub(crate) fn regenerate(
+        &self,
+        grid_values: &ShieldtankGridValues,
+        grid_cell_size: u32,
+        tiles: &mut Vec<ShieldtankLayerTile>,
+        edited: &HashSet<I64Vec2>,
+    ) {
+        let radius = self.rules().map(AutoRule::radius).max().unwrap_or(0);
+        let reach = self
+            .rules()
+            .map(|rule| rule.reach(grid_cell_size))
+            .max()
+            .unwrap_or(0);
+
+        let size = grid_values.size();
+        let contains = |grid: &I64Vec2| grid.cmpge(I64Vec2::ZERO).all() && grid.cmplt(size).all();
+
+        // Cells whose rules see an edit, where their tiles land, and every cell with tiles
+        // landing there.
+        let changed: HashSet<I64Vec2> = dilate(edited.iter().copied(), radius)
+            .into_iter()
+            .filter(contains)
+            .collect();
+        let replaced = dilate(changed.into_iter(), reach);
+        let mut sources: Vec<I64Vec2> = dilate(replaced.iter().copied(), reach)
+            .into_iter()
+            .filter(contains)
+            .collect();
+        sources.sort_by_key(|grid| (grid.y, grid.x));
+
+        let grid_cell = I64Vec2::splat((grid_cell_size as i64).max(1));
+        let lands_in_replaced =
+            |tile: &ShieldtankLayerTile| replaced.contains(&tile.offset.div_euclid(grid_cell));
+
+        tiles.retain(|tile| !lands_in_replaced(tile));
+
+        let regenerated = sources
+            .into_iter()
+            .flat_map(|grid| self.evaluate_cell(grid_values, grid_cell_size, grid))
+            .filter(|(_, tile)| lands_in_replaced(tile))
+            .collect();
+
+        tiles.extend(in_drawing_order(regenerated));
+    }
+}
+
+fn dilate(cells: impl Iterator<Item = I64Vec2>, by: i64) -> HashSet<I64Vec2> {
+    cells
+        .flat_map(|grid| {
+            (-by..=by).flat_map(move |y| (-by..=by).map(move |x| grid + I64Vec2::new(x, y)))
+        })
+        .collect()
+}
+
+fn in_drawing_order(mut tiles: Vec<(usize, ShieldtankLayerTile)>) -> Vec<ShieldtankLayerTile> {
+    tiles.sort_by_key(|(priority, _)| Reverse(*priority));
+    tiles.into_iter().map(|(_, tile)| tile).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMPTY: i64 = -ANY_VALUE;
+
+    fn rule(size: i64, pattern: &[i64], source: u32) -> AutoRule {
+        AutoRule {
+            size,
+            pattern: pattern.to_vec(),
+            tiles: vec![vec![AutoRuleTile::new(UVec2::new(source, 0))]],
+            ..Default::default()
+        }
+    }
+
+    fn sources(tiles: &[ShieldtankLayerTile]) -> Vec<u32> {
+        tiles.iter().map(|tile| tile.source.x).collect()
+    }
+
+    #[test]
+    fn matches_values_and_negated_values() {
+        let grid_values = ShieldtankGridValues::from_rows(&[&[1, 2, 0]]);
+
+        let requires_left = rule(3, &[0, 0, 0, 1, 0, 0, 0, 0, 0], 0);
+        assert!(requires_left.matches(&grid_values, I64Vec2::new(1, 0), false, false));
+        assert!(!requires_left.matches(&grid_values, I64Vec2::new(2, 0), false, false));
+
+        let forbids_right = rule(3, &[0, 0, 0, 0, 0, -2, 0, 0, 0], 0);
+        assert!(!forbids_right.matches(&grid_values, I64Vec2::new(0, 0), false, false));
+        assert!(forbids_right.matches(&grid_values, I64Vec2::new(1, 0), false, false));
+    }
+
+    #[test]
+    fn matches_any_value_and_empty() {
+        let grid_values = ShieldtankGridValues::from_rows(&[&[3, 0, 7]]);
+
+        let any = rule(1, &[ANY_VALUE], 0);
+        assert!(any.matches(&grid_values, I64Vec2::new(0, 0), false, false));
+        assert!(!any.matches(&grid_values, I64Vec2::new(1, 0), false, false));
+        assert!(any.matches(&grid_values, I64Vec2::new(2, 0), false, false));
+
+        let empty = rule(1, &[EMPTY], 0);
+        assert!(!empty.matches(&grid_values, I64Vec2::new(0, 0), false, false));
+        assert!(empty.matches(&grid_values, I64Vec2::new(1, 0), false, false));
+    }
+
+    #[test]
+    fn out_of_bounds_cells() {
+        let grid_values = ShieldtankGridValues::from_rows(&[&[1]]);
+        let requires_above = rule(3, &[0, 1, 0, 0, 0, 0, 0, 0, 0], 0);
+
+        assert!(!requires_above.matches(&grid_values, I64Vec2::ZERO, false, false));
+
+        let with_bounds = AutoRule {
+            out_of_bounds: Some(1),
+            ..requires_above
+        };
+        assert!(with_bounds.matches(&grid_values, I64Vec2::ZERO, false, false));
+    }
+
+    #[test]
+    fn modulo_and_offset() {
+        let grid_values = ShieldtankGridValues::from_rows(&[&[1; 6]]);
+        let every_third = AutoRule {
+            x_modulo: 3,
+            x_offset: 1,
+            ..rule(1, &[1], 0)
+        };
+
+        let matched: Vec<i64> = (0..6)
+            .filter(|x| {
+                every_third
+                    .apply(&grid_values, 16, 0, I64Vec2::new(*x, 0))
+                    .is_some()
+            })
+            .collect();
+
+        assert_eq!(matched, vec![1, 4]);
+    }
+
+    #[test]
+    fn checker_staggers_rows() {
+        let grid_values = ShieldtankGridValues::from_rows(&[&[1; 4], &[1; 4]]);
+        let checker = AutoRule {
+            x_modulo: 2,
+            checker: AutoRuleChecker::Horizontal,
+            ..rule(1, &[1], 0)
+        };
+
+        let matched = |y: i64| -> Vec<i64> {
+            (0..4)
+                .filter(|x| {
+                    checker
+                        .apply(&grid_values, 16, 0, I64Vec2::new(*x, y))
+                        .is_some()
+                })
+                .collect()
+        };
+
+        assert_eq!(matched(0), vec![0, 2]);
+        assert_eq!(matched(1), vec![1, 3]);
+    }
+
+    #[test]
+    fn flips_are_tried_in_order() {
+        // Solid to the right only matches unflipped, solid to the left only matches flipped.
+        let requires_right = AutoRule {
+            flip_x: true,
+            flip_y: true,
+            ..rule(3, &[0, 0, 0, 0, 0, 1, 0, 0, 0], 0)
+        };
+
+        let right = ShieldtankGridValues::from_rows(&[&[0, 0, 0], &[0, 0, 1], &[0, 0, 0]]);
+        let tile = &requires_right.apply(&right, 16, 0, I64Vec2::ONE).unwrap()[0];
+        assert!(!tile.flip_x && !tile.flip_y);
+
+        let left = ShieldtankGridValues::from_rows(&[&[0, 0, 0], &[1, 0, 0], &[0, 0, 0]]);
+        let tile = &requires_right.apply(&left, 16, 0, I64Vec2::ONE).unwrap()[0];
+        assert!(tile.flip_x && !tile.flip_y);
+
+        // Symmetric in x, so the unflipped pattern wins over the x flip.
+        let both = ShieldtankGridValues::from_rows(&[&[0, 0, 0], &[1, 0, 1], &[0, 0, 0]]);
+        let tile = &requires_right.apply(&both, 16, 0, I64Vec2::ONE).unwrap()[0];
+        assert!(!tile.flip_x && !tile.flip_y);
+
+        // Requires a cell below, only found by the y flip.
+        let requires_above = AutoRule {
+            flip_x: true,
+            flip_y: true,
+            ..rule(3, &[0, 1, 0, 0, 0, 0, 0, 0, 0], 0)
+        };
+        let below = ShieldtankGridValues::from_rows(&[&[0, 0, 0], &[0, 0, 0], &[0, 1, 0]]);
+        let tile = &requires_above.apply(&below, 16, 0, I64Vec2::ONE).unwrap()[0];
+        assert!(!tile.flip_x && tile.flip_y);
+
+        let without_flips = AutoRule {
+            flip_x: false,
+            flip_y: false,
+            ..requires_above
+        };
+        assert!(without_flips.apply(&below, 16, 0, I64Vec2::ONE).is_none());
+    }
+
+    #[test]
+    fn break_on_match_skips_later_rules() {
+        let grid_values = ShieldtankGridValues::from_rows(&[&[1]]);
+
+        let first = AutoRule {
+            break_on_match: false,
+            ..rule(1, &[1], 1)
+        };
+        let second = rule(1, &[1], 2);
+        let third = rule(1, &[1], 3);
+
+        let group = AutoRuleGroup {
+            rules: vec![first, second, third],
+            ..Default::default()
+        };
+        let set = AutoRuleSet {
+            groups: &[group],
+            seed: 0,
+        };
+
+        // Earlier rules are drawn on top, so last.
+        let tiles = set.evaluate(&grid_values, 16, I64Vec2::ZERO);
+        assert_eq!(sources(&tiles), vec![2, 1]);
+    }
+
+    #[test]
+    fn inactive_rules_and_groups_are_skipped() {
+        let grid_values = ShieldtankGridValues::from_rows(&[&[1]]);
+
+        let inactive_rule = AutoRule {
+            active: false,
+            ..rule(1, &[1], 1)
+        };
+        let inactive_group = AutoRuleGroup {
+            active: false,
+            rules: vec![rule(1, &[1], 2)],
+        };
+        let group = AutoRuleGroup {
+            rules: vec![inactive_rule, rule(1, &[1], 3)],
+            ..Default::default()
+        };
+        let groups = [inactive_group, group];
+        let set = AutoRuleSet {
+            groups: &groups,
+            seed: 0,
+        };
+
+        let tiles = set.evaluate(&grid_values, 16, I64Vec2::ZERO);
+        assert_eq!(sources(&tiles), vec![3]);
+    }
+
+    fn tile_keys(tiles: &[ShieldtankLayerTile]) -> Vec<(i64, i64, u32, u32, bool, bool)> {
+        let mut keys: Vec<_> = tiles
+            .iter()
+            .map(|tile| {
+                (
+                    tile.offset.x,
+                    tile.offset.y,
+                    tile.source.x,
+                    tile.source.y,
+                    tile.flip_x,
+                    tile.flip_y,
+                )
+            })
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn regenerate_matches_generate() {
+        let size = I64Vec2::new(12, 10);
+        let value_at = |grid: I64Vec2, edited: bool| {
+            let value = (grid.x * 7 + grid.y * 13 + grid.x * grid.y) % 5;
+            let value = if value > 2 { 0 } else { value };
+            match edited && (3..6).contains(&grid.x) && (4..7).contains(&grid.y) {
+                true => (value + 1) % 3,
+                false => value,
+            }
+        };
+        let cells = |edited: bool| {
+            (0..size.y).flat_map(move |y| {
+                (0..size.x).map(move |x| {
+                    let grid = I64Vec2::new(x, y);
+                    (grid, value_at(grid, edited))
+                })
+            })
+        };
+
+        let before = ShieldtankGridValues::from_values(size, 16.0, cells(false));
+        let after = ShieldtankGridValues::from_values(size, 16.0, cells(true));
+
+        let edge = AutoRule {
+            uid: 11,
+            flip_x: true,
+            flip_y: true,
+            chance: 0.6,
+            break_on_match: false,
+            tiles: vec![
+                vec![AutoRuleTile::new(UVec2::new(0, 16))],
+                vec![AutoRuleTile::new(UVec2::new(16, 16))],
+            ],
+            ..rule(3, &[0, EMPTY, 0, 0, 1, 2, 0, 0, 0], 0)
+        };
+        let stamp = AutoRule {
+            uid: 12,
+            x_modulo: 2,
+            y_modulo: 3,
+            pivot: Vec2::new(0.5, 1.0),
+            tiles: vec![vec![
+                AutoRuleTile::new(UVec2::new(32, 0)),
+                AutoRuleTile {
+                    cell: I64Vec2::new(1, 0),
+                    source: UVec2::new(48, 0),
+                },
+                AutoRuleTile {
+                    cell: I64Vec2::new(0, 1),
+                    source: UVec2::new(32, 16),
+                },
+            ]],
+            ..rule(1, &[2], 0)
+        };
+        let fill = AutoRule {
+            uid: 13,
+            tile_offset: I64Vec2::new(4, -20),
+            ..rule(1, &[ANY_VALUE], 64)
+        };
+
+        let group = AutoRuleGroup {
+            rules: vec![edge, stamp, fill],
+            ..Default::default()
+        };
+        let set = AutoRuleSet {
+            groups: &[group],
+            seed: 1234,
+        };
+
+        let edited: HashSet<I64Vec2> = cells(false)
+            .filter(|(grid, value)| value_at(*grid, true) != *value)
+            .map(|(grid, _)| grid)
+            .collect();
+        assert!(!edited.is_empty());
+
+        let mut tiles = set.generate(&before, 16);
+        set.regenerate(&after, 16, &mut tiles, &edited);
+
+        assert_eq!(tile_keys(&tiles), tile_keys(&set.generate(&after, 16)));
+    }
+}
@@ -0,0 +1,475 @@
+//! Physics-engine agnostic collider geometry for IntGrid layers.
+//!
+//! Add [ShieldtankColliderSettings] to a layer to have its solid cells merged into
+//! [ShieldtankColliders], which is regenerated whenever the grid values or settings change.
+
+use bevy_app::Plugin;
+use bevy_ecs::component::Component;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::lifecycle::RemovedComponents;
+use bevy_ecs::query::{Changed, Or};
+use bevy_ecs::schedule::IntoScheduleConfigs;
+use bevy_ecs::system::{Commands, Query};
+use bevy_math::{I64Vec2, Rect, Vec2};
+use bevy_platform::collections::{HashMap, HashSet};
+use bevy_reflect::Reflect;
+
+use super::grid_values::{IntGridSelector, ShieldtankGridValues, grid_values_system};
+use super::shieldtank_component::ShieldtankComponentSystemSet;
+
+/// Makes the cells matching `selector` solid, in the collision group `group`.
+#[derive(Clone, Debug, PartialEq, Reflect)]
+pub struct ColliderGroupRule {
+    pub selector: IntGridSelector,
+    pub group: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum ColliderShape {
+    /// As few rectangles as the greedy merge finds.
+    #[default]
+    Rectangles,
+    /// Closed outlines around each solid region.
+    Outlines,
+}
+
+/// Add to an IntGrid layer to generate [ShieldtankColliders] for it.
+#[derive(Clone, Debug, Default, Component, Reflect)]
+pub struct ShieldtankColliderSettings {
+    /// Checked in order, the first matching rule applies. Cells matching no rule aren't solid.
+    pub groups: Vec<ColliderGroupRule>,
+    pub shape: ColliderShape,
+}
+
+impl ShieldtankColliderSettings {
+    pub fn with_group(mut self, selector: impl Into<IntGridSelector>, group: u32) -> Self {
+        self.groups.push(ColliderGroupRule {
+            selector: selector.into(),
+            group,
+        });
+        self
+    }
+
+    pub fn with_shape(mut self, shape: ColliderShape) -> Self {
+        self.shape = shape;
+        self
+    }
+}
+
+/// Geometry relative to the layer's origin, its top left corner, with y pointing up.
+#[derive(Clone, Debug, PartialEq, Reflect)]
+pub enum ColliderGeometry {
+    Rect(Rect),
+    /// A closed loop, without repeating the first point. Wound clockwise around solid cells, and
+    /// counter-clockwise around holes.
+    Polyline(Vec<Vec2>),
+}
+
+#[derive(Clone, Debug, PartialEq, Reflect)]
+pub struct ShieldtankCollider {
+    pub group: u32,
+    pub geometry: ColliderGeometry,
+}
+
+#[derive(Clone, Debug, Default, Component, Reflect)]
+pub struct ShieldtankColliders {
+    pub colliders: Vec<ShieldtankCollider>,
+}
+
+impl ShieldtankColliders {
+    pub fn new(grid_values: &ShieldtankGridValues, settings: &ShieldtankColliderSettings) -> Self {
+        let grid_cell_size = grid_values.grid_cell_size();
+
+        let mut groups: HashMap<u32, HashSet<I64Vec2>> = HashMap::new();
+        grid_values.enumerate().for_each(|(grid, grid_value)| {
+            if let Some(rule) = settings
+                .groups
+                .iter()
+                .find(|rule| rule.selector.matches(grid_value))
+            {
+                groups.entry(rule.group).or_default().insert(grid);
+            }
+        });
+
+        let mut group_ids: Vec<u32> = groups.keys().copied().collect();
+        group_ids.sort();
+
+        let colliders = group_ids
+            .into_iter()
+            .flat_map(|group| {
+                let cells = &groups[&group];
+
+                let geometry: Vec<ColliderGeometry> = match settings.shape {
+                    ColliderShape::Rectangles => merge_rectangles(cells)
+                        .into_iter()
+                        .map(|(min, max)| {
+                            ColliderGeometry::Rect(Rect::from_corners(
+                                to_local(min, grid_cell_size),
+                                to_local(max, grid_cell_size),
+                            ))
+                        })
+                        .collect(),
+                    ColliderShape::Outlines => trace_outlines(cells)
+                        .into_iter()
+                        .map(|outline| {
+                            ColliderGeometry::Polyline(
+                                outline
+                                    .into_iter()
+                                    .map(|corner| to_local(corner, grid_cell_size))
+                                    .collect(),
+                            )
+                        })
+                        .collect(),
+                };
+
+                geometry
+                    .into_iter()
+                    .map(move |geometry| ShieldtankCollider { group, geometry })
+            })
+            .collect();
+
+        Self { colliders }
+    }
+}
+
+/// Converts a cell corner to a position relative to the layer origin.
+fn to_local(corner: I64Vec2, grid_cell_size: f32) -> Vec2 {
+    corner.as_vec2() * Vec2::new(1.0, -1.0) * grid_cell_size
+}
+
+/// Greedily merges cells into rectangles, as pairs of their top left and bottom right corners.
+fn merge_rectangles(cells: &HashSet<I64Vec2>) -> Vec<(I64Vec2, I64Vec2)> {
+    let mut sorted: Vec<I64Vec2> = cells.iter().copied().collect();
+    sorted.sort_by_key(|cell| (cell.y, cell.x));
+
+    let mut used: HashSet<I64Vec2> = HashSet::new();
+    let mut rectangles = vec![];
+
+    let free =
+        |cell: I64Vec2, used: &HashSet<I64Vec2>| cells.contains(&cell) && !used.contains(&cell);
+
+    for start in sorted {
+        if used.contains(&start) {
+            continue;
+        }
+
+        let mut width = 1;
+        while free(start + I64Vec2::new(width, 0), &used) {
+            width += 1;
+        }
+
+        let mut height = 1;
+        while (0..width).all(|x| free(start + I64Vec2::new(x, height), &used)) {
+            height += 1;
+        }
+
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| I64Vec2::new(x, y)))
+            .for_each(|offset| {
+                used.insert(start + offset);
+            });
+
+        rectangles.push((start, start + I64Vec2::new(width, height)));
+    }
+
+    rectangles
+}
+
+/// How a boundary turns from `from` to `to`, with y pointing down: right, straight, then left.
+fn turn_rank(from: I64Vec2, to: I64Vec2) -> u8 {
+    if to == I64Vec2::new(-from.y, from.x) {
+        0
+    } else if to == from {
+        1
+    } else if to == I64Vec2::new(from.y, -from.x) {
+        2
+    } else {
+        3
+    }
+}
+
+/// Traces the boundary of the cells into closed loops of cell corners, dropping collinear corners.
+///
+/// Where solid cells only touch at a corner, the trace always takes the right-most turn, keeping to
+/// the cell it's going around. Such cells are outlined separately rather than by a loop crossing
+/// itself, and a loop only comes back to a corner when its region wraps around to touch itself.
+fn trace_outlines(cells: &HashSet<I64Vec2>) -> Vec<Vec<I64Vec2>> {
+    // Directed boundary edges, going clockwise around the solid cells (with y pointing down).
+    let mut edges: HashMap<I64Vec2, Vec<I64Vec2>> = HashMap::new();
+
+    cells.iter().for_each(|cell| {
+        let top_left = *cell;
+        let top_right = *cell + I64Vec2::new(1, 0);
+        let bottom_right = *cell + I64Vec2::new(1, 1);
+        let bottom_left = *cell + I64Vec2::new(0, 1);
+
+        [
+            (I64Vec2::NEG_Y, top_left, top_right),
+            (I64Vec2::X, top_right, bottom_right),
+            (I64Vec2::Y, bottom_right, bottom_left),
+            (I64Vec2::NEG_X, bottom_left, top_left),
+        ]
+        .into_iter()
+        .filter(|(side, ..)| !cells.contains(&(*cell + *side)))
+        .for_each(|(_, from, to)| edges.entry(from).or_default().push(to));
+    });
+
+    let mut starts: Vec<I64Vec2> = edges.keys().copied().collect();
+    starts.sort_by_key(|corner| (corner.y, corner.x));
+
+    let mut outlines = vec![];
+
+    for start in starts {
+        while let Some(first) = edges.get_mut(&start).and_then(Vec::pop) {
+            let mut outline = vec![start];
+            let mut previous = start;
+            let mut current = first;
+
+            loop {
+                let direction = current - previous;
+                let outgoing = edges.entry(current).or_default();
+
+                let best = (0..outgoing.len())
+                    .min_by_key(|index| turn_rank(direction, outgoing[*index] - current));
+
+                // Back at the start, the first edge closes the loop unless another turns further
+                // right.
+                let closes = current == start
+                    && best.is_none_or(|index| {
+                        turn_rank(direction, first - start)
+                            < turn_rank(direction, outgoing[index] - current)
+                    });
+
+                if closes {
+                    break;
+                }
+
+                let Some(index) = best else {
+                    break;
+                };
+
+                outline.push(current);
+                previous = current;
+                current = outgoing.swap_remove(index);
+            }
+
+            let count = outline.len();
+            let simplified: Vec<I64Vec2> = (0..count)
+                .filter(|index| {
+                    let previous = outline[(index + count - 1) % count];
+                    let corner = outline[*index];
+                    let next = outline[(index + 1) % count];
+                    (corner - previous).signum() != (next - corner).signum()
+                })
+                .map(|index| outline[index])
+                .collect();
+
+            outlines.push(simplified);
+        }
+    }
+
+    outlines
+}
+
+#[allow(clippy::type_complexity)]
+fn colliders_system(
+    query: Query<
+        (Entity, &ShieldtankGridValues, &ShieldtankColliderSettings),
+        Or<(
+            Changed<ShieldtankGridValues>,
+            Changed<ShieldtankColliderSettings>,
+        )>,
+    >,
+    mut commands: Commands,
+) {
+    query.iter().for_each(|(entity, grid_values, settings)| {
+        let colliders = ShieldtankColliders::new(grid_values, settings);
+        commands.entity(entity).insert(colliders);
+    });
+}
+
+fn collider_settings_removed_system(
+    mut removed: RemovedComponents<ShieldtankColliderSettings>,
+    mut commands: Commands,
+) {
+    removed.read().for_each(|entity| {
+        if let Ok(mut entity_commands) = commands.get_entity(entity) {
+            entity_commands.remove::<ShieldtankColliders>();
+        }
+    });
+}
+
+pub struct CollidersPlugin;
+impl Plugin for CollidersPlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.register_type::<ColliderGroupRule>();
+        app.register_type::<ColliderShape>();
+        app.register_type::<ShieldtankColliderSettings>();
+        app.register_type::<ColliderGeometry>();
+        app.register_type::<ShieldtankCollider>();
+        app.register_type::<ShieldtankColliders>();
+        app.add_systems(
+            ShieldtankComponentSystemSet,
+            (
+                colliders_system.after(grid_values_system),
+                collider_settings_removed_system,
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(cells: &[(i64, i64)]) -> HashSet<I64Vec2> {
+        cells.iter().map(|(x, y)| I64Vec2::new(*x, *y)).collect()
+    }
+
+    fn corners(corners: &[(i64, i64)]) -> Vec<I64Vec2> {
+        corners.iter().map(|(x, y)| I64Vec2::new(*x, *y)).collect()
+    }
+
+    fn assert_simple(outline: &[I64Vec2]) {
+        let unique: HashSet<I64Vec2> = outline.iter().copied().collect();
+        assert_eq!(
+            unique.len(),
+            outline.len(),
+            "{outline:?} visits a corner twice"
+        );
+    }
+
+    #[test]
+    fn outlines_of_a_checkerboard_pinch_stay_separate() {
+        let outlines = trace_outlines(&cells(&[(0, 0), (1, 1)]));
+
+        assert_eq!(
+            outlines,
+            vec![
+                corners(&[(0, 0), (1, 0), (1, 1), (0, 1)]),
+                corners(&[(1, 1), (2, 1), (2, 2), (1, 2)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn outlines_of_a_diagonal_are_all_simple() {
+        let outlines = trace_outlines(&cells(&[(0, 0), (1, 1), (2, 2), (1, 3), (0, 2)]));
+
+        assert_eq!(outlines.len(), 5);
+        outlines.iter().for_each(|outline| {
+            assert_eq!(outline.len(), 4);
+            assert_simple(outline);
+        });
+    }
+
+    #[test]
+    fn outlines_of_a_hollow_ring() {
+        let ring = cells(&[
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (0, 1),
+            (2, 1),
+            (0, 2),
+            (1, 2),
+            (2, 2),
+        ]);
+
+        let outlines = trace_outlines(&ring);
+
+        assert_eq!(
+            outlines,
+            vec![
+                // Clockwise around the solid cells.
+                corners(&[(0, 0), (3, 0), (3, 3), (0, 3)]),
+                // Counter-clockwise around the hole.
+                corners(&[(1, 1), (1, 2), (2, 2), (2, 1)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn outlines_of_a_ring_pinched_to_its_hole() {
+        // The hole at (1, 1) opens onto the outside through the corner at (2, 2), so the ring is
+        // traced as one loop which touches itself there, turning right into the hole.
+        let ring = cells(&[
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (0, 1),
+            (2, 1),
+            (0, 2),
+            (1, 2),
+            (3, 3),
+        ]);
+
+        assert_eq!(
+            trace_outlines(&ring),
+            vec![
+                corners(&[
+                    (0, 0),
+                    (3, 0),
+                    (3, 2),
+                    (2, 2),
+                    (2, 1),
+                    (1, 1),
+                    (1, 2),
+                    (2, 2),
+                    (2, 3),
+                    (0, 3),
+                ]),
+                corners(&[(3, 3), (4, 3), (4, 4), (3, 4)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_rectangles_of_a_block() {
+        let block = cells(&[(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]);
+
+        assert_eq!(
+            merge_rectangles(&block),
+            vec![(I64Vec2::new(0, 0), I64Vec2::new(3, 2))]
+        );
+    }
+
+    #[test]
+    fn merge_rectangles_of_an_l_shape() {
+        let l_shape = cells(&[(0, 0), (1, 0), (0, 1)]);
+
+        assert_eq!(
+            merge_rectangles(&l_shape),
+            vec![
+                (I64Vec2::new(0, 0), I64Vec2::new(2, 1)),
+                (I64Vec2::new(0, 1), I64Vec2::new(1, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_rectangles_covers_each_cell_once() {
+        let shape = cells(&[
+            (0, 0),
+            (1, 0),
+            (3, 0),
+            (0, 1),
+            (1, 1),
+            (2, 1),
+            (3, 1),
+            (1, 2),
+            (2, 2),
+            (4, 4),
+        ]);
+
+        let mut covered = vec![];
+        merge_rectangles(&shape).into_iter().for_each(|(min, max)| {
+            (min.y..max.y)
+                .flat_map(|y| (min.x..max.x).map(move |x| I64Vec2::new(x, y)))
+                .for_each(|cell| covered.push(cell));
+        });
+
+        assert_eq!(covered.len(), shape.len());
+        assert_eq!(covered.into_iter().collect::<HashSet<_>>(), shape);
+    }
+}
@@ -16,12 +16,13 @@ use crate::component::world_bounds::ShieldtankWorldBounds;
 
 use super::entity_definition::ShieldtankEntityDefinition;
 use super::field_instances::ShieldtankFieldInstances;
+use super::grid_coords::GridCoords;
 use super::shieldtank_component::{ShieldtankComponent, ShieldtankComponentSystemSet};
 use super::tags::ShieldtankTags;
 use super::tile::ShieldtankTile;
 
 #[derive(Debug, Default, Component, Reflect)]
-#[require(GlobalTransform, Visibility)]
+#[require(GlobalTransform, GridCoords, Visibility)]
 pub struct ShieldtankEntity {
     pub handle: Handle<EntityInstance>,
 }
@@ -41,7 +42,7 @@ impl ShieldtankComponent for ShieldtankEntity {
 }
 
 #[allow(clippy::type_complexity)]
-fn entity_insert_components_system(
+pub(crate) fn entity_insert_components_system(
     query: Query<
         (
             Entity,
@@ -95,6 +96,13 @@ fn entity_insert_components_system(
         });
 }
 
+/// The bounds of an entity, relative to its translation.
+pub(crate) fn entity_local_bounds(asset: &EntityInstance) -> Rect {
+    let size = asset.size.as_vec2();
+    let offset = asset.anchor.as_vec() * size;
+    Rect::from_center_size(-offset, size)
+}
+
 #[allow(clippy::type_complexity)]
 fn entity_global_bounds_system(
     query: Query<
@@ -121,10 +129,11 @@ fn entity_global_bounds_system(
         })
         .for_each(|(entity, asset, global_transform)| {
             let global_location = global_transform.translation().truncate();
-            let size = asset.size.as_vec2();
-            let anchor = asset.anchor;
-            let offset = anchor.as_vec() * size;
-            let rect = Rect::from_center_size(global_location - offset, size);
+            let local_bounds = entity_local_bounds(asset);
+            let rect = Rect {
+                min: local_bounds.min + global_location,
+                max: local_bounds.max + global_location,
+            };
             let global_bounds = ShieldtankWorldBounds::from(rect);
 
             commands.entity(entity).insert(global_bounds);
@@ -0,0 +1,367 @@
+//! Resolves the entity reference fields of LDtk entities into Bevy relationships.
+//!
+//! Every reference becomes an edge entity holding a [ShieldtankEntityRef]. The edge points back
+//! at the referencing entity with [LdtkReferenceOf], and at the referenced entity with
+//! [LdtkRefersTo] while it's spawned. Targets are looked up under the same root as the referencing
+//! entity, and those in other levels are resolved once they spawn. Levels spawned on their own
+//! with [SpawnLdtkLevelCommandsExt::spawn_ldtk_level] are roots of their own, so references
+//! between them are resolved through the referenced level, when it was spawned from the same
+//! project.
+//!
+//! [SpawnLdtkLevelCommandsExt::spawn_ldtk_level]: super::level_spawner::SpawnLdtkLevelCommandsExt::spawn_ldtk_level
+
+use bevy_app::Plugin;
+use bevy_ecs::component::Component;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::query::{Added, Changed, With, Without};
+use bevy_ecs::relationship::RelationshipTarget;
+use bevy_ecs::schedule::IntoScheduleConfigs;
+use bevy_ecs::system::{Commands, Query, Res, SystemParam};
+use bevy_ldtk_asset::iid::Iid;
+use bevy_reflect::Reflect;
+
+use super::entity::ShieldtankEntity;
+use super::field_instances::ShieldtankFieldInstances;
+use super::iid::{IidRegistry, ShieldtankIid, iid_added};
+use super::level_spawner::ShieldtankLevelRequest;
+use super::shieldtank_component::ShieldtankComponentSystemSet;
+
+/// A single entity reference, read from the field `field`.
+#[derive(Clone, Debug, Component, Reflect)]
+pub struct ShieldtankEntityRef {
+    pub field: String,
+    pub target_iid: Iid,
+    /// The level holding the referenced entity.
+    pub level_iid: Iid,
+}
+
+/// Points from a reference edge to the LDtk entity holding the reference field.
+#[derive(Debug, Component)]
+#[relationship(relationship_target = LdtkReferences)]
+pub struct LdtkReferenceOf(pub Entity);
+
+/// The reference edges of an LDtk entity, despawned along with it.
+#[derive(Debug, Default, Component)]
+#[relationship_target(relationship = LdtkReferenceOf, linked_spawn)]
+pub struct LdtkReferences(Vec<Entity>);
+
+/// Points from a reference edge to the referenced entity. Removed when the target despawns.
+#[derive(Debug, Component)]
+#[relationship(relationship_target = LdtkReferencedBy)]
+pub struct LdtkRefersTo(pub Entity);
+
+/// The reference edges pointing at an LDtk entity.
+#[derive(Debug, Default, Component)]
+#[relationship_target(relationship = LdtkRefersTo)]
+pub struct LdtkReferencedBy(Vec<Entity>);
+
+/// Follows reference edges without going through Iids.
+#[derive(SystemParam)]
+pub struct LdtkEntityRefs<'w, 's> {
+    references_query: Query<'w, 's, &'static LdtkReferences>,
+    referenced_by_query: Query<'w, 's, &'static LdtkReferencedBy>,
+    edge_query: Query<
+        'w,
+        's,
+        (
+            &'static ShieldtankEntityRef,
+            &'static LdtkReferenceOf,
+            Option<&'static LdtkRefersTo>,
+        ),
+    >,
+}
+
+impl LdtkEntityRefs<'_, '_> {
+    /// The spawned entities referenced by `source` through the field `field`.
+    pub fn targets(&self, source: Entity, field: &str) -> impl Iterator<Item = Entity> {
+        self.references_query
+            .get(source)
+            .into_iter()
+            .flat_map(|references| references.iter())
+            .filter_map(|edge| self.edge_query.get(edge).ok())
+            .filter(move |(entity_ref, ..)| entity_ref.field == field)
+            .filter_map(|(.., refers_to)| Some(refers_to?.0))
+    }
+
+    /// The entities referencing `target`, along with the field holding the reference.
+    pub fn sources(&self, target: Entity) -> impl Iterator<Item = (Entity, &str)> {
+        self.referenced_by_query
+            .get(target)
+            .into_iter()
+            .flat_map(|referenced_by| referenced_by.iter())
+            .filter_map(|edge| self.edge_query.get(edge).ok())
+            .map(|(entity_ref, reference_of, _)| (reference_of.0, entity_ref.field.as_str()))
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn entity_ref_spawn_system(
+    query: Query<
+        (Entity, &ShieldtankFieldInstances, Option<&LdtkReferences>),
+        (With<ShieldtankEntity>, Changed<ShieldtankFieldInstances>),
+    >,
+    iid_registry: Res<IidRegistry>,
+    request_query: Query<&ShieldtankLevelRequest>,
+    mut commands: Commands,
+) {
+    query
+        .iter()
+        .for_each(|(entity, field_instances, references)| {
+            references
+                .into_iter()
+                .flat_map(|references| references.iter())
+                .for_each(|edge| {
+                    commands.entity(edge).despawn();
+                });
+
+            // bevy_ldtk_asset names the getter of a single entity ref `get_array_entity_ref`, and
+            // doesn't load arrays of them.
+            field_instances
+                .iter()
+                .filter_map(|(field, field_instance)| {
+                    let entity_ref = field_instance.get_array_entity_ref()?;
+
+                    Some(ShieldtankEntityRef {
+                        field: field.clone(),
+                        target_iid: entity_ref.entity_iid,
+                        level_iid: entity_ref.level_iid,
+                    })
+                })
+                .for_each(|entity_ref| {
+                    let target = resolve_target(entity, &entity_ref, &iid_registry, &request_query);
+
+                    let mut edge_commands = commands.spawn((entity_ref, LdtkReferenceOf(entity)));
+
+                    if let Some(target) = target {
+                        edge_commands.insert(LdtkRefersTo(target));
+                    }
+                });
+        });
+}
+
+/// The entity referenced by `source`, looked up in its root, or in the referenced level when
+/// `source` is in a level spawned on its own.
+fn resolve_target(
+    source: Entity,
+    entity_ref: &ShieldtankEntityRef,
+    iid_registry: &IidRegistry,
+    request_query: &Query<&ShieldtankLevelRequest>,
+) -> Option<Entity> {
+    if let Some(target) = iid_registry.get_in_root_of(source, entity_ref.target_iid) {
+        return Some(target);
+    }
+
+    let root = iid_registry.root_of(source)?;
+    let project = &request_query.get(root).ok()?.project;
+
+    iid_registry
+        .get_all(entity_ref.level_iid)
+        .filter(|level| {
+            request_query
+                .get(*level)
+                .is_ok_and(|request| request.project == *project)
+        })
+        .find_map(|level| iid_registry.get(level, entity_ref.target_iid))
+}
+
+fn entity_ref_resolve_system(
+    added_query: Query<(), Added<ShieldtankIid>>,
+    edge_query: Query<(Entity, &ShieldtankEntityRef, &LdtkReferenceOf), Without<LdtkRefersTo>>,
+    iid_registry: Res<IidRegistry>,
+    request_query: Query<&ShieldtankLevelRequest>,
+    mut commands: Commands,
+) {
+    if added_query.is_empty() {
+        return;
+    }
+
+    edge_query
+        .iter()
+        .for_each(|(edge, entity_ref, reference_of)| {
+            if let Some(target) =
+                resolve_target(reference_of.0, entity_ref, &iid_registry, &request_query)
+            {
+                commands.entity(edge).insert(LdtkRefersTo(target));
+            }
+        });
+}
+
+pub struct EntityRefPlugin;
+impl Plugin for EntityRefPlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.register_type::<ShieldtankEntityRef>();
+        app.add_systems(
+            ShieldtankComponentSystemSet,
+            (
+                entity_ref_spawn_system.after(iid_added),
+                entity_ref_resolve_system,
+            )
+                .chain(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use bevy_asset::Handle;
+    use bevy_ecs::hierarchy::ChildOf;
+    use bevy_ldtk_asset::field_instance::FieldInstanceType;
+    use bevy_platform::collections::HashMap;
+
+    use crate::component::iid::IidPlugin;
+    use crate::component::level::ShieldtankLevel;
+    use crate::component::level_spawner::LevelSelector;
+    use crate::component::world::ShieldtankWorld;
+    use crate::test::{entity_ref, field_instance, test_app};
+
+    use super::*;
+
+    fn app() -> bevy_app::App {
+        let mut app = test_app();
+        app.add_plugins((IidPlugin, EntityRefPlugin));
+        app
+    }
+
+    fn spawn_level(app: &mut bevy_app::App, iid: u128) -> Entity {
+        app.world_mut()
+            .spawn((
+                ShieldtankLevel::default(),
+                ShieldtankIid::new(Iid::from_u128(iid)),
+            ))
+            .id()
+    }
+
+    fn spawn_standalone_level(app: &mut bevy_app::App, iid: u128, project: u128) -> Entity {
+        let level = spawn_level(app, iid);
+        app.world_mut()
+            .entity_mut(level)
+            .insert(ShieldtankLevelRequest {
+                project: Handle::Uuid(Iid::from_u128(project), PhantomData),
+                selector: LevelSelector::Iid(Iid::from_u128(iid)),
+            });
+        level
+    }
+
+    /// An LDtk entity in `level`, optionally referencing `target` in the level `target_level`.
+    fn spawn_entity(
+        app: &mut bevy_app::App,
+        level: Entity,
+        iid: u128,
+        target: Option<(u128, u128)>,
+    ) -> Entity {
+        let field_instances = target
+            .map(|(target, target_level)| {
+                let field_type = FieldInstanceType::EntityRef(entity_ref(target, target_level));
+                ("Target".to_string(), field_instance(field_type))
+            })
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        app.world_mut()
+            .spawn((
+                ShieldtankEntity::default(),
+                ShieldtankIid::new(Iid::from_u128(iid)),
+                ShieldtankFieldInstances::new(field_instances),
+                ChildOf(level),
+            ))
+            .id()
+    }
+
+    /// The target of each reference edge of `source`, if resolved.
+    fn targets(app: &mut bevy_app::App, source: Entity) -> Vec<Option<Entity>> {
+        let world = app.world();
+        world
+            .get::<LdtkReferences>(source)
+            .into_iter()
+            .flat_map(|references| references.iter())
+            .map(|edge| world.get::<LdtkRefersTo>(edge).map(|refers_to| refers_to.0))
+            .collect()
+    }
+
+    #[test]
+    fn resolves_references_within_a_level() {
+        let mut app = app();
+        let level = spawn_level(&mut app, 10);
+        let source = spawn_entity(&mut app, level, 1, Some((2, 10)));
+        let target = spawn_entity(&mut app, level, 2, None);
+
+        app.update();
+
+        assert_eq!(targets(&mut app, source), [Some(target)]);
+
+        let edge = app
+            .world()
+            .get::<LdtkReferences>(source)
+            .unwrap()
+            .iter()
+            .next();
+        let entity_ref = app
+            .world()
+            .get::<ShieldtankEntityRef>(edge.unwrap())
+            .unwrap();
+        assert_eq!(entity_ref.field, "Target");
+        assert_eq!(entity_ref.level_iid, Iid::from_u128(10));
+    }
+
+    #[test]
+    fn resolves_targets_spawned_later() {
+        let mut app = app();
+        let world = app.world_mut().spawn(ShieldtankWorld::default()).id();
+        let first = spawn_level(&mut app, 10);
+        app.world_mut().entity_mut(first).insert(ChildOf(world));
+        let source = spawn_entity(&mut app, first, 1, Some((2, 20)));
+
+        app.update();
+        assert_eq!(targets(&mut app, source), [None]);
+
+        let second = spawn_level(&mut app, 20);
+        app.world_mut().entity_mut(second).insert(ChildOf(world));
+        let target = spawn_entity(&mut app, second, 2, None);
+        app.update();
+
+        assert_eq!(targets(&mut app, source), [Some(target)]);
+    }
+
+    #[test]
+    fn resolves_references_between_standalone_levels_of_a_project() {
+        let mut app = app();
+        let first = spawn_standalone_level(&mut app, 10, 100);
+        let source = spawn_entity(&mut app, first, 1, Some((2, 20)));
+
+        // The same level spawned from another project must not be picked.
+        let other_project = spawn_standalone_level(&mut app, 20, 200);
+        spawn_entity(&mut app, other_project, 2, None);
+
+        app.update();
+        assert_eq!(targets(&mut app, source), [None]);
+
+        let second = spawn_standalone_level(&mut app, 20, 100);
+        let target = spawn_entity(&mut app, second, 2, None);
+        app.update();
+
+        assert_eq!(targets(&mut app, source), [Some(target)]);
+    }
+
+    #[test]
+    fn edges_are_rebuilt_when_fields_change() {
+        let mut app = app();
+        let level = spawn_level(&mut app, 10);
+        let source = spawn_entity(&mut app, level, 1, Some((2, 10)));
+        spawn_entity(&mut app, level, 2, None);
+        let other = spawn_entity(&mut app, level, 3, None);
+        app.update();
+
+        let field_type = FieldInstanceType::EntityRef(entity_ref(3, 10));
+        app.world_mut()
+            .entity_mut(source)
+            .insert(ShieldtankFieldInstances::new(HashMap::from_iter([(
+                "Target".to_string(),
+                field_instance(field_type),
+            )])));
+        app.update();
+
+        assert_eq!(targets(&mut app, source), [Some(other)]);
+    }
+}
@@ -0,0 +1,253 @@
+use bevy_app::{App, Plugin};
+use bevy_asset::prelude::AssetChanged;
+use bevy_asset::{AsAssetId, Assets};
+use bevy_ecs::bundle::Bundle;
+use bevy_ecs::component::Component;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::query::{Changed, Or};
+use bevy_ecs::resource::Resource;
+use bevy_ecs::schedule::IntoScheduleConfigs;
+use bevy_ecs::system::{Commands, EntityCommands, Query, Res};
+use bevy_ldtk_asset::entity::EntityInstance;
+use bevy_ldtk_asset::prelude::LdtkAsset;
+use bevy_platform::collections::HashMap;
+
+use super::entity::{ShieldtankEntity, entity_insert_components_system};
+use super::shieldtank_component::ShieldtankComponentSystemSet;
+
+/// A [Bundle] which can be built from an LDtk entity instance.
+///
+/// Register it for an LDtk entity identifier with [LdtkEntityAppExt::register_ldtk_entity].
+pub trait LdtkEntity: Bundle {
+    fn from_ldtk_entity(entity_instance: &EntityInstance) -> Self;
+}
+
+type LdtkEntityInsert = Box<dyn Fn(&EntityInstance, &mut EntityCommands) + Send + Sync>;
+
+struct LdtkEntityBuilder {
+    insert: LdtkEntityInsert,
+    remove: Box<dyn Fn(&mut EntityCommands) + Send + Sync>,
+}
+
+#[derive(Default, Resource)]
+pub struct LdtkEntityRegistry {
+    builders: HashMap<String, Vec<LdtkEntityBuilder>>,
+    fallback: Option<LdtkEntityInsert>,
+}
+
+impl LdtkEntityRegistry {
+    pub fn is_registered(&self, identifier: &str) -> bool {
+        self.builders.contains_key(identifier)
+    }
+
+    fn insert_components(&self, entity_instance: &EntityInstance, commands: &mut EntityCommands) {
+        match self.builders.get(entity_instance.get_identifier()) {
+            Some(builders) => builders
+                .iter()
+                .for_each(|builder| (builder.insert)(entity_instance, commands)),
+            None => {
+                if let Some(fallback) = &self.fallback {
+                    fallback(entity_instance, commands);
+                }
+            }
+        }
+    }
+
+    fn remove_components(&self, identifier: &str, commands: &mut EntityCommands) {
+        self.builders
+            .get(identifier)
+            .into_iter()
+            .flatten()
+            .for_each(|builder| (builder.remove)(commands));
+    }
+}
+
+/// The identifier whose registered bundles were inserted on an entity, so they can be removed
+/// when a reload changes it.
+#[derive(Component)]
+pub(crate) struct LdtkEntityRegisteredAs(String);
+
+pub trait LdtkEntityAppExt {
+    /// Inserts `B` on every [ShieldtankEntity] whose LDtk identifier is `identifier`. The bundle
+    /// is rebuilt and re-inserted whenever the entity instance is reloaded, and removed when the
+    /// reload changes its identifier.
+    fn register_ldtk_entity<B: LdtkEntity>(&mut self, identifier: impl Into<String>) -> &mut Self;
+
+    /// Called for every [ShieldtankEntity] whose identifier has no registered bundle. Unlike
+    /// registered bundles, what it inserts isn't removed when a reload changes the identifier.
+    fn register_ldtk_entity_fallback(
+        &mut self,
+        fallback: impl Fn(&EntityInstance, &mut EntityCommands) + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+
+impl LdtkEntityAppExt for App {
+    fn register_ldtk_entity<B: LdtkEntity>(&mut self, identifier: impl Into<String>) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<LdtkEntityRegistry>()
+            .builders
+            .entry(identifier.into())
+            .or_default()
+            .push(LdtkEntityBuilder {
+                insert: Box::new(|entity_instance, commands| {
+                    commands.insert(B::from_ldtk_entity(entity_instance));
+                }),
+                remove: Box::new(|commands| {
+                    commands.remove::<B>();
+                }),
+            });
+        self
+    }
+
+    fn register_ldtk_entity_fallback(
+        &mut self,
+        fallback: impl Fn(&EntityInstance, &mut EntityCommands) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<LdtkEntityRegistry>()
+            .fallback = Some(Box::new(fallback));
+        self
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn ldtk_entity_bundle_system(
+    query: Query<
+        (Entity, &ShieldtankEntity, Option<&LdtkEntityRegisteredAs>),
+        Or<(Changed<ShieldtankEntity>, AssetChanged<ShieldtankEntity>)>,
+    >,
+    assets: Res<Assets<EntityInstance>>,
+    registry: Res<LdtkEntityRegistry>,
+    mut commands: Commands,
+) {
+    query
+        .iter()
+        .filter_map(|(entity, component, registered_as)| {
+            Some((entity, assets.get(component.as_asset_id())?, registered_as))
+        })
+        .for_each(|(entity, asset, registered_as)| {
+            let mut entity_commands = commands.entity(entity);
+            let identifier = asset.get_identifier();
+
+            if let Some(LdtkEntityRegisteredAs(old_identifier)) = registered_as
+                && old_identifier != identifier
+            {
+                registry.remove_components(old_identifier, &mut entity_commands);
+            }
+
+            registry.insert_components(asset, &mut entity_commands);
+
+            match registry.is_registered(identifier) {
+                true => entity_commands.insert(LdtkEntityRegisteredAs(identifier.to_string())),
+                false => entity_commands.remove::<LdtkEntityRegisteredAs>(),
+            };
+        });
+}
+
+pub struct LdtkEntityRegistryPlugin;
+impl Plugin for LdtkEntityRegistryPlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.init_resource::<LdtkEntityRegistry>();
+        app.add_systems(
+            ShieldtankComponentSystemSet,
+            ldtk_entity_bundle_system.after(entity_insert_components_system),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::Update;
+    use bevy_asset::Handle;
+    use bevy_math::I64Vec2;
+
+    use crate::component::shieldtank_component::ShieldtankComponent;
+    use crate::test::{entity_asset, test_app};
+
+    use super::*;
+
+    #[derive(Component)]
+    struct Player;
+
+    impl LdtkEntity for Player {
+        fn from_ldtk_entity(_: &EntityInstance) -> Self {
+            Self
+        }
+    }
+
+    #[derive(Component)]
+    struct Enemy(String);
+
+    impl LdtkEntity for Enemy {
+        fn from_ldtk_entity(entity_instance: &EntityInstance) -> Self {
+            Self(entity_instance.identifier.clone())
+        }
+    }
+
+    #[derive(Component)]
+    struct Unknown;
+
+    fn app() -> bevy_app::App {
+        let mut app = test_app();
+        app.init_resource::<LdtkEntityRegistry>();
+        app.register_ldtk_entity::<Player>("Player");
+        app.register_ldtk_entity::<Enemy>("Enemy");
+        app.register_ldtk_entity_fallback(|_, commands| {
+            commands.insert(Unknown);
+        });
+        app.add_systems(Update, ldtk_entity_bundle_system);
+        app
+    }
+
+    fn spawn(app: &mut bevy_app::App, identifier: &str) -> (Entity, Handle<EntityInstance>) {
+        let asset = entity_asset(identifier, 1, I64Vec2::ZERO, Default::default());
+        let handle = app
+            .world_mut()
+            .resource_mut::<Assets<EntityInstance>>()
+            .add(asset);
+        let entity = app
+            .world_mut()
+            .spawn(ShieldtankEntity::new(handle.clone()))
+            .id();
+        app.update();
+
+        (entity, handle)
+    }
+
+    fn rename(app: &mut bevy_app::App, handle: &Handle<EntityInstance>, identifier: &str) {
+        app.world_mut()
+            .resource_mut::<Assets<EntityInstance>>()
+            .get_mut(handle)
+            .unwrap()
+            .identifier = identifier.to_string();
+        app.update();
+    }
+
+    #[test]
+    fn inserts_the_bundle_of_the_identifier() {
+        let mut app = app();
+
+        let (player, _) = spawn(&mut app, "Player");
+        let (other, _) = spawn(&mut app, "Door");
+
+        assert!(app.world().entity(player).contains::<Player>());
+        assert!(!app.world().entity(player).contains::<Unknown>());
+        assert!(app.world().entity(other).contains::<Unknown>());
+    }
+
+    #[test]
+    fn reloads_replace_the_bundle_of_an_old_identifier() {
+        let mut app = app();
+        let (entity, handle) = spawn(&mut app, "Player");
+
+        rename(&mut app, &handle, "Enemy");
+        let entity_ref = app.world().entity(entity);
+        assert!(!entity_ref.contains::<Player>());
+        assert_eq!(entity_ref.get::<Enemy>().unwrap().0, "Enemy");
+
+        rename(&mut app, &handle, "Door");
+        let entity_ref = app.world().entity(entity);
+        assert!(!entity_ref.contains::<Enemy>());
+        assert!(entity_ref.contains::<Unknown>());
+    }
+}
@@ -5,6 +5,9 @@ use bevy_ldtk_asset::field_instance::FieldInstance;
 use bevy_platform::collections::HashMap;
 use bevy_reflect::Reflect;
 
+use crate::error::LdtkFieldError;
+
+use super::ldtk_fields::FromLdtkField;
 use super::tile::ShieldtankTile;
 
 #[derive(Debug, Deref, Component, Reflect)]
@@ -48,6 +51,53 @@ impl ShieldtankFieldInstances {
     }
 }
 
+impl ShieldtankFieldInstances {
+    pub fn get_field<T: FromLdtkField>(&self, identifier: &str) -> Result<T, LdtkFieldError> {
+        let field =
+            self.field_instances
+                .get(identifier)
+                .ok_or_else(|| LdtkFieldError::Missing {
+                    identifier: identifier.to_string(),
+                })?;
+
+        T::from_ldtk_field(field).ok_or_else(|| LdtkFieldError::TypeMismatch {
+            identifier: identifier.to_string(),
+            expected: T::expected(),
+            found: format!("{field:?}"),
+        })
+    }
+
+    pub fn get_field_or_else<T: FromLdtkField>(
+        &self,
+        identifier: &str,
+        default: impl FnOnce() -> T,
+    ) -> Result<T, LdtkFieldError> {
+        match self.get_field(identifier) {
+            Err(LdtkFieldError::Missing { .. }) => Ok(default()),
+            result => result,
+        }
+    }
+
+    /// Returns `None` when the field is missing, and an error when it holds a value of another
+    /// type. Optional fields left empty in LDtk aren't exported, so they're missing too.
+    pub fn get_optional_field<T: FromLdtkField>(
+        &self,
+        identifier: &str,
+    ) -> Result<Option<T>, LdtkFieldError> {
+        let Some(field) = self.field_instances.get(identifier) else {
+            return Ok(None);
+        };
+
+        T::from_ldtk_field(field)
+            .map(Some)
+            .ok_or_else(|| LdtkFieldError::TypeMismatch {
+                identifier: identifier.to_string(),
+                expected: T::expected(),
+                found: format!("{field:?}"),
+            })
+    }
+}
+
 pub struct FieldInstancesPlugin;
 impl Plugin for FieldInstancesPlugin {
     fn build(&self, app: &mut bevy_app::App) {
@@ -1,29 +1,439 @@
+// The derives of ShieldtankComponentFilter name its deprecated variants.
+#![allow(deprecated)]
+
+use std::ops::{Bound, RangeBounds};
+use std::sync::RwLock;
+
+use bevy_app::Plugin;
 use bevy_ecs::component::Component;
+use bevy_ldtk_asset::field_instance::FieldInstance;
+use bevy_ldtk_asset::iid::Iid;
 use bevy_log::error;
+use bevy_platform::collections::{HashMap, HashSet};
+use bevy_reflect::Reflect;
 use regex::Regex;
 
-#[derive(Clone, Default, Component)]
+use super::ldtk_fields::enum_value;
+
+/// The data of a child asset which a [ShieldtankComponentFilter] is evaluated against.
+///
+/// Fields which don't apply to the kind of asset, such as tags for a level, are left empty.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FilterSubject<'a> {
+    /// The label of the child asset handle.
+    pub label: Option<&'a str>,
+    pub identifier: Option<&'a str>,
+    pub iid: Option<Iid>,
+    pub tags: &'a [String],
+    pub world_depth: Option<i64>,
+    pub field_instances: Option<&'a HashMap<String, FieldInstance>>,
+}
+
+/// Decides which children of a project, world, level or layer get spawned.
+///
+/// Insert on the parent entity. Without one, every child is spawned.
+#[derive(Clone, Debug, Default, Component, Reflect)]
+#[reflect(no_field_bounds)]
 pub enum ShieldtankComponentFilter {
     #[default]
     All,
     None,
+    /// Matches the asset label against a regex.
+    Label(FilterPattern),
+    /// Matches the LDtk identifier against a regex.
+    Identifier(FilterPattern),
+    Iids(HashSet<Iid>),
+    /// Matches entities carrying this tag.
+    Tag(String),
+    /// Matches levels within this inclusive world depth range.
+    WorldDepth {
+        min: Option<i64>,
+        max: Option<i64>,
+    },
+    /// Matches levels and entities with a field instance passing the predicate.
+    Field(FieldPredicate),
+    And(Vec<ShieldtankComponentFilter>),
+    Or(Vec<ShieldtankComponentFilter>),
+    /// Matches when the inner filter doesn't. Not reflected, since boxes can't be.
+    Not(#[reflect(ignore)] Box<ShieldtankComponentFilter>),
+    #[deprecated(note = "use `ShieldtankComponentFilter::label`")]
     ByPattern(String),
-    ByList(&'static [&'static str]),
+    #[deprecated(note = "use `ShieldtankComponentFilter::Label` with an alternation, or `Iids`")]
+    ByList(#[reflect(ignore)] &'static [&'static str]),
 }
 
 impl ShieldtankComponentFilter {
-    pub(crate) fn should_load(&self, label: &str) -> bool {
+    pub fn label(pattern: impl Into<String>) -> Self {
+        Self::Label(FilterPattern::new(pattern))
+    }
+
+    pub fn identifier(pattern: impl Into<String>) -> Self {
+        Self::Identifier(FilterPattern::new(pattern))
+    }
+
+    pub fn iids(iids: impl IntoIterator<Item = Iid>) -> Self {
+        Self::Iids(iids.into_iter().collect())
+    }
+
+    pub fn tag(tag: impl Into<String>) -> Self {
+        Self::Tag(tag.into())
+    }
+
+    pub fn world_depth(range: impl RangeBounds<i64>) -> Self {
+        let min = match range.start_bound() {
+            Bound::Included(min) => Some(*min),
+            Bound::Excluded(min) => Some(min + 1),
+            Bound::Unbounded => None,
+        };
+
+        let max = match range.end_bound() {
+            Bound::Included(max) => Some(*max),
+            Bound::Excluded(max) => Some(max - 1),
+            Bound::Unbounded => None,
+        };
+
+        Self::WorldDepth { min, max }
+    }
+
+    /// e.g. `ShieldtankComponentFilter::field("difficulty", FieldComparison::LessOrEqual(2.0))`
+    pub fn field(identifier: impl Into<String>, comparison: FieldComparison) -> Self {
+        Self::Field(FieldPredicate {
+            identifier: identifier.into(),
+            comparison,
+        })
+    }
+
+    pub fn and(self, other: Self) -> Self {
         match self {
-            ShieldtankComponentFilter::All => true,
-            ShieldtankComponentFilter::None => false,
-            ShieldtankComponentFilter::ByPattern(pattern) => {
-                let Ok(re) = Regex::new(pattern) else {
-                    error!("Could not compile regex! {pattern}");
-                    return false;
-                };
-                re.captures(label).is_some()
+            Self::And(mut filters) => {
+                filters.push(other);
+                Self::And(filters)
             }
-            ShieldtankComponentFilter::ByList(list) => list.contains(&label),
+            filter => Self::And(vec![filter, other]),
+        }
+    }
+
+    pub fn or(self, other: Self) -> Self {
+        match self {
+            Self::Or(mut filters) => {
+                filters.push(other);
+                Self::Or(filters)
+            }
+            filter => Self::Or(vec![filter, other]),
+        }
+    }
+
+    pub fn matches(&self, subject: &FilterSubject) -> bool {
+        match self {
+            Self::All => true,
+            Self::None => false,
+            Self::Label(pattern) => subject.label.is_some_and(|label| pattern.is_match(label)),
+            Self::Identifier(pattern) => subject
+                .identifier
+                .is_some_and(|identifier| pattern.is_match(identifier)),
+            Self::Iids(iids) => subject.iid.is_some_and(|iid| iids.contains(&iid)),
+            Self::Tag(tag) => subject.tags.iter().any(|inner_tag| inner_tag == tag),
+            Self::WorldDepth { min, max } => subject.world_depth.is_some_and(|world_depth| {
+                min.is_none_or(|min| world_depth >= min) && max.is_none_or(|max| world_depth <= max)
+            }),
+            Self::Field(predicate) => subject
+                .field_instances
+                .and_then(|field_instances| field_instances.get(&predicate.identifier))
+                .is_some_and(|field| predicate.comparison.matches(field)),
+            Self::And(filters) => filters.iter().all(|filter| filter.matches(subject)),
+            Self::Or(filters) => filters.iter().any(|filter| filter.matches(subject)),
+            Self::Not(filter) => !filter.matches(subject),
+            Self::ByPattern(pattern) => subject.label.is_some_and(|label| {
+                Regex::new(pattern)
+                    .inspect_err(|e| error!("Could not compile regex! {pattern}: {e}"))
+                    .is_ok_and(|regex| regex.is_match(label))
+            }),
+            Self::ByList(list) => subject.label.is_some_and(|label| list.contains(&label)),
+        }
+    }
+}
+
+impl std::ops::Not for ShieldtankComponentFilter {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        match self {
+            Self::Not(filter) => *filter,
+            filter => Self::Not(Box::new(filter)),
+        }
+    }
+}
+
+/// A regex which is compiled the first time it's matched, and again only if `pattern` changes.
+#[derive(Clone, Debug, Default, Reflect)]
+pub struct FilterPattern {
+    pub pattern: String,
+    #[reflect(ignore)]
+    compiled: CompiledPattern,
+}
+
+impl FilterPattern {
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            compiled: CompiledPattern::default(),
+        }
+    }
+
+    pub fn is_match(&self, haystack: &str) -> bool {
+        if let Ok(compiled) = self.compiled.0.read()
+            && let Some((pattern, regex)) = compiled.as_ref()
+            && *pattern == self.pattern
+        {
+            return regex.as_ref().is_some_and(|regex| regex.is_match(haystack));
+        }
+
+        let regex = Regex::new(&self.pattern)
+            .inspect_err(|e| error!("Could not compile regex! {}: {e}", self.pattern))
+            .ok();
+
+        let is_match = regex.as_ref().is_some_and(|regex| regex.is_match(haystack));
+
+        if let Ok(mut compiled) = self.compiled.0.write() {
+            *compiled = Some((self.pattern.clone(), regex));
+        }
+
+        is_match
+    }
+}
+
+#[derive(Debug, Default)]
+struct CompiledPattern(RwLock<Option<(String, Option<Regex>)>>);
+
+impl Clone for CompiledPattern {
+    fn clone(&self) -> Self {
+        let compiled = self
+            .0
+            .read()
+            .map(|compiled| compiled.clone())
+            .unwrap_or_default();
+
+        Self(RwLock::new(compiled))
+    }
+}
+
+#[derive(Clone, Debug, Reflect)]
+pub struct FieldPredicate {
+    pub identifier: String,
+    pub comparison: FieldComparison,
+}
+
+#[derive(Clone, Debug, Reflect)]
+pub enum FieldComparison {
+    Exists,
+    Equals(FieldValue),
+    /// Numeric comparisons accept both int and float fields.
+    LessThan(f64),
+    LessOrEqual(f64),
+    GreaterThan(f64),
+    GreaterOrEqual(f64),
+    /// Matches a string or enum field against a regex.
+    Matches(FilterPattern),
+}
+
+#[derive(Clone, Debug, PartialEq, Reflect)]
+pub enum FieldValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    /// Compared against string and enum fields.
+    String(String),
+}
+
+impl FieldComparison {
+    pub fn matches(&self, field: &FieldInstance) -> bool {
+        let number = || {
+            field
+                .get_int()
+                .map(|value| *value as f64)
+                .or_else(|| field.get_float().copied())
+        };
+
+        let string = || {
+            field
+                .get_string()
+                .map(String::as_str)
+                .or_else(|| enum_value(field))
+        };
+
+        match self {
+            Self::Exists => true,
+            Self::Equals(FieldValue::Int(value)) => field.get_int() == Some(value),
+            Self::Equals(FieldValue::Float(value)) => field.get_float() == Some(value),
+            Self::Equals(FieldValue::Bool(value)) => field.get_bool() == Some(value),
+            Self::Equals(FieldValue::String(value)) => string() == Some(value.as_str()),
+            Self::LessThan(value) => number().is_some_and(|number| number < *value),
+            Self::LessOrEqual(value) => number().is_some_and(|number| number <= *value),
+            Self::GreaterThan(value) => number().is_some_and(|number| number > *value),
+            Self::GreaterOrEqual(value) => number().is_some_and(|number| number >= *value),
+            Self::Matches(pattern) => string().is_some_and(|string| pattern.is_match(string)),
         }
     }
 }
+
+pub struct FilterPlugin;
+impl Plugin for FilterPlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.register_type::<ShieldtankComponentFilter>();
+        app.register_type::<FilterPattern>();
+        app.register_type::<FieldPredicate>();
+        app.register_type::<FieldComparison>();
+        app.register_type::<FieldValue>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ldtk_asset::field_instance::FieldInstanceType;
+
+    use crate::test::{enum_value, field_instance};
+
+    use super::*;
+
+    fn subject<'a>(
+        tags: &'a [String],
+        field_instances: &'a HashMap<String, FieldInstance>,
+    ) -> FilterSubject<'a> {
+        FilterSubject {
+            label: Some("world:World/Level_1"),
+            identifier: Some("Level_1"),
+            iid: Some(Iid::from_u128(1)),
+            tags,
+            world_depth: Some(2),
+            field_instances: Some(field_instances),
+        }
+    }
+
+    fn field_instances() -> HashMap<String, FieldInstance> {
+        HashMap::from_iter([
+            (
+                "difficulty".to_string(),
+                field_instance(FieldInstanceType::Int(2)),
+            ),
+            (
+                "gravity".to_string(),
+                field_instance(FieldInstanceType::Float(0.5)),
+            ),
+            (
+                "biome".to_string(),
+                field_instance(FieldInstanceType::Enum(enum_value("Forest"))),
+            ),
+            (
+                "title".to_string(),
+                field_instance(FieldInstanceType::String("The Woods".to_string())),
+            ),
+        ])
+    }
+
+    #[test]
+    fn matches_asset_data() {
+        let tags = vec!["Enemy".to_string()];
+        let field_instances = field_instances();
+        let subject = subject(&tags, &field_instances);
+
+        assert!(ShieldtankComponentFilter::All.matches(&subject));
+        assert!(!ShieldtankComponentFilter::None.matches(&subject));
+        assert!(ShieldtankComponentFilter::label("/Level_[0-9]$").matches(&subject));
+        assert!(!ShieldtankComponentFilter::label("Level_2").matches(&subject));
+        assert!(ShieldtankComponentFilter::identifier("^Level").matches(&subject));
+        assert!(ShieldtankComponentFilter::iids([Iid::from_u128(1)]).matches(&subject));
+        assert!(!ShieldtankComponentFilter::iids([Iid::from_u128(2)]).matches(&subject));
+        assert!(ShieldtankComponentFilter::tag("Enemy").matches(&subject));
+        assert!(!ShieldtankComponentFilter::tag("Item").matches(&subject));
+        assert!(ShieldtankComponentFilter::world_depth(2..=2).matches(&subject));
+        assert!(!ShieldtankComponentFilter::world_depth(..2).matches(&subject));
+        assert!(ShieldtankComponentFilter::world_depth(1..).matches(&subject));
+    }
+
+    #[test]
+    fn missing_data_does_not_match() {
+        let subject = FilterSubject::default();
+
+        assert!(!ShieldtankComponentFilter::label(".*").matches(&subject));
+        assert!(!ShieldtankComponentFilter::world_depth(..).matches(&subject));
+        assert!(
+            !ShieldtankComponentFilter::field("difficulty", FieldComparison::Exists)
+                .matches(&subject)
+        );
+    }
+
+    #[test]
+    fn matches_field_comparisons() {
+        let field_instances = field_instances();
+        let subject = subject(&[], &field_instances);
+        let field = ShieldtankComponentFilter::field;
+
+        assert!(field("difficulty", FieldComparison::Exists).matches(&subject));
+        assert!(!field("missing", FieldComparison::Exists).matches(&subject));
+        assert!(field("difficulty", FieldComparison::Equals(FieldValue::Int(2))).matches(&subject));
+        assert!(field("difficulty", FieldComparison::LessOrEqual(2.0)).matches(&subject));
+        assert!(!field("difficulty", FieldComparison::LessThan(2.0)).matches(&subject));
+        assert!(field("gravity", FieldComparison::GreaterThan(0.25)).matches(&subject));
+        assert!(
+            field("gravity", FieldComparison::Equals(FieldValue::Float(0.5))).matches(&subject)
+        );
+        assert!(!field("gravity", FieldComparison::Equals(FieldValue::Int(0))).matches(&subject));
+
+        let string = |value: &str| FieldComparison::Equals(FieldValue::String(value.to_string()));
+        assert!(field("biome", string("Forest")).matches(&subject));
+        assert!(!field("biome", string("Desert")).matches(&subject));
+        assert!(field("title", string("The Woods")).matches(&subject));
+
+        let pattern = |pattern: &str| FieldComparison::Matches(FilterPattern::new(pattern));
+        assert!(field("biome", pattern("^For")).matches(&subject));
+        assert!(field("title", pattern("Woods$")).matches(&subject));
+        assert!(!field("difficulty", pattern(".*")).matches(&subject));
+    }
+
+    #[test]
+    fn combines_filters() {
+        let field_instances = field_instances();
+        let subject = subject(&[], &field_instances);
+
+        let level_1 = ShieldtankComponentFilter::identifier("Level_1");
+        let level_2 = ShieldtankComponentFilter::identifier("Level_2");
+
+        assert!(
+            level_1
+                .clone()
+                .and(ShieldtankComponentFilter::All)
+                .matches(&subject)
+        );
+        assert!(!level_1.clone().and(level_2.clone()).matches(&subject));
+        assert!(level_2.clone().or(level_1.clone()).matches(&subject));
+        assert!(
+            !level_2
+                .clone()
+                .or(ShieldtankComponentFilter::None)
+                .matches(&subject)
+        );
+
+        assert!(!(!level_1.clone()).matches(&subject));
+        assert!((!level_2.clone()).matches(&subject));
+        assert!((!!level_1.clone()).matches(&subject));
+        assert!(matches!(
+            !!level_1,
+            ShieldtankComponentFilter::Identifier(_)
+        ));
+
+        // Not inverts the whole inner filter, so it's NAND over an And.
+        let both = ShieldtankComponentFilter::All.and(level_2);
+        assert!((!both).matches(&subject));
+    }
+
+    #[test]
+    fn deprecated_filters_match_labels() {
+        let field_instances = HashMap::default();
+        let subject = subject(&[], &field_instances);
+
+        assert!(ShieldtankComponentFilter::ByPattern("Level_1".to_string()).matches(&subject));
+        assert!(!ShieldtankComponentFilter::ByPattern("Level_2".to_string()).matches(&subject));
+        assert!(ShieldtankComponentFilter::ByList(&["world:World/Level_1"]).matches(&subject));
+        assert!(!ShieldtankComponentFilter::ByList(&["Level_1"]).matches(&subject));
+    }
+}
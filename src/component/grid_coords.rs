@@ -0,0 +1,127 @@
+use bevy_app::Plugin;
+use bevy_derive::Deref;
+use bevy_ecs::change_detection::DetectChangesMut;
+use bevy_ecs::component::Component;
+use bevy_ecs::hierarchy::ChildOf;
+use bevy_ecs::query::Changed;
+use bevy_ecs::schedule::IntoScheduleConfigs;
+use bevy_ecs::system::Query;
+use bevy_math::I64Vec2;
+use bevy_reflect::Reflect;
+use bevy_transform::TransformSystems;
+use bevy_transform::components::GlobalTransform;
+
+use crate::query::layer_grid::LayerGridQuery;
+
+use super::shieldtank_component::ShieldtankComponentSystemSet;
+
+/// The cell of its parent layer an entity occupies, with `(0, 0)` being the top left cell.
+///
+/// Kept in sync with the entity's [GlobalTransform].
+#[derive(Clone, Copy, Debug, Default, Deref, PartialEq, Eq, Hash, Component, Reflect)]
+pub struct GridCoords(#[deref] pub I64Vec2);
+
+impl GridCoords {
+    pub fn new(coords: I64Vec2) -> Self {
+        Self(coords)
+    }
+}
+
+impl From<I64Vec2> for GridCoords {
+    fn from(coords: I64Vec2) -> Self {
+        Self(coords)
+    }
+}
+
+fn grid_coords_system(
+    mut query: Query<(&mut GridCoords, &GlobalTransform, &ChildOf), Changed<GlobalTransform>>,
+    layer_grids: LayerGridQuery,
+) {
+    query
+        .iter_mut()
+        .for_each(|(mut grid_coords, global_transform, child_of)| {
+            let Some(layer_grid) = layer_grids.get(child_of.parent()) else {
+                return;
+            };
+
+            let location = global_transform.translation().truncate();
+            grid_coords.set_if_neq(GridCoords(layer_grid.world_to_grid(location)));
+        });
+}
+
+pub struct GridCoordsPlugin;
+impl Plugin for GridCoordsPlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.register_type::<GridCoords>();
+        app.add_systems(
+            ShieldtankComponentSystemSet,
+            grid_coords_system.after(TransformSystems::Propagate),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_asset::Assets;
+    use bevy_ldtk_asset::layer::LayerInstance;
+    use bevy_math::Vec3;
+    use bevy_transform::TransformPlugin;
+    use bevy_transform::components::Transform;
+
+    use crate::component::layer::ShieldtankLayer;
+    use crate::component::shieldtank_component::ShieldtankComponent;
+    use crate::test::{test_app, tiles_layer};
+
+    use super::*;
+
+    #[test]
+    fn follows_the_transform_in_the_same_frame() {
+        let mut app = test_app();
+        app.add_plugins((TransformPlugin, GridCoordsPlugin));
+
+        let layer = tiles_layer(
+            "Entities",
+            I64Vec2::new(4, 2),
+            16,
+            vec![],
+            Default::default(),
+        );
+        let layer = app
+            .world_mut()
+            .resource_mut::<Assets<LayerInstance>>()
+            .add(layer);
+
+        let layer = app
+            .world_mut()
+            .spawn((
+                ShieldtankLayer::new(layer),
+                Transform::from_xyz(100.0, 50.0, 0.0),
+            ))
+            .id();
+        let entity = app
+            .world_mut()
+            .spawn((
+                GridCoords::default(),
+                Transform::from_xyz(24.0, -24.0, 0.0),
+                ChildOf(layer),
+            ))
+            .id();
+
+        app.update();
+        assert_eq!(
+            app.world().get::<GridCoords>(entity),
+            Some(&GridCoords::new(I64Vec2::new(1, 1)))
+        );
+
+        app.world_mut()
+            .get_mut::<Transform>(entity)
+            .unwrap()
+            .translation = Vec3::new(56.0, -8.0, 0.0);
+
+        app.update();
+        assert_eq!(
+            app.world().get::<GridCoords>(entity),
+            Some(&GridCoords::new(I64Vec2::new(3, 0)))
+        );
+    }
+}
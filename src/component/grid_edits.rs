@@ -0,0 +1,458 @@
+//! Runtime editing of IntGrid layers.
+//!
+//! Edits made through [GridValuesEditor] are checked against the layer definition, written as
+//! [GridValueChanged] messages, and recorded in [ShieldtankGridEdits] so they are reapplied when
+//! the layer's grid values are rebuilt from a reloaded asset. The save state stores and restores
+//! these same edits.
+
+use bevy_app::Plugin;
+use bevy_asset::{AsAssetId, Assets};
+use bevy_ecs::component::Component;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::message::{Message, MessageWriter};
+use bevy_ecs::system::{Query, Res, SystemParam};
+use bevy_ldtk_asset::layer_definition::IntGridValue as LdtkIntGridValue;
+use bevy_ldtk_asset::layer_definition::LayerDefinition as LayerDefinitionAsset;
+use bevy_log::warn;
+use bevy_math::I64Vec2;
+use bevy_platform::collections::HashMap;
+use bevy_reflect::Reflect;
+
+use crate::result::ShieldtankResult;
+use crate::shieldtank_error;
+
+use super::grid_values::{IntGridSelector, ShieldtankGridValue, ShieldtankGridValues};
+use super::layer_definition::ShieldtankLayerDefinition;
+
+/// The cells of a layer edited at runtime, with `0` for a cleared cell.
+#[derive(Clone, Debug, Default, Component, Reflect)]
+pub struct ShieldtankGridEdits {
+    edits: HashMap<I64Vec2, i64>,
+}
+
+impl ShieldtankGridEdits {
+    pub fn get(&self, grid: I64Vec2) -> Option<i64> {
+        self.edits.get(&grid).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (I64Vec2, i64)> {
+        self.edits.iter().map(|(grid, value)| (*grid, *value))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    pub(crate) fn edits(&self) -> &HashMap<I64Vec2, i64> {
+        &self.edits
+    }
+
+    pub(crate) fn replace(&mut self, edits: HashMap<I64Vec2, i64>) {
+        self.edits = edits;
+    }
+
+    pub(crate) fn apply(
+        &self,
+        grid_values: &mut ShieldtankGridValues,
+        layer_definition: &LayerDefinitionAsset,
+    ) {
+        self.edits.iter().for_each(|(grid, value)| {
+            let grid_value = layer_definition
+                .int_grid_values
+                .get(value)
+                .map(ShieldtankGridValue::new);
+
+            if *value != 0 && grid_value.is_none() {
+                warn!("Dropping edit at {grid}, int grid value {value} no longer exists");
+                return;
+            }
+
+            grid_values.set(*grid, grid_value);
+        });
+    }
+}
+
+/// Written for every cell changed by a [GridValuesEditor].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Message)]
+pub struct GridValueChanged {
+    pub layer: Entity,
+    pub grid: I64Vec2,
+    pub old: Option<i64>,
+    pub new: Option<i64>,
+}
+
+fn resolve<'a>(
+    layer_definition: &'a LayerDefinitionAsset,
+    selector: &IntGridSelector,
+) -> ShieldtankResult<&'a LdtkIntGridValue> {
+    match selector {
+        IntGridSelector::Value(value) => layer_definition.int_grid_values.get(value),
+        IntGridSelector::Identifier(identifier) => layer_definition
+            .int_grid_values
+            .values()
+            .find(|value| value.identifier.as_deref() == Some(identifier.as_str())),
+    }
+    .ok_or(shieldtank_error!(
+        "{selector:?} is not an int grid value of layer {}",
+        layer_definition.identifier
+    ))
+}
+
+/// Edits the grid values of IntGrid layers.
+#[derive(SystemParam)]
+pub struct GridValuesEditor<'w, 's> {
+    layer_query: Query<
+        'w,
+        's,
+        (
+            &'static ShieldtankLayerDefinition,
+            &'static mut ShieldtankGridValues,
+            &'static mut ShieldtankGridEdits,
+        ),
+    >,
+    layer_definition_assets: Res<'w, Assets<LayerDefinitionAsset>>,
+    changed: MessageWriter<'w, GridValueChanged>,
+}
+
+impl GridValuesEditor<'_, '_> {
+    pub fn set(
+        &mut self,
+        layer: Entity,
+        grid: I64Vec2,
+        value: impl Into<IntGridSelector>,
+    ) -> ShieldtankResult<()> {
+        self.edit(layer, grid, grid, Some(value.into()))
+    }
+
+    pub fn clear(&mut self, layer: Entity, grid: I64Vec2) -> ShieldtankResult<()> {
+        self.edit(layer, grid, grid, None)
+    }
+
+    /// Sets every cell between two corners, inclusive.
+    pub fn fill_rect(
+        &mut self,
+        layer: Entity,
+        corner_a: I64Vec2,
+        corner_b: I64Vec2,
+        value: impl Into<IntGridSelector>,
+    ) -> ShieldtankResult<()> {
+        self.edit(layer, corner_a, corner_b, Some(value.into()))
+    }
+
+    /// Clears every cell between two corners, inclusive.
+    pub fn clear_rect(
+        &mut self,
+        layer: Entity,
+        corner_a: I64Vec2,
+        corner_b: I64Vec2,
+    ) -> ShieldtankResult<()> {
+        self.edit(layer, corner_a, corner_b, None)
+    }
+
+    fn edit(
+        &mut self,
+        layer: Entity,
+        corner_a: I64Vec2,
+        corner_b: I64Vec2,
+        value: Option<IntGridSelector>,
+    ) -> ShieldtankResult<()> {
+        let (layer_definition, mut grid_values, mut edits) = self.layer_query.get_mut(layer)?;
+
+        let layer_definition = self
+            .layer_definition_assets
+            .get(layer_definition.as_asset_id())
+            .ok_or(shieldtank_error!("layer definition of {layer} not loaded"))?;
+
+        let grid_value = value
+            .map(|value| resolve(layer_definition, &value).map(ShieldtankGridValue::new))
+            .transpose()?;
+
+        let min = corner_a.min(corner_b);
+        let max = corner_a.max(corner_b);
+        let size = grid_values.size();
+
+        if min.cmplt(I64Vec2::ZERO).any() || max.cmpge(size).any() {
+            return Err(shieldtank_error!(
+                "cells {min}..={max} are outside of layer {layer}, sized {size}"
+            ));
+        }
+
+        let new = grid_value.as_ref().map(|grid_value| grid_value.value);
+
+        (min.y..=max.y)
+            .flat_map(|y| (min.x..=max.x).map(move |x| I64Vec2::new(x, y)))
+            .for_each(|grid| {
+                let old = grid_values.get(grid).map(|grid_value| grid_value.value);
+
+                if old == new {
+                    return;
+                }
+
+                grid_values.set(grid, grid_value.clone());
+                edits.edits.insert(grid, new.unwrap_or(0));
+
+                self.changed.write(GridValueChanged {
+                    layer,
+                    grid,
+                    old,
+                    new,
+                });
+            });
+
+        Ok(())
+    }
+}
+
+pub struct GridEditsPlugin;
+impl Plugin for GridEditsPlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.register_type::<ShieldtankGridEdits>();
+        app.add_message::<GridValueChanged>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::Update;
+    use bevy_asset::Handle;
+    use bevy_ecs::message::Messages;
+    use bevy_ecs::system::RunSystemOnce;
+    use bevy_ldtk_asset::layer::LayerInstance;
+    use bevy_ldtk_asset::layer_definition::LayerDefinitionType;
+
+    use crate::component::grid_values::grid_values_system;
+    use crate::component::layer::ShieldtankLayer;
+    use crate::component::shieldtank_component::ShieldtankComponent;
+    use crate::test::{int_grid_value, layer_definition, test_app, tiles_layer};
+
+    use super::*;
+
+    const WALL: i64 = 1;
+    const WATER: i64 = 2;
+
+    struct Editor {
+        app: bevy_app::App,
+        layer: Entity,
+        layer_asset: Handle<LayerInstance>,
+        definition: Handle<LayerDefinitionAsset>,
+    }
+
+    impl Editor {
+        /// A 3 by 2 layer of walls and water, with a wall at `(0, 0)`.
+        fn new() -> Self {
+            let mut app = test_app();
+            app.add_plugins(GridEditsPlugin);
+            app.add_systems(Update, grid_values_system);
+
+            let definition = layer_definition(
+                LayerDefinitionType::IntGrid,
+                16,
+                [int_grid_value(WALL, "Wall"), int_grid_value(WATER, "Water")],
+            );
+            let definition = app
+                .world_mut()
+                .resource_mut::<Assets<LayerDefinitionAsset>>()
+                .add(definition);
+
+            let layer_asset = tiles_layer(
+                "Walls",
+                I64Vec2::new(3, 2),
+                16,
+                vec![WALL, 0, 0, 0, 0, 0],
+                definition.clone(),
+            );
+            let layer_asset = app
+                .world_mut()
+                .resource_mut::<Assets<LayerInstance>>()
+                .add(layer_asset);
+
+            let layer = app
+                .world_mut()
+                .spawn((
+                    ShieldtankLayer::new(layer_asset.clone()),
+                    ShieldtankLayerDefinition::new(definition.clone()),
+                    ShieldtankGridEdits::default(),
+                ))
+                .id();
+            app.update();
+
+            Self {
+                app,
+                layer,
+                layer_asset,
+                definition,
+            }
+        }
+
+        fn edit<T: Send + 'static>(
+            &mut self,
+            edit: impl Fn(&mut GridValuesEditor, Entity) -> T + Send + Sync + 'static,
+        ) -> T {
+            let layer = self.layer;
+            self.app
+                .world_mut()
+                .run_system_once(move |mut editor: GridValuesEditor| edit(&mut editor, layer))
+                .unwrap()
+        }
+
+        fn changes(&mut self) -> Vec<GridValueChanged> {
+            self.app
+                .world_mut()
+                .resource_mut::<Messages<GridValueChanged>>()
+                .drain()
+                .collect()
+        }
+
+        /// The values of the layer, row by row.
+        fn cells(&self) -> Vec<i64> {
+            let grid_values = self
+                .app
+                .world()
+                .get::<ShieldtankGridValues>(self.layer)
+                .unwrap();
+
+            (0..2)
+                .flat_map(|y| (0..3).map(move |x| I64Vec2::new(x, y)))
+                .map(|grid| {
+                    grid_values
+                        .get(grid)
+                        .map_or(0, |grid_value| grid_value.value)
+                })
+                .collect()
+        }
+
+        fn edits(&self) -> Vec<(I64Vec2, i64)> {
+            let mut edits = self
+                .app
+                .world()
+                .get::<ShieldtankGridEdits>(self.layer)
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>();
+            edits.sort_by_key(|(grid, _)| (grid.y, grid.x));
+            edits
+        }
+
+        /// Replaces an asset in place, and runs the frames it takes to see the change.
+        fn reload<A: bevy_asset::Asset>(&mut self, handle: &Handle<A>, asset: A) {
+            self.app
+                .world_mut()
+                .resource_mut::<Assets<A>>()
+                .insert(handle.id(), asset)
+                .unwrap();
+            self.app.update();
+            self.app.update();
+        }
+    }
+
+    #[test]
+    fn edits_change_cells_and_write_messages() {
+        let mut editor = Editor::new();
+
+        editor
+            .edit(|editor, layer| {
+                editor.set(layer, I64Vec2::new(1, 0), "Water")?;
+                editor.fill_rect(layer, I64Vec2::new(2, 1), I64Vec2::new(0, 1), WALL)?;
+                editor.set(layer, I64Vec2::new(0, 0), WALL)?;
+                editor.clear(layer, I64Vec2::new(0, 0))
+            })
+            .unwrap();
+
+        assert_eq!(editor.cells(), vec![0, WATER, 0, WALL, WALL, WALL]);
+        assert_eq!(
+            editor.edits(),
+            vec![
+                (I64Vec2::new(0, 0), 0),
+                (I64Vec2::new(1, 0), WATER),
+                (I64Vec2::new(0, 1), WALL),
+                (I64Vec2::new(1, 1), WALL),
+                (I64Vec2::new(2, 1), WALL),
+            ]
+        );
+
+        let layer = editor.layer;
+        let changed = |x, y, old, new| GridValueChanged {
+            layer,
+            grid: I64Vec2::new(x, y),
+            old,
+            new,
+        };
+        // Setting a cell to the value it already has isn't a change.
+        assert_eq!(
+            editor.changes(),
+            vec![
+                changed(1, 0, None, Some(WATER)),
+                changed(0, 1, None, Some(WALL)),
+                changed(1, 1, None, Some(WALL)),
+                changed(2, 1, None, Some(WALL)),
+                changed(0, 0, Some(WALL), None),
+            ]
+        );
+
+        editor
+            .edit(|editor, layer| editor.clear_rect(layer, I64Vec2::ZERO, I64Vec2::new(2, 1)))
+            .unwrap();
+
+        assert_eq!(editor.cells(), vec![0; 6]);
+        assert_eq!(editor.changes().len(), 4);
+    }
+
+    #[test]
+    fn rejects_unknown_values_and_cells_outside_the_layer() {
+        let mut editor = Editor::new();
+
+        let results = editor.edit(|editor, layer| {
+            [
+                editor.set(layer, I64Vec2::new(1, 0), 3),
+                editor.set(layer, I64Vec2::new(1, 0), "Lava"),
+                editor.set(layer, I64Vec2::new(3, 0), WALL),
+                editor.clear(layer, I64Vec2::new(0, -1)),
+                editor.fill_rect(layer, I64Vec2::new(1, 1), I64Vec2::new(3, 1), WATER),
+                editor.clear(Entity::PLACEHOLDER, I64Vec2::ZERO),
+            ]
+            .map(|result| result.is_err())
+        });
+
+        assert_eq!(results, [true; 6]);
+
+        // A partly outside rect changes no cells at all.
+        assert_eq!(editor.cells(), vec![WALL, 0, 0, 0, 0, 0]);
+        assert!(editor.edits().is_empty());
+        assert!(editor.changes().is_empty());
+    }
+
+    #[test]
+    fn edits_survive_reloads() {
+        let mut editor = Editor::new();
+
+        editor
+            .edit(|editor, layer| {
+                editor.clear(layer, I64Vec2::new(0, 0))?;
+                editor.set(layer, I64Vec2::new(1, 1), WATER)
+            })
+            .unwrap();
+
+        let reloaded = tiles_layer(
+            "Walls",
+            I64Vec2::new(3, 2),
+            16,
+            vec![WALL, WALL, 0, 0, 0, 0],
+            editor.definition.clone(),
+        );
+        let layer_asset = editor.layer_asset.clone();
+        editor.reload(&layer_asset, reloaded);
+
+        assert_eq!(editor.cells(), vec![0, WALL, 0, 0, WATER, 0]);
+
+        // Edits to values which no longer exist are dropped.
+        let reloaded = layer_definition(
+            LayerDefinitionType::IntGrid,
+            16,
+            [int_grid_value(WALL, "Wall")],
+        );
+        let definition = editor.definition.clone();
+        editor.reload(&definition, reloaded);
+
+        assert_eq!(editor.cells(), vec![0, WALL, 0, 0, 0, 0]);
+    }
+}
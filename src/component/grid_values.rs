@@ -15,12 +15,13 @@ use bevy_reflect::Reflect;
 
 use crate::shieldtank_error;
 
+use super::grid_edits::ShieldtankGridEdits;
 use super::layer::ShieldtankLayer;
 use super::layer_definition::ShieldtankLayerDefinition;
 use super::shieldtank_component::ShieldtankComponentSystemSet;
 use super::tile::ShieldtankTile;
 
-#[derive(Clone, Debug, Reflect)]
+#[derive(Clone, Debug, Component, Reflect)]
 pub struct ShieldtankGridValue {
     pub color: Color,
     pub identifier: Option<String>,
@@ -44,6 +45,42 @@ impl ShieldtankGridValue {
     }
 }
 
+/// Selects IntGrid values either by their numeric value, or by their identifier.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Reflect)]
+pub enum IntGridSelector {
+    Value(i64),
+    Identifier(String),
+}
+
+impl IntGridSelector {
+    pub fn matches(&self, grid_value: &ShieldtankGridValue) -> bool {
+        match self {
+            IntGridSelector::Value(value) => grid_value.value == *value,
+            IntGridSelector::Identifier(identifier) => {
+                grid_value.identifier.as_deref() == Some(identifier.as_str())
+            }
+        }
+    }
+}
+
+impl From<i64> for IntGridSelector {
+    fn from(value: i64) -> Self {
+        Self::Value(value)
+    }
+}
+
+impl From<&str> for IntGridSelector {
+    fn from(identifier: &str) -> Self {
+        Self::Identifier(identifier.to_string())
+    }
+}
+
+impl From<String> for IntGridSelector {
+    fn from(identifier: String) -> Self {
+        Self::Identifier(identifier)
+    }
+}
+
 #[derive(Debug, Component, Reflect)]
 pub struct ShieldtankGridValues {
     size: I64Vec2,
@@ -62,9 +99,10 @@ impl ShieldtankGridValues {
             .enumerate()
             .filter(|(_, i)| **i != 0)
             .map(|(index, i)| -> bevy_ecs::error::Result<_> {
+                // IntGrid values are stored row by row, so the column wraps at the layer's width.
                 let index = index as i64;
-                let x = index % size.y;
-                let y = index / size.y;
+                let x = index % size.x;
+                let y = index / size.x;
 
                 let key = I64Vec2::new(x, y);
 
@@ -86,10 +124,62 @@ impl ShieldtankGridValues {
         })
     }
 
+    #[cfg(test)]
+    pub(crate) fn from_values(
+        size: I64Vec2,
+        grid_cell_size: f32,
+        values: impl IntoIterator<Item = (I64Vec2, i64)>,
+    ) -> Self {
+        let values = values
+            .into_iter()
+            .filter(|(_, value)| *value != 0)
+            .map(|(grid, value)| {
+                let grid_value = ShieldtankGridValue {
+                    color: Color::WHITE,
+                    identifier: None,
+                    tile: None,
+                    value,
+                };
+                (grid, grid_value)
+            })
+            .collect();
+
+        Self {
+            size,
+            grid_cell_size,
+            values,
+        }
+    }
+
+    /// Grid values laid out row by row, from the top, sized to fit them.
+    #[cfg(test)]
+    pub(crate) fn from_rows<R: AsRef<[i64]>>(rows: &[R]) -> Self {
+        let size = I64Vec2::new(rows[0].as_ref().len() as i64, rows.len() as i64);
+        let values = rows.iter().enumerate().flat_map(|(y, row)| {
+            row.as_ref()
+                .iter()
+                .enumerate()
+                .map(move |(x, value)| (I64Vec2::new(x as i64, y as i64), *value))
+        });
+
+        Self::from_values(size, 16.0, values)
+    }
+
     pub fn get(&self, grid: I64Vec2) -> Option<&ShieldtankGridValue> {
         self.values.get(&grid)
     }
 
+    pub(crate) fn set(&mut self, grid: I64Vec2, value: Option<ShieldtankGridValue>) {
+        match value {
+            Some(value) => self.values.insert(grid, value),
+            None => self.values.remove(&grid),
+        };
+    }
+
+    pub fn size(&self) -> I64Vec2 {
+        self.size
+    }
+
     pub fn grid_cell_size(&self) -> f32 {
         self.grid_cell_size
     }
@@ -102,7 +192,12 @@ impl ShieldtankGridValues {
 #[allow(clippy::type_complexity)]
 pub(crate) fn grid_values_system(
     query: Query<
-        (Entity, &ShieldtankLayer, &ShieldtankLayerDefinition),
+        (
+            Entity,
+            &ShieldtankLayer,
+            &ShieldtankLayerDefinition,
+            Option<&ShieldtankGridEdits>,
+        ),
         Or<(
             Changed<ShieldtankLayer>,
             AssetChanged<ShieldtankLayer>,
@@ -116,27 +211,34 @@ pub(crate) fn grid_values_system(
 ) -> bevy_ecs::error::Result<()> {
     query
         .iter()
-        .filter_map(|(entity, component, layer_definition)| {
+        .filter_map(|(entity, component, layer_definition, edits)| {
             Some((
                 entity,
                 component_assets.get(component.as_asset_id())?,
                 layer_definition_assets.get(layer_definition.as_asset_id())?,
+                edits,
             ))
         })
-        .filter_map(|(entity, layer, layer_definition)| {
+        .filter_map(|(entity, layer, layer_definition, edits)| {
             Some((
                 entity,
                 layer,
                 layer.layer_type.get_tiles_layer()?,
                 layer_definition,
+                edits,
             ))
         })
         .try_for_each(
-            |(entity, layer, tiles_layer, ldtk_layer_definition)| -> bevy_ecs::error::Result<()> {
+            |(entity, layer, tiles_layer, ldtk_layer_definition, edits)| -> bevy_ecs::error::Result<()> {
                 let size = layer.grid_size;
                 let int_grid = tiles_layer.int_grid.as_slice();
 
-                let grid_values = ShieldtankGridValues::new(size, int_grid, ldtk_layer_definition)?;
+                let mut grid_values =
+                    ShieldtankGridValues::new(size, int_grid, ldtk_layer_definition)?;
+
+                if let Some(edits) = edits {
+                    edits.apply(&mut grid_values, ldtk_layer_definition);
+                }
 
                 commands.entity(entity).insert(grid_values);
 
@@ -148,7 +250,110 @@ pub(crate) fn grid_values_system(
 pub struct GridValuesPlugin;
 impl Plugin for GridValuesPlugin {
     fn build(&self, app: &mut bevy_app::App) {
+        app.register_type::<ShieldtankGridValue>();
         app.register_type::<ShieldtankGridValues>();
+        app.register_type::<IntGridSelector>();
         app.add_systems(ShieldtankComponentSystemSet, grid_values_system);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::Update;
+    use bevy_ldtk_asset::layer_definition::LayerDefinitionType;
+
+    use crate::component::shieldtank_component::ShieldtankComponent;
+    use crate::test::{int_grid_value, layer_definition, test_app, tiles_layer};
+
+    use super::*;
+
+    #[test]
+    fn cells_of_non_square_layers_wrap_at_the_width() {
+        let definition = layer_definition(
+            LayerDefinitionType::IntGrid,
+            16,
+            [int_grid_value(1, "Wall"), int_grid_value(2, "Water")],
+        );
+
+        #[rustfmt::skip]
+        let int_grid = [
+            1, 0, 0, 0,
+            0, 0, 0, 2,
+        ];
+
+        let grid_values =
+            ShieldtankGridValues::new(I64Vec2::new(4, 2), &int_grid, &definition).unwrap();
+
+        let mut cells: Vec<_> = grid_values
+            .enumerate()
+            .map(|(grid, value)| (grid, value.value))
+            .collect();
+        cells.sort_by_key(|(grid, _)| (grid.y, grid.x));
+
+        assert_eq!(
+            cells,
+            vec![(I64Vec2::new(0, 0), 1), (I64Vec2::new(3, 1), 2)]
+        );
+    }
+
+    #[test]
+    fn pure_int_grid_layers_get_grid_values() {
+        let mut app = test_app();
+        app.add_systems(Update, grid_values_system);
+
+        let definition = layer_definition(
+            LayerDefinitionType::IntGrid,
+            16,
+            [int_grid_value(1, "Wall")],
+        );
+        let definition = app
+            .world_mut()
+            .resource_mut::<Assets<LayerDefinitionAsset>>()
+            .add(definition);
+
+        // No tiles or tileset, only the int grid.
+        #[rustfmt::skip]
+        let int_grid = vec![
+            1, 0, 0,
+            0, 0, 1,
+        ];
+        let layer = tiles_layer(
+            "Walls",
+            I64Vec2::new(3, 2),
+            16,
+            int_grid,
+            definition.clone(),
+        );
+        let layer = app
+            .world_mut()
+            .resource_mut::<Assets<LayerInstance>>()
+            .add(layer);
+
+        let entity = app
+            .world_mut()
+            .spawn((
+                ShieldtankLayer::new(layer),
+                ShieldtankLayerDefinition::new(definition),
+            ))
+            .id();
+
+        app.update();
+
+        let grid_values = app
+            .world()
+            .get::<ShieldtankGridValues>(entity)
+            .expect("grid values of the IntGrid layer");
+
+        assert_eq!(grid_values.size(), I64Vec2::new(3, 2));
+        assert_eq!(grid_values.enumerate().count(), 2);
+        assert_eq!(
+            grid_values
+                .get(I64Vec2::new(0, 0))
+                .unwrap()
+                .identifier
+                .as_deref(),
+            Some("Wall")
+        );
+        assert_eq!(grid_values.get(I64Vec2::new(2, 1)).unwrap().value, 1);
+    }
+}
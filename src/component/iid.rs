@@ -2,15 +2,24 @@ use bevy_app::Plugin;
 use bevy_derive::Deref;
 use bevy_ecs::component::Component;
 use bevy_ecs::entity::Entity;
+use bevy_ecs::hierarchy::ChildOf;
 use bevy_ecs::lifecycle::RemovedComponents;
-use bevy_ecs::query::Added;
+use bevy_ecs::query::{Added, Or, With};
 use bevy_ecs::resource::Resource;
+use bevy_ecs::schedule::IntoScheduleConfigs;
 use bevy_ecs::system::{Query, ResMut};
 use bevy_ldtk_asset::iid::Iid;
+use bevy_log::warn;
 use bevy_platform::collections::HashMap;
 use bevy_reflect::Reflect;
+use itertools::Itertools;
 
+use crate::component::entity::ShieldtankEntity;
+use crate::component::layer::ShieldtankLayer;
+use crate::component::level::ShieldtankLevel;
+use crate::component::project::LdtkProject;
 use crate::component::shieldtank_component::ShieldtankComponentSystemSet;
+use crate::component::world::ShieldtankWorld;
 
 #[derive(Clone, Copy, Debug, Deref, PartialEq, Eq, Component, Reflect)]
 #[component(immutable)]
@@ -40,17 +49,118 @@ impl From<Iid> for ShieldtankIid {
     }
 }
 
+/// Maps [Iid]s to entities, separately for each spawned root.
+///
+/// A root is the topmost project, world, level, layer or entity in a hierarchy, so spawning the
+/// same project or level more than once gives each copy its own scope. When an [Iid] is duplicated
+/// within a root, the first entity is kept, and the next one takes its place once it's removed.
 #[derive(Debug, Default, Reflect, Resource)]
-pub(crate) struct IidRegistry {
-    pub(crate) registry: HashMap<Iid, Entity>,
+pub struct IidRegistry {
+    roots: HashMap<Entity, HashMap<Iid, Entity>>,
+    entities: HashMap<Entity, (Entity, Iid)>,
+    duplicates: HashMap<(Entity, Iid), Vec<Entity>>,
 }
 
-fn iid_added(
+impl IidRegistry {
+    /// The entity with this [Iid] under `root`.
+    pub fn get(&self, root: Entity, iid: Iid) -> Option<Entity> {
+        self.roots.get(&root)?.get(&iid).copied()
+    }
+
+    /// The entity with this [Iid] in the same root as `entity`.
+    pub fn get_in_root_of(&self, entity: Entity, iid: Iid) -> Option<Entity> {
+        self.get(self.root_of(entity)?, iid)
+    }
+
+    /// Every entity with this [Iid], one per root holding it.
+    pub fn get_all(&self, iid: Iid) -> impl Iterator<Item = Entity> {
+        self.roots
+            .values()
+            .filter_map(move |registry| registry.get(&iid).copied())
+    }
+
+    /// The entity with this [Iid], if exactly one root holds it.
+    pub fn get_unique(&self, iid: Iid) -> Option<Entity> {
+        self.get_all(iid).exactly_one().ok()
+    }
+
+    pub fn root_of(&self, entity: Entity) -> Option<Entity> {
+        self.entities.get(&entity).map(|(root, _)| *root)
+    }
+
+    pub fn roots(&self) -> impl Iterator<Item = Entity> {
+        self.roots.keys().copied()
+    }
+
+    fn insert(&mut self, root: Entity, iid: Iid, entity: Entity) {
+        let registry = self.roots.entry(root).or_default();
+
+        match registry.get(&iid) {
+            Some(existing) if *existing != entity => {
+                warn!(
+                    "Duplicate Iid {iid} under root {root}: keeping {existing}, ignoring {entity}"
+                );
+                self.duplicates.entry((root, iid)).or_default().push(entity);
+            }
+            _ => {
+                registry.insert(iid, entity);
+            }
+        }
+
+        self.entities.insert(entity, (root, iid));
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        let Some((root, iid)) = self.entities.remove(&entity) else {
+            return;
+        };
+
+        let Some(registry) = self.roots.get_mut(&root) else {
+            return;
+        };
+
+        let duplicates = self.duplicates.entry((root, iid)).or_default();
+        duplicates.retain(|duplicate| *duplicate != entity);
+
+        if registry.get(&iid) == Some(&entity) {
+            match duplicates.is_empty() {
+                true => registry.remove(&iid),
+                false => registry.insert(iid, duplicates.remove(0)),
+            };
+        }
+
+        if duplicates.is_empty() {
+            self.duplicates.remove(&(root, iid));
+        }
+
+        if registry.is_empty() {
+            self.roots.remove(&root);
+        }
+    }
+}
+
+type ShieldtankHierarchyFilter = Or<(
+    With<LdtkProject>,
+    With<ShieldtankWorld>,
+    With<ShieldtankLevel>,
+    With<ShieldtankLayer>,
+    With<ShieldtankEntity>,
+)>;
+
+pub(crate) fn iid_added(
     added_query: Query<(Entity, &ShieldtankIid), Added<ShieldtankIid>>,
+    parent_query: Query<&ChildOf>,
+    hierarchy_query: Query<(), ShieldtankHierarchyFilter>,
     mut iid_registry: ResMut<IidRegistry>,
 ) {
     added_query.iter().for_each(|(entity, &iid)| {
-        iid_registry.registry.insert(*iid, entity);
+        let root = parent_query
+            .iter_ancestors(entity)
+            .filter(|ancestor| hierarchy_query.contains(*ancestor))
+            .last()
+            .unwrap_or(entity);
+
+        iid_registry.insert(root, *iid, entity);
     });
 }
 
@@ -59,9 +169,7 @@ fn iid_removed(
     mut iid_registry: ResMut<IidRegistry>,
 ) {
     removed.read().for_each(|removed_entity| {
-        iid_registry
-            .registry
-            .retain(|_, entity| *entity != removed_entity)
+        iid_registry.remove(removed_entity);
     });
 }
 
@@ -71,6 +179,121 @@ impl Plugin for IidPlugin {
         app.register_type::<ShieldtankIid>()
             .insert_resource(IidRegistry::default())
             .register_type::<IidRegistry>()
-            .add_systems(ShieldtankComponentSystemSet, (iid_added, iid_removed));
+            .add_systems(
+                ShieldtankComponentSystemSet,
+                // Removals first, so a respawned Iid isn't reported as a duplicate.
+                (iid_removed, iid_added).chain(),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::Update;
+
+    use crate::test::test_app;
+
+    use super::*;
+
+    fn app() -> bevy_app::App {
+        let mut app = test_app();
+        app.init_resource::<IidRegistry>();
+        app.add_systems(Update, (iid_removed, iid_added).chain());
+        app
+    }
+
+    fn spawn_root(app: &mut bevy_app::App, iid: u128) -> Entity {
+        app.world_mut()
+            .spawn((
+                LdtkProject::default(),
+                ShieldtankIid::new(Iid::from_u128(iid)),
+            ))
+            .id()
+    }
+
+    fn spawn_child(app: &mut bevy_app::App, parent: Entity, iid: u128) -> Entity {
+        app.world_mut()
+            .spawn((ShieldtankIid::new(Iid::from_u128(iid)), ChildOf(parent)))
+            .id()
+    }
+
+    fn iid_registry(app: &bevy_app::App) -> &IidRegistry {
+        app.world().resource::<IidRegistry>()
+    }
+
+    #[test]
+    fn scopes_iids_to_their_root() {
+        let mut app = app();
+        let first_root = spawn_root(&mut app, 1);
+        let first = spawn_child(&mut app, first_root, 2);
+        let second_root = spawn_root(&mut app, 1);
+        let second = spawn_child(&mut app, second_root, 2);
+        app.update();
+
+        let iid = Iid::from_u128(2);
+        let registry = iid_registry(&app);
+        assert_eq!(registry.get(first_root, iid), Some(first));
+        assert_eq!(registry.get(second_root, iid), Some(second));
+        assert_eq!(registry.get_in_root_of(second, iid), Some(second));
+        assert_eq!(registry.root_of(first), Some(first_root));
+        assert_eq!(registry.get_all(iid).count(), 2);
+        assert_eq!(registry.get_unique(iid), None);
+        assert_eq!(registry.roots().count(), 2);
+
+        app.world_mut().entity_mut(second_root).despawn();
+        app.update();
+
+        let registry = iid_registry(&app);
+        assert_eq!(registry.get_unique(iid), Some(first));
+        assert_eq!(registry.roots().collect::<Vec<_>>(), vec![first_root]);
+    }
+
+    #[test]
+    fn keeps_the_first_duplicate_in_a_root() {
+        let mut app = app();
+        let root = spawn_root(&mut app, 1);
+        let kept = spawn_child(&mut app, root, 2);
+        app.update();
+        let ignored = spawn_child(&mut app, root, 2);
+        app.update();
+
+        let iid = Iid::from_u128(2);
+        assert_eq!(iid_registry(&app).get(root, iid), Some(kept));
+
+        app.world_mut().entity_mut(ignored).despawn();
+        app.update();
+        assert_eq!(iid_registry(&app).get(root, iid), Some(kept));
+
+        app.world_mut().entity_mut(kept).despawn();
+        app.update();
+        assert_eq!(iid_registry(&app).get(root, iid), None);
+    }
+
+    #[test]
+    fn promotes_a_duplicate_when_the_kept_entity_is_removed() {
+        let mut app = app();
+        let root = spawn_root(&mut app, 1);
+        let kept = spawn_child(&mut app, root, 2);
+        app.update();
+        let first_duplicate = spawn_child(&mut app, root, 2);
+        app.update();
+        let second_duplicate = spawn_child(&mut app, root, 2);
+        app.update();
+
+        let iid = Iid::from_u128(2);
+
+        app.world_mut().entity_mut(kept).despawn();
+        app.update();
+        assert_eq!(iid_registry(&app).get(root, iid), Some(first_duplicate));
+        assert_eq!(iid_registry(&app).root_of(first_duplicate), Some(root));
+
+        app.world_mut().entity_mut(first_duplicate).despawn();
+        app.update();
+        assert_eq!(iid_registry(&app).get(root, iid), Some(second_duplicate));
+
+        app.world_mut().entity_mut(second_duplicate).despawn();
+        app.update();
+        assert_eq!(iid_registry(&app).get(root, iid), None);
+        assert!(iid_registry(&app).duplicates.is_empty());
     }
 }
@@ -0,0 +1,351 @@
+use bevy_app::{App, Plugin};
+use bevy_asset::{AsAssetId, Assets};
+use bevy_camera::visibility::Visibility;
+use bevy_ecs::bundle::Bundle;
+use bevy_ecs::component::Component;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::hierarchy::Children;
+use bevy_ecs::query::{Changed, With};
+use bevy_ecs::resource::Resource;
+use bevy_ecs::schedule::IntoScheduleConfigs;
+use bevy_ecs::system::{Commands, EntityCommands, Query, Res};
+use bevy_ldtk_asset::layer::LayerInstance;
+use bevy_ldtk_asset::prelude::LdtkAsset;
+use bevy_math::{I64Vec2, Vec2};
+use bevy_platform::collections::HashMap;
+use bevy_reflect::Reflect;
+use bevy_transform::components::Transform;
+
+use super::grid_coords::GridCoords;
+use super::grid_values::{
+    IntGridSelector, ShieldtankGridValue, ShieldtankGridValues, grid_values_system,
+};
+use super::layer::ShieldtankLayer;
+use super::shieldtank_component::ShieldtankComponentSystemSet;
+
+/// A [Bundle] which can be built from a single IntGrid cell.
+///
+/// Register it with [IntGridCellAppExt::register_int_grid_cell].
+pub trait LdtkIntGridCell: Bundle {
+    fn from_int_grid_cell(coords: I64Vec2, grid_value: &ShieldtankGridValue) -> Self;
+}
+
+/// Marks the child entities spawned for registered IntGrid cells.
+#[derive(Clone, Copy, Debug, Default, Component, Reflect)]
+#[require(Transform, Visibility)]
+pub struct ShieldtankIntGridCell;
+
+type IntGridCellBuilder =
+    Box<dyn Fn(I64Vec2, &ShieldtankGridValue, &mut EntityCommands) + Send + Sync>;
+
+struct IntGridCellRegistration {
+    layer_identifier: String,
+    selector: IntGridSelector,
+    builder: IntGridCellBuilder,
+}
+
+#[derive(Default, Resource)]
+pub struct IntGridCellRegistry {
+    registrations: Vec<IntGridCellRegistration>,
+}
+
+impl IntGridCellRegistry {
+    fn for_layer<'a>(
+        &'a self,
+        layer_identifier: &'a str,
+    ) -> impl Iterator<Item = &'a IntGridCellRegistration> {
+        self.registrations
+            .iter()
+            .filter(move |registration| registration.layer_identifier == layer_identifier)
+    }
+}
+
+pub trait IntGridCellAppExt {
+    /// Spawns a child entity with `B` for every cell of the IntGrid layer `layer_identifier`
+    /// matching `selector`.
+    fn register_int_grid_cell<B: LdtkIntGridCell>(
+        &mut self,
+        layer_identifier: impl Into<String>,
+        selector: impl Into<IntGridSelector>,
+    ) -> &mut Self;
+}
+
+impl IntGridCellAppExt for App {
+    fn register_int_grid_cell<B: LdtkIntGridCell>(
+        &mut self,
+        layer_identifier: impl Into<String>,
+        selector: impl Into<IntGridSelector>,
+    ) -> &mut Self {
+        let registration = IntGridCellRegistration {
+            layer_identifier: layer_identifier.into(),
+            selector: selector.into(),
+            builder: Box::new(|coords, grid_value, commands| {
+                commands.insert(B::from_int_grid_cell(coords, grid_value));
+            }),
+        };
+
+        self.world_mut()
+            .get_resource_or_init::<IntGridCellRegistry>()
+            .registrations
+            .push(registration);
+        self
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn int_grid_cell_system(
+    query: Query<
+        (
+            Entity,
+            &ShieldtankLayer,
+            &ShieldtankGridValues,
+            Option<&Children>,
+        ),
+        Changed<ShieldtankGridValues>,
+    >,
+    cell_query: Query<(&GridCoords, &ShieldtankGridValue), With<ShieldtankIntGridCell>>,
+    layer_assets: Res<Assets<LayerInstance>>,
+    registry: Res<IntGridCellRegistry>,
+    mut commands: Commands,
+) {
+    query
+        .iter()
+        .filter_map(|(entity, component, grid_values, children)| {
+            Some((
+                entity,
+                layer_assets.get(component.as_asset_id())?,
+                grid_values,
+                children,
+            ))
+        })
+        .for_each(|(entity, asset, grid_values, children)| {
+            let registrations: Vec<_> = registry.for_layer(asset.get_identifier()).collect();
+
+            let mut spawned_cells: HashMap<I64Vec2, Entity> = HashMap::new();
+
+            children
+                .into_iter()
+                .flatten()
+                .copied()
+                .filter_map(|child| Some((child, cell_query.get(child).ok()?)))
+                .for_each(|(child, (coords, cell_value))| {
+                    let unchanged = grid_values
+                        .get(**coords)
+                        .is_some_and(|grid_value| grid_value.value == cell_value.value);
+
+                    if unchanged {
+                        spawned_cells.insert(**coords, child);
+                    } else {
+                        commands.entity(child).despawn();
+                    }
+                });
+
+            if registrations.is_empty() {
+                return;
+            }
+
+            let grid_cell_size = grid_values.grid_cell_size();
+
+            grid_values
+                .enumerate()
+                .filter(|(coords, _)| !spawned_cells.contains_key(coords))
+                .for_each(|(coords, grid_value)| {
+                    let mut matching = registrations
+                        .iter()
+                        .filter(|registration| registration.selector.matches(grid_value))
+                        .peekable();
+
+                    if matching.peek().is_none() {
+                        return;
+                    }
+
+                    let center = (coords.as_vec2() + 0.5) * grid_cell_size;
+                    let translation = (Vec2::new(1.0, -1.0) * center).extend(0.0);
+
+                    let mut cell_commands = commands.spawn((
+                        ShieldtankIntGridCell,
+                        GridCoords::new(coords),
+                        grid_value.clone(),
+                        Transform::from_translation(translation),
+                    ));
+
+                    matching.for_each(|registration| {
+                        (registration.builder)(coords, grid_value, &mut cell_commands);
+                    });
+
+                    let cell = cell_commands.id();
+                    commands.entity(entity).add_child(cell);
+                });
+        });
+}
+
+pub struct IntGridCellPlugin;
+impl Plugin for IntGridCellPlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.register_type::<ShieldtankIntGridCell>();
+        app.init_resource::<IntGridCellRegistry>();
+        app.add_systems(
+            ShieldtankComponentSystemSet,
+            int_grid_cell_system.after(grid_values_system),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::Update;
+    use bevy_ecs::hierarchy::ChildOf;
+    use bevy_ldtk_asset::layer_definition::LayerDefinitionType;
+    use bevy_math::Vec3;
+
+    use crate::component::shieldtank_component::ShieldtankComponent;
+    use crate::test::{int_grid_value, layer_definition, test_app, tiles_layer};
+
+    use super::*;
+
+    #[derive(Component)]
+    struct Wall(I64Vec2);
+
+    impl LdtkIntGridCell for Wall {
+        fn from_int_grid_cell(coords: I64Vec2, _: &ShieldtankGridValue) -> Self {
+            Self(coords)
+        }
+    }
+
+    #[derive(Component)]
+    struct Water;
+
+    impl LdtkIntGridCell for Water {
+        fn from_int_grid_cell(_: I64Vec2, _: &ShieldtankGridValue) -> Self {
+            Self
+        }
+    }
+
+    fn grid_values(int_grid: &[i64]) -> ShieldtankGridValues {
+        let definition = layer_definition(
+            LayerDefinitionType::IntGrid,
+            16,
+            [
+                int_grid_value(1, "Wall"),
+                int_grid_value(2, "Water"),
+                int_grid_value(3, "Lava"),
+            ],
+        );
+
+        ShieldtankGridValues::new(I64Vec2::new(3, 2), int_grid, &definition).unwrap()
+    }
+
+    fn app() -> bevy_app::App {
+        let mut app = test_app();
+        app.init_resource::<IntGridCellRegistry>();
+        app.register_int_grid_cell::<Wall>("Walls", "Wall");
+        app.register_int_grid_cell::<Water>("Walls", 2);
+        app.add_systems(Update, int_grid_cell_system);
+        app
+    }
+
+    fn spawn_layer(app: &mut bevy_app::App, identifier: &str, int_grid: &[i64]) -> Entity {
+        let layer = tiles_layer(
+            identifier,
+            I64Vec2::new(3, 2),
+            16,
+            int_grid.to_vec(),
+            Default::default(),
+        );
+        let layer = app
+            .world_mut()
+            .resource_mut::<Assets<LayerInstance>>()
+            .add(layer);
+
+        let entity = app
+            .world_mut()
+            .spawn((ShieldtankLayer::new(layer), grid_values(int_grid)))
+            .id();
+        app.update();
+
+        entity
+    }
+
+    /// The cells spawned under `layer`, by their coordinates.
+    fn cells(app: &mut bevy_app::App, layer: Entity) -> HashMap<I64Vec2, Entity> {
+        app.world_mut()
+            .query_filtered::<(Entity, &GridCoords, &ChildOf), With<ShieldtankIntGridCell>>()
+            .iter(app.world())
+            .filter(|(_, _, child_of)| child_of.parent() == layer)
+            .map(|(entity, coords, _)| (**coords, entity))
+            .collect()
+    }
+
+    #[test]
+    fn spawns_cells_matching_a_registration() {
+        let mut app = app();
+
+        #[rustfmt::skip]
+        let layer = spawn_layer(&mut app, "Walls", &[
+            1, 0, 3,
+            0, 2, 0,
+        ]);
+
+        let cells = cells(&mut app, layer);
+        assert_eq!(cells.len(), 2);
+
+        let wall = app.world().entity(cells[&I64Vec2::new(0, 0)]);
+        assert_eq!(wall.get::<Wall>().unwrap().0, I64Vec2::new(0, 0));
+        assert!(!wall.contains::<Water>());
+        assert_eq!(wall.get::<ShieldtankGridValue>().unwrap().value, 1);
+
+        let water = app.world().entity(cells[&I64Vec2::new(1, 1)]);
+        assert!(water.contains::<Water>());
+        assert_eq!(
+            water.get::<Transform>().unwrap().translation,
+            Vec3::new(24.0, -24.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn ignores_layers_without_registrations() {
+        let mut app = app();
+        let layer = spawn_layer(&mut app, "Other", &[1, 1, 1, 2, 2, 2]);
+
+        assert!(cells(&mut app, layer).is_empty());
+    }
+
+    #[test]
+    fn respawns_only_the_cells_which_changed() {
+        let mut app = app();
+
+        #[rustfmt::skip]
+        let layer = spawn_layer(&mut app, "Walls", &[
+            1, 1, 0,
+            0, 2, 0,
+        ]);
+        let before = cells(&mut app, layer);
+
+        #[rustfmt::skip]
+        let int_grid = [
+            1, 2, 0,
+            0, 3, 1,
+        ];
+        app.world_mut()
+            .entity_mut(layer)
+            .insert(grid_values(&int_grid));
+        app.update();
+
+        let after = cells(&mut app, layer);
+        assert_eq!(after.len(), 3);
+        assert_eq!(after[&I64Vec2::new(0, 0)], before[&I64Vec2::new(0, 0)]);
+        assert!(app.world().get_entity(before[&I64Vec2::new(1, 0)]).is_err());
+        assert!(
+            app.world()
+                .entity(after[&I64Vec2::new(1, 0)])
+                .contains::<Water>()
+        );
+        assert!(app.world().get_entity(before[&I64Vec2::new(1, 1)]).is_err());
+        assert!(!after.contains_key(&I64Vec2::new(1, 1)));
+        assert!(
+            app.world()
+                .entity(after[&I64Vec2::new(2, 1)])
+                .contains::<Wall>()
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Draws IntGrid cells in their LDtk colours, the way the editor shows IntGrid layers.
+
+use bevy_app::Plugin;
+use bevy_asset::prelude::AssetChanged;
+use bevy_asset::{AsAssetId, Assets, RenderAssetUsages};
+use bevy_color::ColorToPacked;
+use bevy_ecs::component::Component;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::lifecycle::RemovedComponents;
+use bevy_ecs::query::{Changed, Or, With, Without};
+use bevy_ecs::schedule::IntoScheduleConfigs;
+use bevy_ecs::system::{Commands, Query, Res, ResMut};
+use bevy_image::{Image, ImageSampler};
+use bevy_ldtk_asset::layer::LayerInstance;
+use bevy_reflect::Reflect;
+use bevy_sprite::{Anchor, Sprite};
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use super::grid_values::{ShieldtankGridValues, grid_values_system};
+use super::layer::ShieldtankLayer;
+use super::layer_tiles::LdtkLayerTiles;
+use super::shieldtank_component::ShieldtankComponentSystemSet;
+
+/// Add to an IntGrid layer to draw its cells as coloured squares. Layers with [LdtkLayerTiles]
+/// keep drawing their tiles instead.
+///
+/// `opacity` is multiplied with the layer's own opacity from LDtk.
+#[derive(Clone, Debug, Component, Reflect)]
+pub struct ShieldtankIntGridRendering {
+    pub opacity: f32,
+}
+
+impl Default for ShieldtankIntGridRendering {
+    fn default() -> Self {
+        Self { opacity: 1.0 }
+    }
+}
+
+impl ShieldtankIntGridRendering {
+    /// One pixel per cell, to be drawn scaled up with a nearest neighbour sampler.
+    pub(crate) fn generate_image(&self, grid_values: &ShieldtankGridValues, opacity: f32) -> Image {
+        let size = grid_values.size().as_uvec2();
+
+        let mut new_image = RgbaImage::new(size.x, size.y);
+
+        let opacity = (self.opacity * opacity).clamp(0.0, 1.0);
+
+        grid_values.enumerate().for_each(|(grid, grid_value)| {
+            let mut color = grid_value.color.to_srgba();
+            color.alpha *= opacity;
+            let pixel = Rgba(color.to_u8_array());
+
+            let grid = grid.as_uvec2();
+            new_image.put_pixel(grid.x, grid.y, pixel);
+        });
+
+        let new_image = DynamicImage::from(new_image);
+
+        let mut image = Image::from_dynamic(new_image, true, RenderAssetUsages::default());
+        image.sampler = ImageSampler::nearest();
+        image
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn int_grid_rendering_system(
+    query: Query<
+        (
+            Entity,
+            &ShieldtankLayer,
+            &ShieldtankGridValues,
+            &ShieldtankIntGridRendering,
+        ),
+        (
+            Or<(
+                Changed<ShieldtankGridValues>,
+                Changed<ShieldtankIntGridRendering>,
+                AssetChanged<ShieldtankLayer>,
+            )>,
+            Without<LdtkLayerTiles>,
+        ),
+    >,
+    layer_assets: Res<Assets<LayerInstance>>,
+    mut images: ResMut<Assets<Image>>,
+    mut commands: Commands,
+) {
+    query
+        .iter()
+        .for_each(|(entity, component, grid_values, rendering)| {
+            // The layer's opacity, as set on its definition in LDtk.
+            let opacity = layer_assets
+                .get(component.as_asset_id())
+                .map_or(1.0, |layer_asset| layer_asset.opacity as f32);
+
+            let image = images.add(rendering.generate_image(grid_values, opacity));
+            let size = grid_values.size().as_vec2() * grid_values.grid_cell_size();
+            let anchor = Anchor::TOP_LEFT;
+            let sprite = Sprite {
+                image,
+                custom_size: Some(size),
+                ..Default::default()
+            };
+
+            commands.entity(entity).insert((sprite, anchor));
+        });
+}
+
+fn int_grid_rendering_removed_system(
+    mut removed: RemovedComponents<ShieldtankIntGridRendering>,
+    layer_tiles_query: Query<(), With<LdtkLayerTiles>>,
+    mut commands: Commands,
+) {
+    removed.read().for_each(|entity| {
+        if layer_tiles_query.contains(entity) {
+            return;
+        }
+
+        if let Ok(mut entity_commands) = commands.get_entity(entity) {
+            entity_commands.remove::<Sprite>();
+        }
+    });
+}
+
+pub struct IntGridRenderingPlugin;
+impl Plugin for IntGridRenderingPlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.register_type::<ShieldtankIntGridRendering>();
+        app.add_systems(
+            ShieldtankComponentSystemSet,
+            (
+                int_grid_rendering_system.after(grid_values_system),
+                int_grid_rendering_removed_system,
+            ),
+        );
+    }
+}
@@ -0,0 +1,662 @@
+//! Tile based kinematic movement against IntGrid layers, for games which don't need a physics
+//! engine.
+//!
+//! Bodies move by their velocity each frame, one axis at a time, stopping at solid cells of any
+//! IntGrid layer. Which cells are solid, one-way platforms or ladders is configured with the
+//! [ShieldtankKinematicTerrain] resource. [KinematicStepped] is triggered on bodies as they move.
+//!
+//! Bodies move before transform propagation, from the location their [Transform] and their
+//! parent's [GlobalTransform] put them at, so changes made to the [Transform] earlier in the frame
+//! are seen. A parent which itself moves this frame is seen where it was last frame.
+
+use bevy_app::Plugin;
+use bevy_asset::{AsAssetId, Assets};
+use bevy_ecs::component::Component;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::event::EntityEvent;
+use bevy_ecs::hierarchy::ChildOf;
+use bevy_ecs::query::With;
+use bevy_ecs::resource::Resource;
+use bevy_ecs::schedule::IntoScheduleConfigs;
+use bevy_ecs::system::{Commands, Query, Res};
+use bevy_ldtk_asset::entity::EntityInstance;
+use bevy_math::{I64Vec2, Rect, Vec2};
+use bevy_reflect::Reflect;
+use bevy_time::Time;
+use bevy_transform::TransformSystems;
+use bevy_transform::components::{GlobalTransform, Transform};
+
+use crate::query::layer_grid::{LayerGrid, LayerGridQuery};
+
+use super::entity::{ShieldtankEntity, entity_local_bounds};
+use super::grid_values::{IntGridSelector, ShieldtankGridValue, ShieldtankGridValues};
+use super::layer::ShieldtankLayer;
+use super::shieldtank_component::ShieldtankComponentSystemSet;
+
+/// Slack for float error when a body rests exactly against a cell.
+const EPSILON: f32 = 0.001;
+/// How far to look for contacts around a body.
+const PROBE_DISTANCE: f32 = 0.01;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TerrainKind {
+    Solid,
+    OneWay,
+    Ladder,
+}
+
+/// Selects which IntGrid values kinematic bodies collide with.
+#[derive(Clone, Debug, Default, Resource, Reflect)]
+pub struct ShieldtankKinematicTerrain {
+    pub solid: Vec<IntGridSelector>,
+    /// Only block bodies falling onto them from above.
+    pub one_way: Vec<IntGridSelector>,
+    /// Never block, but are reported in [KinematicContacts::on_ladder].
+    pub ladders: Vec<IntGridSelector>,
+}
+
+impl ShieldtankKinematicTerrain {
+    pub fn with_solid(mut self, selector: impl Into<IntGridSelector>) -> Self {
+        self.solid.push(selector.into());
+        self
+    }
+
+    pub fn with_one_way(mut self, selector: impl Into<IntGridSelector>) -> Self {
+        self.one_way.push(selector.into());
+        self
+    }
+
+    pub fn with_ladder(mut self, selector: impl Into<IntGridSelector>) -> Self {
+        self.ladders.push(selector.into());
+        self
+    }
+
+    fn kind(&self, grid_value: &ShieldtankGridValue) -> Option<TerrainKind> {
+        let matches = |selectors: &[IntGridSelector]| {
+            selectors
+                .iter()
+                .any(|selector| selector.matches(grid_value))
+        };
+
+        if matches(&self.solid) {
+            Some(TerrainKind::Solid)
+        } else if matches(&self.one_way) {
+            Some(TerrainKind::OneWay)
+        } else if matches(&self.ladders) {
+            Some(TerrainKind::Ladder)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub struct KinematicContacts {
+    pub grounded: bool,
+    pub wall_left: bool,
+    pub wall_right: bool,
+    pub ceiling: bool,
+    pub on_ladder: bool,
+}
+
+#[derive(Clone, Debug, Default, Component, Reflect)]
+#[require(Transform)]
+pub struct ShieldtankKinematicBody {
+    /// In world units per second. The component of the velocity which runs into a cell is zeroed.
+    pub velocity: Vec2,
+    /// Falls through one-way platforms while set.
+    pub drop_through: bool,
+    /// The collision box, relative to the entity's translation. When `None`, the box of the LDtk
+    /// entity is used, which is recomputed every step and matches its
+    /// [ShieldtankWorldBounds](super::world_bounds::ShieldtankWorldBounds).
+    pub collider_override: Option<Rect>,
+    collider: Option<Rect>,
+    contacts: KinematicContacts,
+}
+
+impl ShieldtankKinematicBody {
+    pub fn contacts(&self) -> KinematicContacts {
+        self.contacts
+    }
+
+    /// The collision box used by the last step, relative to the entity's translation.
+    pub fn collider(&self) -> Option<Rect> {
+        self.collider
+    }
+}
+
+/// Triggered on a kinematic body after each step where it tried to move, or its contacts changed.
+#[derive(Clone, Copy, Debug, EntityEvent)]
+pub struct KinematicStepped {
+    pub entity: Entity,
+    pub requested: Vec2,
+    pub moved: Vec2,
+    pub contacts: KinematicContacts,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Axis {
+    X,
+    Y,
+}
+
+struct TerrainLayer<'a> {
+    layer_grid: LayerGrid,
+    grid_values: &'a ShieldtankGridValues,
+}
+
+impl TerrainLayer<'_> {
+    fn kind(&self, terrain: &ShieldtankKinematicTerrain, grid: I64Vec2) -> Option<TerrainKind> {
+        terrain.kind(self.grid_values.get(grid)?)
+    }
+
+    fn to_layer_px(&self, rect: Rect) -> Rect {
+        Rect::from_corners(
+            self.layer_grid.world_to_layer_px(rect.min),
+            self.layer_grid.world_to_layer_px(rect.max),
+        )
+    }
+
+    /// The cells overlapped by a rect in layer pixels along one axis, ignoring touching edges.
+    fn cell_span(&self, min: f32, max: f32) -> std::ops::RangeInclusive<i64> {
+        let grid_cell_size = self.layer_grid.grid_cell_size;
+        let first = ((min + EPSILON) / grid_cell_size).floor() as i64;
+        let last = ((max - EPSILON) / grid_cell_size).ceil() as i64 - 1;
+        first..=last
+    }
+
+    /// How far the body can move by `delta` world units along `axis` before entering a blocking
+    /// cell, or `None` when nothing blocks it.
+    fn sweep(
+        &self,
+        terrain: &ShieldtankKinematicTerrain,
+        body: Rect,
+        axis: Axis,
+        delta: f32,
+        drop_through: bool,
+    ) -> Option<f32> {
+        if delta == 0.0 {
+            return None;
+        }
+
+        let grid_cell_size = self.layer_grid.grid_cell_size;
+        let body = self.to_layer_px(body);
+
+        // Layer pixels point down, so world y flips.
+        let delta = match axis {
+            Axis::X => delta,
+            Axis::Y => -delta,
+        };
+
+        let (along_min, along_max, cross) = match axis {
+            Axis::X => (
+                body.min.x,
+                body.max.x,
+                self.cell_span(body.min.y, body.max.y),
+            ),
+            Axis::Y => (
+                body.min.y,
+                body.max.y,
+                self.cell_span(body.min.x, body.max.x),
+            ),
+        };
+
+        let blocks = |index: i64| {
+            cross.clone().any(|cross_index| {
+                let grid = match axis {
+                    Axis::X => I64Vec2::new(index, cross_index),
+                    Axis::Y => I64Vec2::new(cross_index, index),
+                };
+
+                match self.kind(terrain, grid) {
+                    Some(TerrainKind::Solid) => true,
+                    Some(TerrainKind::OneWay) => axis == Axis::Y && delta > 0.0 && !drop_through,
+                    _ => false,
+                }
+            })
+        };
+
+        let allowed = if delta > 0.0 {
+            let first = ((along_max - EPSILON) / grid_cell_size).ceil() as i64;
+            let last = ((along_max + delta) / grid_cell_size).ceil() as i64 - 1;
+
+            (first..=last)
+                .find(|index| blocks(*index))
+                .map(|index| index as f32 * grid_cell_size - along_max)
+        } else {
+            let first = ((along_min + EPSILON) / grid_cell_size).floor() as i64 - 1;
+            let last = ((along_min + delta) / grid_cell_size).floor() as i64;
+
+            (last..=first)
+                .rev()
+                .find(|index| blocks(*index))
+                .map(|index| (index + 1) as f32 * grid_cell_size - along_min)
+        }?;
+
+        match axis {
+            Axis::X => Some(allowed),
+            Axis::Y => Some(-allowed),
+        }
+    }
+
+    fn overlaps_ladder(&self, terrain: &ShieldtankKinematicTerrain, body: Rect) -> bool {
+        let body = self.to_layer_px(body);
+        let rows = self.cell_span(body.min.y, body.max.y);
+
+        self.cell_span(body.min.x, body.max.x).any(|x| {
+            rows.clone()
+                .any(|y| self.kind(terrain, I64Vec2::new(x, y)) == Some(TerrainKind::Ladder))
+        })
+    }
+}
+
+fn sweep_layers(
+    layers: &[TerrainLayer],
+    terrain: &ShieldtankKinematicTerrain,
+    body: Rect,
+    axis: Axis,
+    delta: f32,
+    drop_through: bool,
+) -> Option<f32> {
+    layers
+        .iter()
+        .filter_map(|layer| layer.sweep(terrain, body, axis, delta, drop_through))
+        .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+}
+
+fn offset_rect(rect: Rect, offset: Vec2) -> Rect {
+    Rect {
+        min: rect.min + offset,
+        max: rect.max + offset,
+    }
+}
+
+#[allow(clippy::type_complexity)]
+#[allow(clippy::too_many_arguments)]
+fn kinematic_body_system(
+    mut body_query: Query<(
+        Entity,
+        &mut ShieldtankKinematicBody,
+        &mut Transform,
+        Option<&ChildOf>,
+        Option<&ShieldtankEntity>,
+    )>,
+    parent_query: Query<&GlobalTransform>,
+    layer_query: Query<(Entity, &ShieldtankGridValues), With<ShieldtankLayer>>,
+    layer_grids: LayerGridQuery,
+    entity_assets: Res<Assets<EntityInstance>>,
+    terrain: Res<ShieldtankKinematicTerrain>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let layers: Vec<TerrainLayer> = layer_query
+        .iter()
+        .filter_map(|(layer, grid_values)| {
+            Some(TerrainLayer {
+                layer_grid: layer_grids.get(layer)?,
+                grid_values,
+            })
+        })
+        .collect();
+
+    let delta_secs = time.delta_secs();
+
+    body_query
+        .iter_mut()
+        .for_each(|(entity, mut body, mut transform, child_of, ldtk_entity)| {
+            let parent_transform = child_of
+                .and_then(|child_of| parent_query.get(child_of.parent()).ok())
+                .copied()
+                .unwrap_or_default();
+
+            let location = parent_transform
+                .mul_transform(*transform)
+                .translation()
+                .truncate();
+
+            body.collider = body.collider_override.or_else(|| {
+                let asset = entity_assets.get(ldtk_entity?.as_asset_id())?;
+                Some(entity_local_bounds(asset))
+            });
+
+            let Some(collider) = body.collider else {
+                return;
+            };
+
+            let drop_through = body.drop_through;
+            let requested = body.velocity * delta_secs;
+            let mut moved = Vec2::ZERO;
+
+            let step = |axis: Axis, delta: f32, moved: &mut Vec2| -> bool {
+                let body_rect = offset_rect(collider, location + *moved);
+                let hit = sweep_layers(&layers, &terrain, body_rect, axis, delta, drop_through);
+
+                let allowed = hit.unwrap_or(delta);
+                match axis {
+                    Axis::X => moved.x += allowed,
+                    Axis::Y => moved.y += allowed,
+                }
+
+                hit.is_some()
+            };
+
+            if step(Axis::X, requested.x, &mut moved) {
+                body.velocity.x = 0.0;
+            }
+
+            if step(Axis::Y, requested.y, &mut moved) {
+                body.velocity.y = 0.0;
+            }
+
+            let body_rect = offset_rect(collider, location + moved);
+            let probe = |axis: Axis, delta: f32| {
+                sweep_layers(&layers, &terrain, body_rect, axis, delta, drop_through).is_some()
+            };
+
+            let contacts = KinematicContacts {
+                grounded: probe(Axis::Y, -PROBE_DISTANCE),
+                wall_left: probe(Axis::X, -PROBE_DISTANCE),
+                wall_right: probe(Axis::X, PROBE_DISTANCE),
+                ceiling: probe(Axis::Y, PROBE_DISTANCE),
+                on_ladder: layers
+                    .iter()
+                    .any(|layer| layer.overlaps_ladder(&terrain, body_rect)),
+            };
+
+            // `moved` is in world space, while the translation is relative to the parent.
+            if moved != Vec2::ZERO {
+                transform.translation += parent_transform
+                    .affine()
+                    .inverse()
+                    .transform_vector3(moved.extend(0.0));
+            }
+
+            let contacts_changed = body.contacts != contacts;
+            body.contacts = contacts;
+
+            if requested != Vec2::ZERO || contacts_changed {
+                commands.trigger(KinematicStepped {
+                    entity,
+                    requested,
+                    moved,
+                    contacts,
+                });
+            }
+        });
+}
+
+pub struct KinematicPlugin;
+impl Plugin for KinematicPlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.register_type::<ShieldtankKinematicTerrain>();
+        app.register_type::<KinematicContacts>();
+        app.register_type::<ShieldtankKinematicBody>();
+        app.init_resource::<ShieldtankKinematicTerrain>();
+        app.add_systems(
+            ShieldtankComponentSystemSet,
+            kinematic_body_system.before(TransformSystems::Propagate),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy_app::Update;
+    use bevy_ecs::observer::On;
+    use bevy_ecs::system::ResMut;
+    use bevy_ldtk_asset::layer::LayerInstance;
+    use bevy_math::Vec3;
+
+    use crate::component::shieldtank_component::ShieldtankComponent;
+    use crate::test::{test_app, tiles_layer};
+
+    use super::*;
+
+    const SOLID: i64 = 1;
+    const ONE_WAY: i64 = 2;
+    const LADDER: i64 = 3;
+
+    /// A 6 by 4 layer of 16 pixel cells, with its top left corner at the world origin: a floor
+    /// whose top is at y = -48, a wall on its right end, a one-way platform whose top is at
+    /// y = -32, and a ladder in the third column.
+    fn grid_values() -> ShieldtankGridValues {
+        ShieldtankGridValues::from_rows(&[
+            [0, 0, 0, 0, 0, 0],
+            [0, 0, LADDER, 0, 0, 0],
+            [0, 0, LADDER, ONE_WAY, ONE_WAY, SOLID],
+            [SOLID, SOLID, SOLID, SOLID, SOLID, SOLID],
+        ])
+    }
+
+    fn terrain() -> ShieldtankKinematicTerrain {
+        ShieldtankKinematicTerrain::default()
+            .with_solid(SOLID)
+            .with_one_way(ONE_WAY)
+            .with_ladder(LADDER)
+    }
+
+    fn layer_grid() -> LayerGrid {
+        LayerGrid {
+            origin: Vec2::ZERO,
+            level_offset: Vec2::ZERO,
+            grid_cell_size: 16.0,
+            size: I64Vec2::new(6, 4),
+        }
+    }
+
+    /// An 8 pixel body with its bottom left corner at `(x, y)`.
+    fn body_at(x: f32, y: f32) -> Rect {
+        Rect::new(x, y, x + 8.0, y + 8.0)
+    }
+
+    #[test]
+    fn sweeps_stop_at_solid_cells() {
+        let grid_values = grid_values();
+        let layer = TerrainLayer {
+            layer_grid: layer_grid(),
+            grid_values: &grid_values,
+        };
+        let terrain = terrain();
+
+        let falling = body_at(4.0, -44.0);
+        assert_eq!(
+            layer.sweep(&terrain, falling, Axis::Y, -10.0, false),
+            Some(-4.0)
+        );
+        assert_eq!(layer.sweep(&terrain, falling, Axis::Y, -3.0, false), None);
+        assert_eq!(layer.sweep(&terrain, falling, Axis::Y, 10.0, false), None);
+
+        let walking = body_at(68.0, -48.0);
+        assert_eq!(
+            layer.sweep(&terrain, walking, Axis::X, 10.0, false),
+            Some(4.0)
+        );
+        assert_eq!(layer.sweep(&terrain, walking, Axis::X, -40.0, false), None);
+        assert_eq!(layer.sweep(&terrain, walking, Axis::X, 0.0, false), None);
+    }
+
+    #[test]
+    fn one_way_platforms_only_block_falling_bodies() {
+        let grid_values = grid_values();
+        let layer = TerrainLayer {
+            layer_grid: layer_grid(),
+            grid_values: &grid_values,
+        };
+        let terrain = terrain();
+
+        let above = body_at(52.0, -28.0);
+        assert_eq!(
+            layer.sweep(&terrain, above, Axis::Y, -10.0, false),
+            Some(-4.0)
+        );
+        assert_eq!(layer.sweep(&terrain, above, Axis::Y, -10.0, true), None);
+
+        let below = body_at(52.0, -46.0);
+        assert_eq!(layer.sweep(&terrain, below, Axis::Y, 10.0, false), None);
+        assert_eq!(layer.sweep(&terrain, below, Axis::X, -20.0, false), None);
+    }
+
+    #[test]
+    fn ladders_never_block() {
+        let grid_values = grid_values();
+        let layer = TerrainLayer {
+            layer_grid: layer_grid(),
+            grid_values: &grid_values,
+        };
+        let terrain = terrain();
+
+        let beside = body_at(20.0, -30.0);
+        assert_eq!(layer.sweep(&terrain, beside, Axis::X, 30.0, false), None);
+        assert!(!layer.overlaps_ladder(&terrain, beside));
+        assert!(layer.overlaps_ladder(&terrain, body_at(36.0, -30.0)));
+    }
+
+    #[derive(Default, Resource)]
+    struct Steps(Vec<KinematicStepped>);
+
+    struct Bodies {
+        app: bevy_app::App,
+    }
+
+    impl Bodies {
+        fn new() -> Self {
+            let mut app = test_app();
+            app.insert_resource(terrain());
+            app.init_resource::<Time>();
+            app.init_resource::<Steps>();
+            app.add_observer(|stepped: On<KinematicStepped>, mut steps: ResMut<Steps>| {
+                steps.0.push(*stepped);
+            });
+            app.add_systems(Update, kinematic_body_system);
+
+            let layer = tiles_layer(
+                "Terrain",
+                I64Vec2::new(6, 4),
+                16,
+                vec![],
+                Default::default(),
+            );
+            let layer = app
+                .world_mut()
+                .resource_mut::<Assets<LayerInstance>>()
+                .add(layer);
+            app.world_mut().spawn((
+                ShieldtankLayer::new(layer),
+                GlobalTransform::default(),
+                grid_values(),
+            ));
+
+            Self { app }
+        }
+
+        /// A body with an 8 pixel collider centered on `(x, y)`.
+        fn spawn(&mut self, x: f32, y: f32, velocity: Vec2) -> Entity {
+            self.app
+                .world_mut()
+                .spawn((
+                    ShieldtankKinematicBody {
+                        velocity,
+                        collider_override: Some(Rect::new(-4.0, -4.0, 4.0, 4.0)),
+                        ..Default::default()
+                    },
+                    Transform::from_xyz(x, y, 0.0),
+                ))
+                .id()
+        }
+
+        /// Runs a frame a tenth of a second long.
+        fn step(&mut self) {
+            self.app
+                .world_mut()
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_millis(100));
+            self.app.update();
+        }
+
+        fn body(&self, entity: Entity) -> (Vec3, &ShieldtankKinematicBody) {
+            let entity = self.app.world().entity(entity);
+            (
+                entity.get::<Transform>().unwrap().translation,
+                entity.get::<ShieldtankKinematicBody>().unwrap(),
+            )
+        }
+    }
+
+    #[test]
+    fn bodies_land_on_the_floor() {
+        let mut bodies = Bodies::new();
+        let entity = bodies.spawn(8.0, -36.0, Vec2::new(0.0, -100.0));
+        bodies.step();
+
+        let (translation, body) = bodies.body(entity);
+        assert_eq!(translation, Vec3::new(8.0, -44.0, 0.0));
+        assert_eq!(body.velocity, Vec2::ZERO);
+        assert_eq!(
+            body.contacts(),
+            KinematicContacts {
+                grounded: true,
+                ..Default::default()
+            }
+        );
+
+        let steps = &bodies.app.world().resource::<Steps>().0;
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].requested, Vec2::new(0.0, -10.0));
+        assert_eq!(steps[0].moved, Vec2::new(0.0, -8.0));
+    }
+
+    #[test]
+    fn bodies_drop_through_one_way_platforms() {
+        let mut bodies = Bodies::new();
+        let entity = bodies.spawn(56.0, -20.0, Vec2::new(0.0, -100.0));
+        bodies.step();
+
+        let (translation, body) = bodies.body(entity);
+        assert_eq!(translation.y, -28.0);
+        assert!(body.contacts().grounded);
+
+        let mut body = bodies
+            .app
+            .world_mut()
+            .get_mut::<ShieldtankKinematicBody>(entity)
+            .unwrap();
+        body.velocity.y = -100.0;
+        body.drop_through = true;
+        bodies.step();
+
+        let (translation, body) = bodies.body(entity);
+        assert_eq!(translation.y, -38.0);
+        assert!(!body.contacts().grounded);
+    }
+
+    #[test]
+    fn contacts_report_walls_and_ladders() {
+        let mut bodies = Bodies::new();
+        let against_wall = bodies.spawn(76.0, -44.0, Vec2::ZERO);
+        let on_ladder = bodies.spawn(40.0, -24.0, Vec2::ZERO);
+        bodies.step();
+
+        assert_eq!(
+            bodies.body(against_wall).1.contacts(),
+            KinematicContacts {
+                grounded: true,
+                wall_right: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            bodies.body(on_ladder).1.contacts(),
+            KinematicContacts {
+                on_ladder: true,
+                ..Default::default()
+            }
+        );
+
+        // Unchanged contacts of a body at rest don't trigger another step.
+        bodies.app.world_mut().resource_mut::<Steps>().0.clear();
+        bodies.step();
+        assert!(bodies.app.world().resource::<Steps>().0.is_empty());
+    }
+}
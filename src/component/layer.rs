@@ -9,12 +9,18 @@ use bevy_ecs::system::{Commands, Query, Res};
 use bevy_ldtk_asset::entity::EntityInstance;
 use bevy_ldtk_asset::layer::EntitiesLayer;
 use bevy_ldtk_asset::layer::LayerInstance;
+use bevy_ldtk_asset::layer_definition::{
+    LayerDefinition as LayerDefinitionAsset, LayerDefinitionType,
+};
+use bevy_ldtk_asset::prelude::LdtkAsset;
 use bevy_math::Vec2;
 use bevy_reflect::Reflect;
 use bevy_transform::components::{GlobalTransform, Transform};
 use either::Either;
 
 use super::entity::ShieldtankEntity;
+use super::filter::FilterSubject;
+use super::grid_edits::ShieldtankGridEdits;
 use super::layer_definition::ShieldtankLayerDefinition;
 use super::layer_tiles::LdtkLayerTiles;
 use super::shieldtank_component::{ShieldtankComponent, ShieldtankComponentSystemSet};
@@ -69,6 +75,16 @@ impl SpawnChildren for ShieldtankLayer {
             Either::Right(vec![].into_iter())
         }
     }
+
+    fn filter_subject(child_asset: &EntityInstance) -> FilterSubject<'_> {
+        FilterSubject {
+            identifier: Some(child_asset.get_identifier()),
+            iid: Some(child_asset.get_iid()),
+            tags: &child_asset.tags,
+            field_instances: Some(&child_asset.field_instances),
+            ..Default::default()
+        }
+    }
 }
 
 #[allow(clippy::type_complexity)]
@@ -78,6 +94,7 @@ fn layer_insert_components_system(
         Or<(Changed<ShieldtankLayer>, AssetChanged<ShieldtankLayer>)>,
     >,
     assets: Res<Assets<LayerInstance>>,
+    layer_definition_assets: Res<Assets<LayerDefinitionAsset>>,
     mut commands: Commands,
 ) {
     query
@@ -89,19 +106,37 @@ fn layer_insert_components_system(
         .for_each(|(entity, component, transform, asset)| {
             let mut entity_commands = commands.entity(entity);
 
-            if let Some(tiles_layer) = asset.layer_type.get_tiles_layer() {
-                if !tiles_layer.tiles.is_empty() {
-                    let layer_tiles = LdtkLayerTiles::new(asset, tiles_layer);
-
-                    entity_commands.insert(layer_tiles);
-                }
+            let int_grid_or_auto_layer = layer_definition_assets
+                .get(asset.layer_definition.id())
+                .is_some_and(|layer_definition| {
+                    matches!(
+                        layer_definition.layer_definition_type,
+                        LayerDefinitionType::IntGrid | LayerDefinitionType::Autolayer
+                    )
+                });
+
+            // IntGrid and AutoLayer layers with a tileset but no tiles yet can still have tiles
+            // generated by rules.
+            if let Some(tiles_layer) = asset.layer_type.get_tiles_layer()
+                && (!tiles_layer.tiles.is_empty()
+                    || (int_grid_or_auto_layer && tiles_layer.tileset_image.is_some()))
+            {
+                let layer_tiles = LdtkLayerTiles::new(asset, tiles_layer);
+
+                entity_commands.insert(layer_tiles);
+            }
 
+            if asset.layer_type.is_tiles_layer() {
                 let layer_definition = asset.layer_definition.clone();
                 let layer_definition = ShieldtankLayerDefinition::new(layer_definition);
 
                 entity_commands.insert(layer_definition);
             }
 
+            if int_grid_or_auto_layer {
+                entity_commands.insert_if_new(ShieldtankGridEdits::default());
+            }
+
             if transform.is_none() {
                 let location = Vec2::new(1.0, -1.0) * asset.location.as_vec2();
                 let z = (asset.index + 1) as f32 * component.layer_separation;
@@ -149,3 +184,61 @@ impl Plugin for ShieldtankLayerPlugin {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::Update;
+    use bevy_ldtk_asset::layer::LayerType;
+    use bevy_math::I64Vec2;
+
+    use crate::test::{int_grid_value, layer_definition, test_app, tiles_layer};
+
+    use super::*;
+
+    /// Spawns a layer with a tileset image but no tiles, of the given definition type.
+    fn spawn_untiled_layer(layer_definition_type: LayerDefinitionType) -> (bevy_app::App, Entity) {
+        let mut app = test_app();
+        app.add_systems(Update, layer_insert_components_system);
+
+        let definition = layer_definition(layer_definition_type, 16, [int_grid_value(1, "Wall")]);
+        let definition = app
+            .world_mut()
+            .resource_mut::<Assets<LayerDefinitionAsset>>()
+            .add(definition);
+
+        let mut layer = tiles_layer("Layer", I64Vec2::new(2, 2), 16, vec![0; 4], definition);
+        if let LayerType::Tiles(tiles_layer) = &mut layer.layer_type {
+            tiles_layer.tileset_image = Some(Handle::default());
+        }
+        let layer = app
+            .world_mut()
+            .resource_mut::<Assets<LayerInstance>>()
+            .add(layer);
+
+        let entity = app.world_mut().spawn(ShieldtankLayer::new(layer)).id();
+        app.update();
+
+        (app, entity)
+    }
+
+    #[test]
+    fn int_grid_and_auto_layers_can_generate_and_edit_tiles() {
+        for layer_definition_type in [LayerDefinitionType::IntGrid, LayerDefinitionType::Autolayer]
+        {
+            let (app, entity) = spawn_untiled_layer(layer_definition_type);
+            let entity = app.world().entity(entity);
+
+            assert!(entity.contains::<LdtkLayerTiles>());
+            assert!(entity.contains::<ShieldtankGridEdits>());
+        }
+    }
+
+    #[test]
+    fn tiles_layers_without_tiles_are_left_alone() {
+        let (app, entity) = spawn_untiled_layer(LayerDefinitionType::Tiles);
+        let entity = app.world().entity(entity);
+
+        assert!(!entity.contains::<LdtkLayerTiles>());
+        assert!(!entity.contains::<ShieldtankGridEdits>());
+    }
+}
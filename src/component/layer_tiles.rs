@@ -145,7 +145,7 @@ impl LdtkLayerTiles {
 }
 
 #[allow(clippy::type_complexity)]
-fn layer_tile_system(
+pub(crate) fn layer_tile_system(
     query: Query<
         (Entity, &LdtkLayerTiles),
         Or<(Changed<LdtkLayerTiles>, AssetChanged<LdtkLayerTiles>)>,
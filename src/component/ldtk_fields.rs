@@ -0,0 +1,328 @@
+use std::borrow::Cow;
+
+use bevy_app::App;
+use bevy_asset::{AsAssetId, Assets, Handle};
+use bevy_color::Color;
+use bevy_ecs::component::Component;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::query::Changed;
+use bevy_ecs::schedule::IntoScheduleConfigs;
+use bevy_ecs::system::{Commands, Query, Res};
+use bevy_ldtk_asset::entity::EntityInstance;
+use bevy_ldtk_asset::enum_definition::EnumDefinition;
+use bevy_ldtk_asset::field_instance::{EntityRef, EnumValue, FieldInstance};
+use bevy_ldtk_asset::iid::Iid;
+use bevy_ldtk_asset::prelude::LdtkAsset;
+use bevy_log::error;
+use bevy_math::{I64Vec2, IVec2};
+use bevy_reflect::{DynamicStruct, FromReflect, Struct};
+
+use crate::error::LdtkFieldError;
+
+use super::entity::{ShieldtankEntity, entity_insert_components_system};
+use super::field_instances::ShieldtankFieldInstances;
+use super::shieldtank_component::ShieldtankComponentSystemSet;
+use super::tile::ShieldtankTile;
+
+/// A component built from the [ShieldtankFieldInstances] of an LDtk entity.
+///
+/// Usually derived with `#[derive(LdtkFields)]`, then registered with
+/// [LdtkFieldsAppExt::register_ldtk_fields].
+///
+/// `Option` fields are already `None` when missing, so they can't have a default:
+///
+/// ```compile_fail
+/// # use bevy_ecs::component::Component;
+/// # use shieldtank::prelude::*;
+/// #[derive(Component, LdtkFields)]
+/// struct Door {
+///     #[ldtk(default)]
+///     key: Option<String>,
+/// }
+/// ```
+pub trait LdtkFields: Component + Sized {
+    /// When set, the component is only inserted on LDtk entities with this identifier.
+    const ENTITY_IDENTIFIER: Option<&'static str>;
+
+    fn from_field_instances(
+        field_instances: &ShieldtankFieldInstances,
+    ) -> Result<Self, LdtkFieldError>;
+}
+
+/// A type which can be read out of a single LDtk field instance.
+pub trait FromLdtkField: Sized {
+    /// Describes the expected LDtk field type, for error messages.
+    fn expected() -> Cow<'static, str>;
+
+    fn from_ldtk_field(field: &FieldInstance) -> Option<Self>;
+
+    fn from_ldtk_array(_field: &FieldInstance) -> Option<Vec<Self>> {
+        None
+    }
+}
+
+/// The value of an LDtk enum. [EnumValue] keeps it private, so it's read through reflection.
+pub fn enum_value_str(value: &EnumValue) -> Option<&str> {
+    value
+        .field("value")?
+        .try_downcast_ref::<String>()
+        .map(String::as_str)
+}
+
+/// Builds an [EnumValue] through reflection, since its fields are private.
+pub fn new_enum_value(value: &str, enum_definition: Handle<EnumDefinition>) -> Option<EnumValue> {
+    let mut dynamic = DynamicStruct::default();
+    dynamic.insert("value", value.to_string());
+    dynamic.insert("enum_definition", enum_definition);
+
+    EnumValue::from_reflect(&dynamic)
+}
+
+pub fn enum_value(field: &FieldInstance) -> Option<&str> {
+    field.get_enum().and_then(enum_value_str)
+}
+
+pub fn enum_values(field: &FieldInstance) -> Option<impl Iterator<Item = &str>> {
+    let values = field
+        .get_array_enum()?
+        .iter()
+        .map(enum_value_str)
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(values.into_iter())
+}
+
+macro_rules! impl_from_ldtk_int {
+    ($($ty:ty),*) => {
+        $(
+            impl FromLdtkField for $ty {
+                fn expected() -> Cow<'static, str> {
+                    Cow::Borrowed(concat!("int (", stringify!($ty), ")"))
+                }
+
+                fn from_ldtk_field(field: &FieldInstance) -> Option<Self> {
+                    field.get_int().and_then(|value| <$ty>::try_from(*value).ok())
+                }
+
+                fn from_ldtk_array(field: &FieldInstance) -> Option<Vec<Self>> {
+                    field
+                        .get_array_int()?
+                        .iter()
+                        .map(|value| <$ty>::try_from(*value).ok())
+                        .collect()
+                }
+            }
+        )*
+    };
+}
+
+impl_from_ldtk_int!(i8, i16, i32, u8, u16, u32, u64, isize, usize);
+
+impl FromLdtkField for i64 {
+    fn expected() -> Cow<'static, str> {
+        Cow::Borrowed("int")
+    }
+
+    fn from_ldtk_field(field: &FieldInstance) -> Option<Self> {
+        field.get_int().copied()
+    }
+
+    fn from_ldtk_array(field: &FieldInstance) -> Option<Vec<Self>> {
+        field.get_array_int().cloned()
+    }
+}
+
+impl FromLdtkField for f64 {
+    fn expected() -> Cow<'static, str> {
+        Cow::Borrowed("float")
+    }
+
+    fn from_ldtk_field(field: &FieldInstance) -> Option<Self> {
+        field.get_float().copied()
+    }
+}
+
+impl FromLdtkField for f32 {
+    fn expected() -> Cow<'static, str> {
+        Cow::Borrowed("float")
+    }
+
+    fn from_ldtk_field(field: &FieldInstance) -> Option<Self> {
+        field.get_float().map(|value| *value as f32)
+    }
+}
+
+impl FromLdtkField for bool {
+    fn expected() -> Cow<'static, str> {
+        Cow::Borrowed("bool")
+    }
+
+    fn from_ldtk_field(field: &FieldInstance) -> Option<Self> {
+        field.get_bool().copied()
+    }
+}
+
+// Enum values are accepted as strings too, for when a dedicated `LdtkEnum` is overkill.
+impl FromLdtkField for String {
+    fn expected() -> Cow<'static, str> {
+        Cow::Borrowed("string or enum value")
+    }
+
+    fn from_ldtk_field(field: &FieldInstance) -> Option<Self> {
+        field
+            .get_string()
+            .map(String::as_str)
+            .or_else(|| enum_value(field))
+            .map(str::to_string)
+    }
+
+    fn from_ldtk_array(field: &FieldInstance) -> Option<Vec<Self>> {
+        field
+            .get_array_string()
+            .cloned()
+            .or_else(|| Some(enum_values(field)?.map(str::to_string).collect()))
+    }
+}
+
+impl FromLdtkField for Color {
+    fn expected() -> Cow<'static, str> {
+        Cow::Borrowed("color")
+    }
+
+    fn from_ldtk_field(field: &FieldInstance) -> Option<Self> {
+        field.get_color().copied()
+    }
+}
+
+impl FromLdtkField for I64Vec2 {
+    fn expected() -> Cow<'static, str> {
+        Cow::Borrowed("point")
+    }
+
+    fn from_ldtk_field(field: &FieldInstance) -> Option<Self> {
+        field.get_point().copied()
+    }
+
+    fn from_ldtk_array(field: &FieldInstance) -> Option<Vec<Self>> {
+        field.get_array_point().cloned()
+    }
+}
+
+impl FromLdtkField for IVec2 {
+    fn expected() -> Cow<'static, str> {
+        Cow::Borrowed("point")
+    }
+
+    fn from_ldtk_field(field: &FieldInstance) -> Option<Self> {
+        field.get_point().map(I64Vec2::as_ivec2)
+    }
+
+    fn from_ldtk_array(field: &FieldInstance) -> Option<Vec<Self>> {
+        field
+            .get_array_point()
+            .map(|points| points.iter().map(I64Vec2::as_ivec2).collect())
+    }
+}
+
+impl FromLdtkField for ShieldtankTile {
+    fn expected() -> Cow<'static, str> {
+        Cow::Borrowed("tile")
+    }
+
+    fn from_ldtk_field(field: &FieldInstance) -> Option<Self> {
+        field.get_tile().map(ShieldtankTile::new)
+    }
+
+    fn from_ldtk_array(field: &FieldInstance) -> Option<Vec<Self>> {
+        field
+            .get_array_tile()
+            .map(|tiles| tiles.iter().map(ShieldtankTile::new).collect())
+    }
+}
+
+// bevy_ldtk_asset names the getter of a single entity ref `get_array_entity_ref`, and doesn't
+// load arrays of them.
+impl FromLdtkField for EntityRef {
+    fn expected() -> Cow<'static, str> {
+        Cow::Borrowed("entity ref")
+    }
+
+    fn from_ldtk_field(field: &FieldInstance) -> Option<Self> {
+        field.get_array_entity_ref().copied()
+    }
+}
+
+// An entity ref read as an `Iid` is the Iid of the referenced entity.
+impl FromLdtkField for Iid {
+    fn expected() -> Cow<'static, str> {
+        Cow::Borrowed("entity ref")
+    }
+
+    fn from_ldtk_field(field: &FieldInstance) -> Option<Self> {
+        field
+            .get_array_entity_ref()
+            .map(|entity_ref| entity_ref.entity_iid)
+    }
+}
+
+impl<T: FromLdtkField> FromLdtkField for Vec<T> {
+    fn expected() -> Cow<'static, str> {
+        Cow::Owned(format!("array of {}", T::expected()))
+    }
+
+    fn from_ldtk_field(field: &FieldInstance) -> Option<Self> {
+        T::from_ldtk_array(field)
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn ldtk_fields_system<T: LdtkFields>(
+    query: Query<
+        (Entity, &ShieldtankEntity, &ShieldtankFieldInstances),
+        Changed<ShieldtankFieldInstances>,
+    >,
+    assets: Res<Assets<EntityInstance>>,
+    mut commands: Commands,
+) {
+    query
+        .iter()
+        .filter_map(|(entity, component, field_instances)| {
+            Some((
+                entity,
+                assets.get(component.as_asset_id())?,
+                field_instances,
+            ))
+        })
+        .filter(|(_, asset, _)| {
+            T::ENTITY_IDENTIFIER.is_none_or(|identifier| asset.get_identifier() == identifier)
+        })
+        .for_each(|(entity, asset, field_instances)| {
+            match T::from_field_instances(field_instances) {
+                Ok(component) => {
+                    commands.entity(entity).insert(component);
+                }
+                Err(e) => {
+                    error!(
+                        "Could not build {} for {} {entity:?}: {e}",
+                        std::any::type_name::<T>(),
+                        asset.get_identifier()
+                    );
+                }
+            }
+        });
+}
+
+pub trait LdtkFieldsAppExt {
+    /// Inserts `T` on LDtk entities, and rebuilds it whenever their [ShieldtankFieldInstances]
+    /// change.
+    fn register_ldtk_fields<T: LdtkFields>(&mut self) -> &mut Self;
+}
+
+impl LdtkFieldsAppExt for App {
+    fn register_ldtk_fields<T: LdtkFields>(&mut self) -> &mut Self {
+        self.add_systems(
+            ShieldtankComponentSystemSet,
+            ldtk_fields_system::<T>.after(entity_insert_components_system),
+        )
+    }
+}
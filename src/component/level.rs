@@ -5,11 +5,13 @@ use bevy_camera::visibility::Visibility;
 use bevy_ecs::component::Component;
 use bevy_ecs::entity::Entity;
 use bevy_ecs::query::{Changed, Or};
+use bevy_ecs::schedule::IntoScheduleConfigs;
 use bevy_ecs::system::{Commands, Query, Res};
 use bevy_ldtk_asset::layer::LayerInstance;
 use bevy_ldtk_asset::level::Level as LevelAsset;
 use bevy_math::{Rect, Vec2};
 use bevy_reflect::Reflect;
+use bevy_transform::TransformSystems;
 use bevy_transform::components::{GlobalTransform, Transform};
 
 use super::layer::ShieldtankLayer;
@@ -119,7 +121,7 @@ fn level_insert_components_system(
         });
 }
 
-fn level_global_bounds_system(
+pub(crate) fn level_global_bounds_system(
     query: Query<(Entity, &ShieldtankLevel, &GlobalTransform), Changed<GlobalTransform>>,
     assets: Res<Assets<LevelAsset>>,
     mut commands: Commands,
@@ -135,14 +137,19 @@ fn level_global_bounds_system(
         })
         .for_each(|(entity, global_transform, asset)| {
             let global_location = global_transform.translation().truncate();
-            let size = Vec2::new(1.0, -1.0) * asset.size.as_vec2();
-            let rect = Rect::from_corners(global_location, global_location + size);
-            let global_bounds = ShieldtankWorldBounds::from(rect);
+            let global_bounds = level_bounds(asset, global_location);
 
             commands.entity(entity).insert(global_bounds);
         });
 }
 
+pub(crate) fn level_bounds(asset: &LevelAsset, global_location: Vec2) -> ShieldtankWorldBounds {
+    let size = Vec2::new(1.0, -1.0) * asset.size.as_vec2();
+    let rect = Rect::from_corners(global_location, global_location + size);
+
+    ShieldtankWorldBounds::from(rect)
+}
+
 pub struct ShieldtankLevelPlugin;
 impl Plugin for ShieldtankLevelPlugin {
     fn build(&self, app: &mut bevy_app::App) {
@@ -152,6 +159,9 @@ impl Plugin for ShieldtankLevelPlugin {
             <ShieldtankLevel as ShieldtankComponent>::add_basic_components_system,
         );
         app.add_systems(ShieldtankComponentSystemSet, level_insert_components_system);
-        app.add_systems(ShieldtankComponentSystemSet, level_global_bounds_system);
+        app.add_systems(
+            ShieldtankComponentSystemSet,
+            level_global_bounds_system.after(TransformSystems::Propagate),
+        );
     }
 }
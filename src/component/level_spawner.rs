@@ -0,0 +1,321 @@
+use bevy_app::Plugin;
+use bevy_asset::{Assets, Handle};
+use bevy_camera::visibility::Visibility;
+use bevy_ecs::component::Component;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::query::Without;
+use bevy_ecs::schedule::IntoScheduleConfigs;
+use bevy_ecs::system::{Commands, EntityCommands, Query, Res};
+use bevy_ldtk_asset::iid::Iid;
+use bevy_ldtk_asset::level::Level as LevelAsset;
+use bevy_ldtk_asset::prelude::LdtkAsset;
+use bevy_ldtk_asset::project::Project;
+use bevy_ldtk_asset::world::World as WorldAsset;
+use bevy_log::error;
+use bevy_reflect::Reflect;
+use bevy_transform::components::Transform;
+
+use super::level::ShieldtankLevel;
+use super::shieldtank_component::{ShieldtankComponent, ShieldtankComponentSystemSet};
+
+#[derive(Clone, Debug, PartialEq, Eq, Reflect)]
+pub enum LevelSelector {
+    Identifier(String),
+    Iid(Iid),
+}
+
+impl LevelSelector {
+    fn find(
+        &self,
+        project: &Project,
+        world_assets: &Assets<WorldAsset>,
+        level_assets: &Assets<LevelAsset>,
+    ) -> Option<Handle<LevelAsset>> {
+        project
+            .worlds
+            .values()
+            .filter_map(|world_handle| world_assets.get(world_handle.id()))
+            .flat_map(|world| world.levels.iter())
+            .find_map(|(iid, level_handle)| {
+                let found = match self {
+                    LevelSelector::Identifier(identifier) => level_assets
+                        .get(level_handle.id())
+                        .is_some_and(|level| level.get_identifier() == identifier),
+                    LevelSelector::Iid(selected_iid) => iid == selected_iid,
+                };
+
+                found.then(|| level_handle.clone())
+            })
+    }
+}
+
+impl From<&str> for LevelSelector {
+    fn from(identifier: &str) -> Self {
+        Self::Identifier(identifier.to_string())
+    }
+}
+
+impl From<String> for LevelSelector {
+    fn from(identifier: String) -> Self {
+        Self::Identifier(identifier)
+    }
+}
+
+impl From<Iid> for LevelSelector {
+    fn from(iid: Iid) -> Self {
+        Self::Iid(iid)
+    }
+}
+
+/// A level waiting for its project to load, spawned by
+/// [SpawnLdtkLevelCommandsExt::spawn_ldtk_level].
+///
+/// A [ShieldtankLevel] is inserted next to it once the level is found. The request stays, keeping
+/// the project loaded.
+#[derive(Clone, Debug, Component, Reflect)]
+#[require(Transform, Visibility)]
+pub struct ShieldtankLevelRequest {
+    pub project: Handle<Project>,
+    pub selector: LevelSelector,
+}
+
+pub trait SpawnLdtkLevelCommandsExt {
+    /// Spawns a single level and its layers and entities, without its project or world.
+    ///
+    /// The level is placed at the origin. Insert a [Transform] on the returned entity to place it
+    /// elsewhere.
+    fn spawn_ldtk_level(
+        &mut self,
+        project: Handle<Project>,
+        selector: impl Into<LevelSelector>,
+    ) -> EntityCommands<'_>;
+}
+
+impl SpawnLdtkLevelCommandsExt for Commands<'_, '_> {
+    fn spawn_ldtk_level(
+        &mut self,
+        project: Handle<Project>,
+        selector: impl Into<LevelSelector>,
+    ) -> EntityCommands<'_> {
+        self.spawn(ShieldtankLevelRequest {
+            project,
+            selector: selector.into(),
+        })
+    }
+}
+
+fn project_loaded(
+    project: &Project,
+    world_assets: &Assets<WorldAsset>,
+    level_assets: &Assets<LevelAsset>,
+) -> bool {
+    project.worlds.values().all(|world_handle| {
+        world_assets.get(world_handle.id()).is_some_and(|world| {
+            world
+                .levels
+                .values()
+                .all(|level_handle| level_assets.contains(level_handle.id()))
+        })
+    })
+}
+
+fn level_request_system(
+    query: Query<(Entity, &ShieldtankLevelRequest), Without<ShieldtankLevel>>,
+    project_assets: Res<Assets<Project>>,
+    world_assets: Res<Assets<WorldAsset>>,
+    level_assets: Res<Assets<LevelAsset>>,
+    mut commands: Commands,
+) {
+    query
+        .iter()
+        .filter_map(|(entity, request)| {
+            Some((entity, request, project_assets.get(request.project.id())?))
+        })
+        .filter(|(_, _, project)| project_loaded(project, &world_assets, &level_assets))
+        .for_each(|(entity, request, project)| {
+            match request.selector.find(project, &world_assets, &level_assets) {
+                Some(level_handle) => {
+                    commands
+                        .entity(entity)
+                        .insert(ShieldtankLevel::new(level_handle));
+                }
+                None => {
+                    error!("No level matching {:?} in project!", request.selector);
+                    commands.entity(entity).remove::<ShieldtankLevelRequest>();
+                }
+            }
+        });
+}
+
+pub struct LevelSpawnerPlugin;
+impl Plugin for LevelSpawnerPlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.register_type::<LevelSelector>();
+        app.register_type::<ShieldtankLevelRequest>();
+        app.add_systems(
+            ShieldtankComponentSystemSet,
+            level_request_system
+                .before(<ShieldtankLevel as ShieldtankComponent>::add_basic_components_system),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use bevy_app::Update;
+    use bevy_asset::uuid::Uuid;
+    use bevy_ldtk_asset::world::WorldLayout;
+    use bevy_math::I64Vec2;
+
+    use crate::test::{level_asset, test_app};
+
+    use super::*;
+
+    struct Spawner {
+        app: bevy_app::App,
+        project: Handle<Project>,
+        levels: Vec<Handle<LevelAsset>>,
+    }
+
+    /// A project with one world holding levels 1 and 2. Level 2 hasn't loaded yet.
+    fn spawner() -> Spawner {
+        let mut app = test_app();
+        app.add_systems(Update, level_request_system);
+
+        let level = level_asset(1, I64Vec2::ZERO, I64Vec2::splat(100));
+        let levels = vec![
+            app.world_mut()
+                .resource_mut::<Assets<LevelAsset>>()
+                .add(level),
+            Handle::Uuid(Uuid::from_u128(2), PhantomData),
+        ];
+
+        let world = WorldAsset {
+            identifier: "World".to_string(),
+            iid: Iid::nil(),
+            world_layout: WorldLayout::Free,
+            levels: levels
+                .iter()
+                .enumerate()
+                .map(|(index, handle)| (Iid::from_u128(index as u128 + 1), handle.clone()))
+                .collect(),
+        };
+        let world = app
+            .world_mut()
+            .resource_mut::<Assets<WorldAsset>>()
+            .add(world);
+
+        let project = Project {
+            iid: Iid::nil(),
+            ldtk_version: "1.5.3".to_string(),
+            worlds: [(Iid::nil(), world)].into_iter().collect(),
+        };
+        let project = app
+            .world_mut()
+            .resource_mut::<Assets<Project>>()
+            .add(project);
+
+        Spawner {
+            app,
+            project,
+            levels,
+        }
+    }
+
+    impl Spawner {
+        fn finish_loading(&mut self) {
+            let level = level_asset(2, I64Vec2::new(100, 0), I64Vec2::splat(100));
+            self.app
+                .world_mut()
+                .resource_mut::<Assets<LevelAsset>>()
+                .insert(self.levels[1].id(), level)
+                .unwrap();
+        }
+
+        fn spawn(&mut self, selector: impl Into<LevelSelector>) -> Entity {
+            let world = self.app.world_mut();
+            let entity = world
+                .commands()
+                .spawn_ldtk_level(self.project.clone(), selector)
+                .id();
+            world.flush();
+            self.app.update();
+
+            entity
+        }
+
+        fn spawned_level(&self, entity: Entity) -> Option<Handle<LevelAsset>> {
+            self.app
+                .world()
+                .get::<ShieldtankLevel>(entity)
+                .map(|level| level.handle.clone())
+        }
+    }
+
+    #[test]
+    fn waits_for_every_level_of_the_project() {
+        let mut spawner = spawner();
+        let entity = spawner.spawn("Level_1");
+
+        assert_eq!(spawner.spawned_level(entity), None);
+        assert!(
+            spawner
+                .app
+                .world()
+                .entity(entity)
+                .contains::<ShieldtankLevelRequest>()
+        );
+
+        spawner.finish_loading();
+        spawner.app.update();
+
+        assert_eq!(
+            spawner.spawned_level(entity),
+            Some(spawner.levels[0].clone())
+        );
+        assert!(
+            spawner
+                .app
+                .world()
+                .entity(entity)
+                .contains::<ShieldtankLevelRequest>()
+        );
+    }
+
+    #[test]
+    fn selects_levels_by_identifier_or_iid() {
+        let mut spawner = spawner();
+        spawner.finish_loading();
+
+        let by_identifier = spawner.spawn("Level_2");
+        let by_iid = spawner.spawn(Iid::from_u128(1));
+
+        assert_eq!(
+            spawner.spawned_level(by_identifier),
+            Some(spawner.levels[1].clone())
+        );
+        assert_eq!(
+            spawner.spawned_level(by_iid),
+            Some(spawner.levels[0].clone())
+        );
+    }
+
+    #[test]
+    fn drops_requests_for_missing_levels() {
+        let mut spawner = spawner();
+        spawner.finish_loading();
+
+        let entity = spawner.spawn("Level_3");
+
+        assert_eq!(spawner.spawned_level(entity), None);
+        assert!(
+            !spawner
+                .app
+                .world()
+                .entity(entity)
+                .contains::<ShieldtankLevelRequest>()
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! Spawns and despawns the levels of a [ShieldtankWorld] based on their distance to any entity
+//! marked with [ShieldtankStreamingFocus].
+//!
+//! Streaming works by maintaining the world's [ShieldtankStreamedLevels], so the regular reconcile
+//! pass in [crate::component::spawn_children] does the actual spawning and despawning. A level is
+//! only spawned when it is both streamed in and passes the world's
+//! [ShieldtankComponentFilter](crate::component::filter::ShieldtankComponentFilter).
+//!
+//! Streaming defers the level, layer and entity hierarchy, along with its sprites and baked layer
+//! images. It doesn't defer reading external `.ldtkl` level files: `bevy_ldtk_asset` loads them
+//! along with the project, and its loader has no settings to skip them.
+
+use bevy_app::Plugin;
+use bevy_asset::{AsAssetId, Assets};
+use bevy_ecs::component::Component;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::hierarchy::Children;
+use bevy_ecs::lifecycle::RemovedComponents;
+use bevy_ecs::query::With;
+use bevy_ecs::schedule::IntoScheduleConfigs;
+use bevy_ecs::system::{Commands, Query, Res};
+use bevy_ldtk_asset::iid::Iid;
+use bevy_ldtk_asset::level::Level as LevelAsset;
+use bevy_ldtk_asset::world::World as WorldAsset;
+use bevy_math::{Rect, Vec2};
+use bevy_platform::collections::{HashMap, HashSet};
+use bevy_reflect::Reflect;
+use bevy_transform::components::GlobalTransform;
+
+use super::level::{ShieldtankLevel, level_bounds};
+use super::shieldtank_component::ShieldtankComponentSystemSet;
+use super::spawn_children::SpawnChildren;
+use super::world::ShieldtankWorld;
+use super::world_bounds::ShieldtankWorldBounds;
+
+/// Add to a [ShieldtankWorld] entity to only spawn the levels near a [ShieldtankStreamingFocus].
+#[derive(Clone, Debug, Component, Reflect)]
+pub struct ShieldtankLevelStreaming {
+    /// Levels whose bounds are within this distance of a focus are spawned.
+    pub preload_radius: f32,
+    /// Spawned levels are kept until they are this much further away than `preload_radius`.
+    pub unload_margin: f32,
+}
+
+impl Default for ShieldtankLevelStreaming {
+    fn default() -> Self {
+        Self {
+            preload_radius: 256.0,
+            unload_margin: 64.0,
+        }
+    }
+}
+
+/// The levels of a streaming world which are near a [ShieldtankStreamingFocus]. Maintained by
+/// [ShieldtankLevelStreaming], and removed along with it.
+#[derive(Clone, Debug, Default, PartialEq, Component, Reflect)]
+pub struct ShieldtankStreamedLevels {
+    iids: HashSet<Iid>,
+}
+
+impl ShieldtankStreamedLevels {
+    pub fn contains(&self, iid: Iid) -> bool {
+        self.iids.contains(&iid)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Iid> {
+        self.iids.iter().copied()
+    }
+}
+
+/// Marks an entity, usually the player or camera, which streamed levels are spawned around.
+#[derive(Clone, Copy, Debug, Default, Component, Reflect)]
+pub struct ShieldtankStreamingFocus;
+
+fn distance_to_rect(rect: Rect, point: Vec2) -> f32 {
+    point.clamp(rect.min, rect.max).distance(point)
+}
+
+#[allow(clippy::type_complexity)]
+fn level_streaming_system(
+    world_query: Query<(
+        Entity,
+        &ShieldtankWorld,
+        &ShieldtankLevelStreaming,
+        &GlobalTransform,
+        Option<&Children>,
+        Option<&ShieldtankStreamedLevels>,
+    )>,
+    level_query: Query<(&ShieldtankLevel, &ShieldtankWorldBounds)>,
+    focus_query: Query<&GlobalTransform, With<ShieldtankStreamingFocus>>,
+    world_assets: Res<Assets<WorldAsset>>,
+    level_assets: Res<Assets<LevelAsset>>,
+    mut commands: Commands,
+) {
+    let focus_locations: Vec<Vec2> = focus_query
+        .iter()
+        .map(|global_transform| global_transform.translation().truncate())
+        .collect();
+
+    world_query
+        .iter()
+        .filter_map(
+            |(entity, component, streaming, global_transform, children, streamed)| {
+                Some((
+                    entity,
+                    streaming,
+                    global_transform,
+                    children,
+                    streamed,
+                    world_assets.get(component.as_asset_id())?,
+                ))
+            },
+        )
+        .for_each(
+            |(entity, streaming, global_transform, children, streamed, asset)| {
+                let spawned_levels: HashMap<_, _> = children
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|child| level_query.get(*child).ok())
+                    .map(|(level, global_bounds)| (level.as_asset_id(), global_bounds.bounds()))
+                    .collect();
+
+                let world_location = global_transform.translation().truncate();
+
+                let iids: HashSet<Iid> = asset
+                    .levels
+                    .values()
+                    .filter_map(|level_handle| {
+                        let level_asset = level_assets.get(level_handle.id())?;
+
+                        let spawned_bounds = spawned_levels.get(&level_handle.id()).copied();
+
+                        let bounds = match spawned_bounds {
+                            Some(bounds) => bounds,
+                            None => {
+                                let location =
+                                    Vec2::new(1.0, -1.0) * level_asset.location.as_vec2();
+                                level_bounds(level_asset, world_location + location).bounds()
+                            }
+                        };
+
+                        let radius = match spawned_bounds {
+                            Some(_) => streaming.preload_radius + streaming.unload_margin,
+                            None => streaming.preload_radius,
+                        };
+
+                        focus_locations
+                            .iter()
+                            .any(|focus| distance_to_rect(bounds, *focus) <= radius)
+                            .then_some(level_asset.iid)
+                    })
+                    .collect();
+
+                let wanted = ShieldtankStreamedLevels { iids };
+
+                if streamed != Some(&wanted) {
+                    commands.entity(entity).insert(wanted);
+                }
+            },
+        );
+}
+
+fn level_streaming_removed_system(
+    mut removed: RemovedComponents<ShieldtankLevelStreaming>,
+    mut commands: Commands,
+) {
+    removed.read().for_each(|entity| {
+        if let Ok(mut entity_commands) = commands.get_entity(entity) {
+            entity_commands.remove::<ShieldtankStreamedLevels>();
+        }
+    });
+}
+
+pub struct LevelStreamingPlugin;
+impl Plugin for LevelStreamingPlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.register_type::<ShieldtankLevelStreaming>();
+        app.register_type::<ShieldtankStreamedLevels>();
+        app.register_type::<ShieldtankStreamingFocus>();
+        app.add_systems(
+            ShieldtankComponentSystemSet,
+            (level_streaming_system, level_streaming_removed_system)
+                .before(<ShieldtankWorld as SpawnChildren>::child_spawn_system),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::Update;
+    use bevy_asset::Handle;
+    use bevy_ecs::hierarchy::ChildOf;
+    use bevy_ldtk_asset::world::WorldLayout;
+    use bevy_math::{I64Vec2, Vec3};
+
+    use crate::component::shieldtank_component::ShieldtankComponent;
+    use crate::test::{level_asset, test_app};
+
+    use super::*;
+
+    struct Streaming {
+        app: bevy_app::App,
+        world: Entity,
+        focus: Entity,
+        levels: Vec<Handle<LevelAsset>>,
+    }
+
+    /// A streaming world at `world_x` with two 100 pixel levels, at 0 and 400 pixels along it.
+    fn streaming(world_x: f32) -> Streaming {
+        let mut app = test_app();
+        app.add_systems(
+            Update,
+            (level_streaming_system, level_streaming_removed_system),
+        );
+
+        let levels: Vec<_> = [(1, 0), (2, 400)]
+            .into_iter()
+            .map(|(iid, x)| {
+                let level = level_asset(iid, I64Vec2::new(x, 0), I64Vec2::splat(100));
+                app.world_mut()
+                    .resource_mut::<Assets<LevelAsset>>()
+                    .add(level)
+            })
+            .collect();
+
+        let world_asset = WorldAsset {
+            identifier: "World".to_string(),
+            iid: Iid::nil(),
+            world_layout: WorldLayout::Free,
+            levels: levels
+                .iter()
+                .enumerate()
+                .map(|(index, handle)| (Iid::from_u128(index as u128 + 1), handle.clone()))
+                .collect(),
+        };
+        let handle = app
+            .world_mut()
+            .resource_mut::<Assets<WorldAsset>>()
+            .add(world_asset);
+
+        let world = app
+            .world_mut()
+            .spawn((
+                ShieldtankWorld::new(handle),
+                ShieldtankLevelStreaming {
+                    preload_radius: 100.0,
+                    unload_margin: 50.0,
+                },
+                GlobalTransform::from_translation(Vec3::new(world_x, 0.0, 0.0)),
+            ))
+            .id();
+
+        let focus = app
+            .world_mut()
+            .spawn((ShieldtankStreamingFocus, GlobalTransform::default()))
+            .id();
+
+        Streaming {
+            app,
+            world,
+            focus,
+            levels,
+        }
+    }
+
+    impl Streaming {
+        fn focus_at(&mut self, x: f32) -> Vec<u128> {
+            self.app
+                .world_mut()
+                .entity_mut(self.focus)
+                .insert(GlobalTransform::from_translation(Vec3::new(x, -50.0, 0.0)));
+            self.app.update();
+
+            let mut iids: Vec<u128> = self
+                .app
+                .world()
+                .get::<ShieldtankStreamedLevels>(self.world)
+                .into_iter()
+                .flat_map(ShieldtankStreamedLevels::iter)
+                .map(|iid| iid.as_u128())
+                .collect();
+            iids.sort();
+            iids
+        }
+
+        /// Spawns the level at `index` as the reconcile pass would, with its bounds at `x`.
+        fn spawn_level(&mut self, index: usize, x: f32) {
+            self.app.world_mut().spawn((
+                ShieldtankLevel::new(self.levels[index].clone()),
+                ShieldtankWorldBounds::new(Vec2::new(x, 0.0), Vec2::new(x + 100.0, -100.0)),
+                ChildOf(self.world),
+            ));
+        }
+    }
+
+    #[test]
+    fn streams_levels_within_the_preload_radius() {
+        let mut streaming = streaming(0.0);
+
+        assert_eq!(streaming.focus_at(50.0), vec![1]);
+        assert_eq!(streaming.focus_at(190.0), vec![1]);
+        assert_eq!(streaming.focus_at(250.0), Vec::<u128>::new());
+        assert_eq!(streaming.focus_at(310.0), vec![2]);
+        assert_eq!(streaming.focus_at(450.0), vec![2]);
+    }
+
+    #[test]
+    fn unspawned_levels_are_placed_relative_to_the_world() {
+        let mut streaming = streaming(1000.0);
+
+        assert_eq!(streaming.focus_at(50.0), Vec::<u128>::new());
+        assert_eq!(streaming.focus_at(1050.0), vec![1]);
+        assert_eq!(streaming.focus_at(1450.0), vec![2]);
+    }
+
+    #[test]
+    fn spawned_levels_are_kept_within_the_unload_margin() {
+        let mut streaming = streaming(0.0);
+
+        assert_eq!(streaming.focus_at(320.0), vec![2]);
+        streaming.spawn_level(1, 400.0);
+
+        // Outside the preload radius, but within the unload margin of the spawned level.
+        assert_eq!(streaming.focus_at(270.0), vec![2]);
+        assert_eq!(streaming.focus_at(240.0), Vec::<u128>::new());
+
+        // Unspawned levels only stream in at the preload radius.
+        assert_eq!(streaming.focus_at(190.0), vec![1]);
+    }
+
+    #[test]
+    fn streamed_levels_are_removed_with_streaming() {
+        let mut streaming = streaming(0.0);
+        assert_eq!(streaming.focus_at(50.0), vec![1]);
+
+        streaming
+            .app
+            .world_mut()
+            .entity_mut(streaming.world)
+            .remove::<ShieldtankLevelStreaming>();
+        streaming.app.update();
+
+        assert!(
+            streaming
+                .app
+                .world()
+                .get::<ShieldtankStreamedLevels>(streaming.world)
+                .is_none()
+        );
+    }
+}
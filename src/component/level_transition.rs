@@ -0,0 +1,371 @@
+use std::cmp::Ordering;
+
+use bevy_app::Plugin;
+use bevy_ecs::change_detection::DetectChanges;
+use bevy_ecs::component::Component;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::event::EntityEvent;
+use bevy_ecs::lifecycle::RemovedComponents;
+use bevy_ecs::query::{Changed, With};
+use bevy_ecs::schedule::IntoScheduleConfigs;
+use bevy_ecs::system::{Commands, Query};
+use bevy_ecs::world::Ref;
+use bevy_ldtk_asset::iid::Iid;
+use bevy_ldtk_asset::level::NeighbourDir;
+use bevy_math::{Rect, Vec2};
+use bevy_reflect::Reflect;
+use bevy_transform::TransformSystems;
+use bevy_transform::components::GlobalTransform;
+
+use crate::query::level_neighbours::{LevelNeighbours, copy_neighbour_dir};
+
+use super::iid::ShieldtankIid;
+use super::level::{ShieldtankLevel, level_global_bounds_system};
+use super::shieldtank_component::ShieldtankComponentSystemSet;
+use super::world_bounds::ShieldtankWorldBounds;
+
+/// Add to an entity, such as the player, to trigger [LevelExited] and [LevelEntered] on it as it
+/// moves between levels, or as levels spawn and despawn around it.
+#[derive(Clone, Copy, Debug, Default, Component, Reflect)]
+pub struct ShieldtankLevelTracker {
+    current_level: Option<Entity>,
+    /// Kept so [LevelExited] can still be triggered once the level is despawned.
+    current_level_iid: Option<Iid>,
+}
+
+impl ShieldtankLevelTracker {
+    /// The level entity the tracked entity is in, if any.
+    pub fn current_level(&self) -> Option<Entity> {
+        self.current_level
+    }
+}
+
+/// Triggered on a tracked entity as it leaves a level.
+#[derive(Debug, EntityEvent)]
+pub struct LevelExited {
+    pub entity: Entity,
+    pub level: Entity,
+    pub level_iid: Iid,
+    /// The side of the level the entity left through, or [NeighbourDir::Overlap] when the level
+    /// was despawned under it.
+    pub direction: NeighbourDir,
+    /// The level being entered, if any.
+    pub destination: Option<Iid>,
+}
+
+/// Triggered on a tracked entity as it enters a level.
+#[derive(Debug, EntityEvent)]
+pub struct LevelEntered {
+    pub entity: Entity,
+    pub level: Entity,
+    pub level_iid: Iid,
+    /// The direction the entity was travelling in, or [NeighbourDir::Overlap] when it didn't come
+    /// from another level.
+    pub direction: NeighbourDir,
+    /// The level which was left, if any.
+    pub origin: Option<Iid>,
+}
+
+/// Which side of `rect` the point lies past, with y pointing up.
+fn exit_direction(rect: Rect, point: Vec2) -> NeighbourDir {
+    let x = match point.x {
+        x if x < rect.min.x => Ordering::Less,
+        x if x > rect.max.x => Ordering::Greater,
+        _ => Ordering::Equal,
+    };
+
+    let y = match point.y {
+        y if y < rect.min.y => Ordering::Less,
+        y if y > rect.max.y => Ordering::Greater,
+        _ => Ordering::Equal,
+    };
+
+    match (x, y) {
+        (Ordering::Equal, Ordering::Greater) => NeighbourDir::North,
+        (Ordering::Greater, Ordering::Greater) => NeighbourDir::NorthEast,
+        (Ordering::Greater, Ordering::Equal) => NeighbourDir::East,
+        (Ordering::Greater, Ordering::Less) => NeighbourDir::SouthEast,
+        (Ordering::Equal, Ordering::Less) => NeighbourDir::South,
+        (Ordering::Less, Ordering::Less) => NeighbourDir::SouthWest,
+        (Ordering::Less, Ordering::Equal) => NeighbourDir::West,
+        (Ordering::Less, Ordering::Greater) => NeighbourDir::NorthWest,
+        (Ordering::Equal, Ordering::Equal) => NeighbourDir::Overlap,
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn level_transition_system(
+    mut tracker_query: Query<(Entity, &mut ShieldtankLevelTracker, Ref<GlobalTransform>)>,
+    level_query: Query<
+        (
+            Entity,
+            &ShieldtankIid,
+            &ShieldtankWorldBounds,
+            &GlobalTransform,
+        ),
+        With<ShieldtankLevel>,
+    >,
+    changed_level_query: Query<(), (With<ShieldtankLevel>, Changed<ShieldtankWorldBounds>)>,
+    mut removed_bounds: RemovedComponents<ShieldtankWorldBounds>,
+    level_neighbours: LevelNeighbours,
+    mut commands: Commands,
+) {
+    // Levels spawning, despawning or moving can move a tracker between levels without it moving.
+    let bounds_removed = removed_bounds.read().count() > 0;
+    let levels_changed = bounds_removed || !changed_level_query.is_empty();
+
+    tracker_query
+        .iter_mut()
+        .for_each(|(entity, mut tracker, global_transform)| {
+            if !levels_changed && !global_transform.is_changed() {
+                return;
+            }
+
+            let location = global_transform.translation().truncate();
+
+            let current = tracker
+                .current_level
+                .and_then(|level| level_query.get(level).ok());
+
+            if current.is_some_and(|(_, _, bounds, _)| bounds.contains(location)) {
+                return;
+            }
+
+            // Prefer the topmost level where levels overlap.
+            let next = level_query
+                .iter()
+                .filter(|(_, _, bounds, _)| bounds.contains(location))
+                .max_by(|(.., a), (.., b)| {
+                    a.translation()
+                        .z
+                        .partial_cmp(&b.translation().z)
+                        .unwrap_or(Ordering::Equal)
+                });
+
+            // The current level, even when it has been despawned.
+            let exited = match current {
+                Some((level, iid, ..)) => Some((level, **iid)),
+                None => tracker.current_level.zip(tracker.current_level_iid),
+            };
+
+            if exited.is_none() && next.is_none() {
+                return;
+            }
+
+            let exited_iid = exited.map(|(_, iid)| iid);
+            let next_iid = next.map(|(_, iid, _, _)| **iid);
+
+            let direction = match (current, next_iid) {
+                (Some((level, ..)), Some(next_iid)) => level_neighbours
+                    .neighbours(level)
+                    .find(|(_, level_iid)| *level_iid == next_iid)
+                    .map(|(dir, _)| copy_neighbour_dir(dir)),
+                _ => None,
+            }
+            .or_else(|| current.map(|(_, _, bounds, _)| exit_direction(bounds.bounds(), location)))
+            .unwrap_or(NeighbourDir::Overlap);
+
+            if let Some((level, level_iid)) = exited {
+                commands.trigger(LevelExited {
+                    entity,
+                    level,
+                    level_iid,
+                    direction: copy_neighbour_dir(&direction),
+                    destination: next_iid,
+                });
+            }
+
+            if let Some((level, iid, ..)) = next {
+                commands.trigger(LevelEntered {
+                    entity,
+                    level,
+                    level_iid: **iid,
+                    direction,
+                    origin: exited_iid,
+                });
+            }
+
+            tracker.current_level = next.map(|(level, ..)| level);
+            tracker.current_level_iid = next_iid;
+        });
+}
+
+pub struct LevelTransitionPlugin;
+impl Plugin for LevelTransitionPlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.register_type::<ShieldtankLevelTracker>();
+        app.add_systems(
+            ShieldtankComponentSystemSet,
+            level_transition_system
+                .after(TransformSystems::Propagate)
+                .after(level_global_bounds_system),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::Update;
+    use bevy_ecs::observer::On;
+    use bevy_ecs::resource::Resource;
+    use bevy_ecs::system::ResMut;
+    use bevy_math::Vec3;
+
+    use crate::component::iid::IidRegistry;
+    use crate::test::test_app;
+
+    use super::*;
+
+    #[derive(Default, Resource)]
+    struct Transitions(Vec<String>);
+
+    fn app() -> bevy_app::App {
+        let mut app = test_app();
+        app.init_resource::<IidRegistry>();
+        app.init_resource::<Transitions>();
+        app.add_systems(Update, level_transition_system);
+        app.add_observer(
+            |exited: On<LevelExited>, mut transitions: ResMut<Transitions>| {
+                transitions.0.push(format!(
+                    "exited {} {:?}",
+                    exited.level_iid.as_u128(),
+                    exited.direction
+                ));
+            },
+        );
+        app.add_observer(
+            |entered: On<LevelEntered>, mut transitions: ResMut<Transitions>| {
+                transitions.0.push(format!(
+                    "entered {} {:?} from {:?}",
+                    entered.level_iid.as_u128(),
+                    entered.direction,
+                    entered.origin.map(|origin| origin.as_u128())
+                ));
+            },
+        );
+        app
+    }
+
+    /// A level whose top left corner is at `x`, 100 pixels wide and tall.
+    fn spawn_level(app: &mut bevy_app::App, iid: u128, x: f32) -> Entity {
+        app.world_mut()
+            .spawn((
+                ShieldtankLevel::default(),
+                ShieldtankIid::new(Iid::from_u128(iid)),
+                level_bounds_at(x),
+            ))
+            .id()
+    }
+
+    fn level_bounds_at(x: f32) -> ShieldtankWorldBounds {
+        ShieldtankWorldBounds::new(Vec2::new(x, 0.0), Vec2::new(x + 100.0, -100.0))
+    }
+
+    fn move_to(app: &mut bevy_app::App, entity: Entity, x: f32) {
+        app.world_mut()
+            .entity_mut(entity)
+            .insert(GlobalTransform::from_translation(Vec3::new(x, -50.0, 0.0)));
+    }
+
+    fn transitions(app: &mut bevy_app::App) -> Vec<String> {
+        std::mem::take(&mut app.world_mut().resource_mut::<Transitions>().0)
+    }
+
+    #[test]
+    fn enters_and_exits_levels_as_the_tracker_moves() {
+        let mut app = app();
+        let first = spawn_level(&mut app, 1, 0.0);
+        spawn_level(&mut app, 2, 100.0);
+
+        let tracker = app
+            .world_mut()
+            .spawn((
+                ShieldtankLevelTracker::default(),
+                GlobalTransform::default(),
+            ))
+            .id();
+        move_to(&mut app, tracker, 50.0);
+        app.update();
+
+        assert_eq!(transitions(&mut app), ["entered 1 Overlap from None"]);
+        assert_eq!(
+            app.world()
+                .get::<ShieldtankLevelTracker>(tracker)
+                .unwrap()
+                .current_level(),
+            Some(first)
+        );
+
+        move_to(&mut app, tracker, 60.0);
+        app.update();
+        assert!(transitions(&mut app).is_empty());
+
+        move_to(&mut app, tracker, 150.0);
+        app.update();
+        assert_eq!(
+            transitions(&mut app),
+            ["exited 1 East", "entered 2 East from Some(1)"]
+        );
+
+        move_to(&mut app, tracker, 250.0);
+        app.update();
+        assert_eq!(transitions(&mut app), ["exited 2 East"]);
+    }
+
+    #[test]
+    fn exits_levels_despawned_under_the_tracker() {
+        let mut app = app();
+        let level = spawn_level(&mut app, 1, 0.0);
+
+        let tracker = app
+            .world_mut()
+            .spawn((
+                ShieldtankLevelTracker::default(),
+                GlobalTransform::default(),
+            ))
+            .id();
+        move_to(&mut app, tracker, 50.0);
+        app.update();
+        transitions(&mut app);
+
+        app.world_mut().despawn(level);
+        app.update();
+
+        assert_eq!(transitions(&mut app), ["exited 1 Overlap"]);
+        assert_eq!(
+            app.world()
+                .get::<ShieldtankLevelTracker>(tracker)
+                .unwrap()
+                .current_level(),
+            None
+        );
+    }
+
+    #[test]
+    fn follows_levels_moving_without_the_tracker() {
+        let mut app = app();
+        let level = spawn_level(&mut app, 1, 0.0);
+
+        let tracker = app
+            .world_mut()
+            .spawn((
+                ShieldtankLevelTracker::default(),
+                GlobalTransform::default(),
+            ))
+            .id();
+        move_to(&mut app, tracker, 50.0);
+        app.update();
+        transitions(&mut app);
+
+        app.world_mut()
+            .entity_mut(level)
+            .insert(level_bounds_at(200.0));
+        spawn_level(&mut app, 2, 0.0);
+        app.update();
+
+        assert_eq!(
+            transitions(&mut app),
+            ["exited 1 West", "entered 2 West from Some(1)"]
+        );
+    }
+}
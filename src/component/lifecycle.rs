@@ -0,0 +1,407 @@
+use bevy_app::Plugin;
+use bevy_asset::prelude::AssetChanged;
+use bevy_asset::{AsAssetId, AssetId, Assets};
+use bevy_ecs::component::Component;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::event::EntityEvent;
+use bevy_ecs::hierarchy::{ChildOf, Children};
+use bevy_ecs::lifecycle::{Add, Remove, RemovedComponents};
+use bevy_ecs::message::MessageReader;
+use bevy_ecs::observer::On;
+use bevy_ecs::query::{Changed, Has, Or, With, Without};
+use bevy_ecs::schedule::IntoScheduleConfigs;
+use bevy_ecs::system::{Commands, Query, Res};
+use bevy_ldtk_asset::entity::EntityInstance;
+use bevy_ldtk_asset::iid::Iid;
+use bevy_ldtk_asset::layer::LayerInstance;
+use bevy_ldtk_asset::level::Level as LevelAsset;
+use bevy_platform::collections::{HashMap, HashSet};
+use bevy_reflect::Reflect;
+use bevy_sprite::Sprite;
+
+use super::entity::ShieldtankEntity;
+use super::filter::ShieldtankComponentFilter;
+use super::iid::ShieldtankIid;
+use super::layer::ShieldtankLayer;
+use super::layer_tiles::LdtkLayerTiles;
+use super::level::ShieldtankLevel;
+use super::level_background::image::LevelBackgroundImage;
+use super::project::LdtkProject;
+use super::shieldtank_component::ShieldtankComponentSystemSet;
+use super::spawn_children::{ShieldtankReloadDiff, SpawnChildren};
+use super::tile::ShieldtankTile;
+use super::world::ShieldtankWorld;
+use super::world_bounds::ShieldtankWorldBounds;
+
+macro_rules! lifecycle_event {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, EntityEvent)]
+        pub struct $name {
+            pub entity: Entity,
+            pub iid: Iid,
+        }
+    };
+}
+
+lifecycle_event!(
+    /// Triggered once an [LdtkProject]'s asset has loaded and its [ShieldtankIid] is inserted.
+    ProjectSpawned
+);
+lifecycle_event!(
+    /// Triggered once a [ShieldtankWorld]'s asset has loaded and its [ShieldtankIid] is inserted.
+    WorldSpawned
+);
+lifecycle_event!(
+    /// Triggered once a [ShieldtankLevel]'s asset has loaded and its [ShieldtankIid] is inserted.
+    LevelSpawned
+);
+lifecycle_event!(
+    /// Triggered once a [ShieldtankLayer]'s asset has loaded and its [ShieldtankIid] is inserted.
+    LayerSpawned
+);
+lifecycle_event!(
+    /// Triggered once a [ShieldtankEntity]'s asset has loaded and its [ShieldtankIid] is inserted.
+    EntitySpawned
+);
+lifecycle_event!(
+    /// Triggered once every layer and entity of a level has spawned, has its
+    /// [ShieldtankWorldBounds], and has its sprite or baked layer image in place.
+    ///
+    /// Triggered again after the level's asset is reloaded.
+    LevelFullyReady
+);
+lifecycle_event!(ProjectDespawned);
+lifecycle_event!(WorldDespawned);
+lifecycle_event!(LevelDespawned);
+lifecycle_event!(LayerDespawned);
+lifecycle_event!(EntityDespawned);
+
+/// Inserted on a level once [LevelFullyReady] was triggered for it.
+#[derive(Clone, Copy, Debug, Default, Component, Reflect)]
+pub struct ShieldtankLevelReady;
+
+type LifecycleKind<'a> = (
+    &'a ShieldtankIid,
+    Has<LdtkProject>,
+    Has<ShieldtankWorld>,
+    Has<ShieldtankLevel>,
+    Has<ShieldtankLayer>,
+    Has<ShieldtankEntity>,
+);
+
+fn spawned_observer(
+    add: On<Add, ShieldtankIid>,
+    query: Query<LifecycleKind>,
+    mut commands: Commands,
+) {
+    let entity = add.entity;
+
+    let Ok((iid, project, world, level, layer, ldtk_entity)) = query.get(entity) else {
+        return;
+    };
+
+    let iid = **iid;
+
+    if project {
+        commands.trigger(ProjectSpawned { entity, iid });
+    }
+    if world {
+        commands.trigger(WorldSpawned { entity, iid });
+    }
+    if level {
+        commands.trigger(LevelSpawned { entity, iid });
+    }
+    if layer {
+        commands.trigger(LayerSpawned { entity, iid });
+    }
+    if ldtk_entity {
+        commands.trigger(EntitySpawned { entity, iid });
+    }
+}
+
+fn despawned_observer(
+    remove: On<Remove, ShieldtankIid>,
+    query: Query<LifecycleKind>,
+    mut commands: Commands,
+) {
+    let entity = remove.entity;
+
+    let Ok((iid, project, world, level, layer, ldtk_entity)) = query.get(entity) else {
+        return;
+    };
+
+    let iid = **iid;
+
+    if project {
+        commands.trigger(ProjectDespawned { entity, iid });
+    }
+    if world {
+        commands.trigger(WorldDespawned { entity, iid });
+    }
+    if level {
+        commands.trigger(LevelDespawned { entity, iid });
+    }
+    if layer {
+        commands.trigger(LayerDespawned { entity, iid });
+    }
+    if ldtk_entity {
+        commands.trigger(EntityDespawned { entity, iid });
+    }
+}
+
+fn spawned_children<C: Component + AsAssetId>(
+    children: Option<&Children>,
+    query: &Query<&C, With<ShieldtankIid>>,
+) -> HashMap<AssetId<C::Asset>, Entity> {
+    children
+        .into_iter()
+        .flatten()
+        .copied()
+        .filter_map(|child| Some((query.get(child).ok()?.as_asset_id(), child)))
+        .collect()
+}
+
+#[allow(clippy::type_complexity)]
+#[allow(clippy::too_many_arguments)]
+fn level_fully_ready_system(
+    level_query: Query<
+        (
+            Entity,
+            &ShieldtankLevel,
+            &ShieldtankIid,
+            Option<&Children>,
+            Option<&ShieldtankComponentFilter>,
+            Has<LevelBackgroundImage>,
+            Has<Sprite>,
+        ),
+        (With<ShieldtankWorldBounds>, Without<ShieldtankLevelReady>),
+    >,
+    layer_query: Query<(
+        &ShieldtankLayer,
+        Option<&Children>,
+        Option<&ShieldtankComponentFilter>,
+        Has<ShieldtankWorldBounds>,
+        Has<LdtkLayerTiles>,
+        Has<Sprite>,
+    )>,
+    entity_query: Query<(Has<ShieldtankWorldBounds>, Has<ShieldtankTile>, Has<Sprite>)>,
+    spawned_layer_query: Query<&ShieldtankLayer, With<ShieldtankIid>>,
+    spawned_entity_query: Query<&ShieldtankEntity, With<ShieldtankIid>>,
+    level_assets: Res<Assets<LevelAsset>>,
+    layer_assets: Res<Assets<LayerInstance>>,
+    entity_assets: Res<Assets<EntityInstance>>,
+    mut commands: Commands,
+) {
+    let layer_ready = |layer: Entity| -> bool {
+        let Ok((component, children, filter, bounds, layer_tiles, sprite)) = layer_query.get(layer)
+        else {
+            return false;
+        };
+
+        let Some(asset) = layer_assets.get(component.as_asset_id()) else {
+            return false;
+        };
+
+        let spawned_entities = spawned_children(children, &spawned_entity_query);
+
+        let entities_spawned = component
+            .wanted_children(asset, &entity_assets, filter, None)
+            .iter()
+            .all(|entity_handle| spawned_entities.contains_key(&entity_handle.id()));
+
+        let entities_ready = spawned_entities.values().all(|entity| {
+            entity_query
+                .get(*entity)
+                .is_ok_and(|(bounds, tile, sprite)| bounds && (!tile || sprite))
+        });
+
+        bounds && (!layer_tiles || sprite) && entities_spawned && entities_ready
+    };
+
+    level_query.iter().for_each(
+        |(entity, component, iid, children, filter, background_image, sprite)| {
+            if background_image && !sprite {
+                return;
+            }
+
+            let Some(asset) = level_assets.get(component.as_asset_id()) else {
+                return;
+            };
+
+            let spawned_layers = spawned_children(children, &spawned_layer_query);
+
+            let layers_spawned = component
+                .wanted_children(asset, &layer_assets, filter, None)
+                .iter()
+                .all(|layer_handle| spawned_layers.contains_key(&layer_handle.id()));
+
+            if layers_spawned && spawned_layers.values().copied().all(layer_ready) {
+                commands.entity(entity).insert(ShieldtankLevelReady);
+                commands.trigger(LevelFullyReady { entity, iid: **iid });
+            }
+        },
+    );
+}
+
+/// Removes [ShieldtankLevelReady] from levels which have to be waited on again: when the level
+/// is reloaded, when its children or those of its layers are reconciled, or when the filter of the
+/// level or one of its layers changes.
+#[allow(clippy::type_complexity)]
+fn level_ready_reset_system(
+    changed_query: Query<
+        Entity,
+        Or<(
+            Changed<ShieldtankLevel>,
+            AssetChanged<ShieldtankLevel>,
+            Changed<ShieldtankComponentFilter>,
+        )>,
+    >,
+    ready_query: Query<(), With<ShieldtankLevelReady>>,
+    parent_query: Query<&ChildOf>,
+    mut removed_filters: RemovedComponents<ShieldtankComponentFilter>,
+    mut reload_diffs: MessageReader<ShieldtankReloadDiff>,
+    mut commands: Commands,
+) {
+    let levels: HashSet<Entity> = changed_query
+        .iter()
+        .chain(removed_filters.read())
+        .chain(reload_diffs.read().map(|diff| diff.parent))
+        // A change to a layer resets the level it belongs to.
+        .flat_map(|entity| {
+            std::iter::once(entity).chain(parent_query.get(entity).map(ChildOf::parent))
+        })
+        .filter(|entity| ready_query.contains(*entity))
+        .collect();
+
+    levels.into_iter().for_each(|entity| {
+        commands.entity(entity).remove::<ShieldtankLevelReady>();
+    });
+}
+
+pub struct LifecyclePlugin;
+impl Plugin for LifecyclePlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.register_type::<ShieldtankLevelReady>();
+        app.add_observer(spawned_observer);
+        app.add_observer(despawned_observer);
+        app.add_systems(
+            ShieldtankComponentSystemSet,
+            (level_ready_reset_system, level_fully_ready_system).chain(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::Update;
+
+    use crate::component::shieldtank_component::ShieldtankComponent;
+    use crate::test::test_app;
+
+    use super::*;
+
+    struct ReadyLevel {
+        app: bevy_app::App,
+        level: Entity,
+        layer: Entity,
+    }
+
+    impl ReadyLevel {
+        fn new() -> Self {
+            let mut app = test_app();
+            app.add_message::<ShieldtankReloadDiff>();
+            app.add_systems(Update, level_ready_reset_system);
+
+            let level = app
+                .world_mut()
+                .spawn(ShieldtankLevel::new(Default::default()))
+                .id();
+            let layer = app
+                .world_mut()
+                .spawn((ShieldtankLayer::new(Default::default()), ChildOf(level)))
+                .id();
+            app.update();
+
+            app.world_mut()
+                .entity_mut(level)
+                .insert(ShieldtankLevelReady);
+            app.update();
+
+            Self { app, level, layer }
+        }
+
+        fn is_ready(&self) -> bool {
+            self.app
+                .world()
+                .entity(self.level)
+                .contains::<ShieldtankLevelReady>()
+        }
+
+        fn reconcile(&mut self, parent: Entity) {
+            self.app.world_mut().write_message(ShieldtankReloadDiff {
+                parent,
+                added: vec![Iid::from_u128(1)],
+                removed: vec![],
+                updated: vec![],
+            });
+            self.app.update();
+        }
+    }
+
+    #[test]
+    fn stays_ready_without_changes() {
+        let mut ready_level = ReadyLevel::new();
+        ready_level.app.update();
+
+        assert!(ready_level.is_ready());
+    }
+
+    #[test]
+    fn resets_when_the_children_of_the_level_are_reconciled() {
+        let mut ready_level = ReadyLevel::new();
+        let level = ready_level.level;
+        ready_level.reconcile(level);
+
+        assert!(!ready_level.is_ready());
+    }
+
+    #[test]
+    fn resets_when_the_children_of_a_layer_are_reconciled() {
+        let mut ready_level = ReadyLevel::new();
+        let layer = ready_level.layer;
+        ready_level.reconcile(layer);
+
+        assert!(!ready_level.is_ready());
+    }
+
+    #[test]
+    fn resets_when_a_filter_changes() {
+        let mut ready_level = ReadyLevel::new();
+        let layer = ready_level.layer;
+        ready_level
+            .app
+            .world_mut()
+            .entity_mut(layer)
+            .insert(ShieldtankComponentFilter::default());
+        ready_level.app.update();
+        assert!(!ready_level.is_ready());
+
+        let level = ready_level.level;
+        ready_level
+            .app
+            .world_mut()
+            .entity_mut(level)
+            .insert(ShieldtankLevelReady);
+        ready_level.app.update();
+        assert!(ready_level.is_ready());
+
+        ready_level
+            .app
+            .world_mut()
+            .entity_mut(layer)
+            .remove::<ShieldtankComponentFilter>();
+        ready_level.app.update();
+        assert!(!ready_level.is_ready());
+    }
+}
@@ -0,0 +1,414 @@
+use bevy_app::Plugin;
+use bevy_asset::{AsAssetId, AssetId, Assets};
+use bevy_ecs::change_detection::DetectChangesMut;
+use bevy_ecs::component::Component;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::hierarchy::Children;
+use bevy_ecs::message::MessageReader;
+use bevy_ecs::query::{Has, With};
+use bevy_ecs::system::{Query, Res, SystemParam};
+use bevy_image::Image;
+use bevy_ldtk_asset::prelude::LdtkAsset;
+use bevy_ldtk_asset::project::Project;
+use bevy_ldtk_asset::tileset_definition::TilesetDefinition as TilesetDefinitionAsset;
+use bevy_platform::collections::HashSet;
+use bevy_reflect::Reflect;
+use bevy_sprite::Sprite;
+
+use super::filter::ShieldtankComponentFilter;
+use super::iid::ShieldtankIid;
+use super::layer::ShieldtankLayer;
+use super::layer_tiles::LdtkLayerTiles;
+use super::level::ShieldtankLevel;
+use super::level_streaming::ShieldtankStreamedLevels;
+use super::project::LdtkProject;
+use super::shieldtank_component::ShieldtankComponentSystemSet;
+use super::spawn_children::{ChildAsset, ShieldtankReloadDiff, SpawnChildren};
+use super::tileset_definition::ShieldtankTilesetDefinition;
+use super::world::ShieldtankWorld;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub struct ShieldtankLoadCount {
+    pub loaded: usize,
+    pub total: usize,
+}
+
+impl ShieldtankLoadCount {
+    pub fn pending(&self) -> usize {
+        self.total.saturating_sub(self.loaded)
+    }
+}
+
+/// Tracks how far along the spawning of an [LdtkProject] is. Insert it next to the project to
+/// track it.
+///
+/// Worlds, levels, layers and entities count as loaded once their asset is available and their
+/// [ShieldtankIid] is inserted. Totals only include children of loaded parents, so they grow as
+/// the hierarchy is discovered. Once complete, progress is only counted again after a reconcile
+/// pass changes the hierarchy, such as a hot reload or a level streaming in.
+#[derive(Clone, Debug, Default, PartialEq, Component, Reflect)]
+pub struct ShieldtankLoadProgress {
+    pub project_loaded: bool,
+    pub worlds: ShieldtankLoadCount,
+    pub levels: ShieldtankLoadCount,
+    pub layers: ShieldtankLoadCount,
+    pub entities: ShieldtankLoadCount,
+    /// Tileset images used by tile layers and entity tiles.
+    pub tileset_images: ShieldtankLoadCount,
+    /// Tile layers waiting for their sprite image to be generated.
+    pub layer_bakes: ShieldtankLoadCount,
+}
+
+impl ShieldtankLoadProgress {
+    fn counts(&self) -> [ShieldtankLoadCount; 6] {
+        [
+            self.worlds,
+            self.levels,
+            self.layers,
+            self.entities,
+            self.tileset_images,
+            self.layer_bakes,
+        ]
+    }
+
+    pub fn pending(&self) -> usize {
+        let project_pending = usize::from(!self.project_loaded);
+        project_pending
+            + self
+                .counts()
+                .iter()
+                .map(ShieldtankLoadCount::pending)
+                .sum::<usize>()
+    }
+
+    /// Overall completion, from `0.0` to `1.0`.
+    pub fn fraction(&self) -> f32 {
+        let loaded = usize::from(self.project_loaded)
+            + self
+                .counts()
+                .iter()
+                .map(|count| count.loaded)
+                .sum::<usize>();
+        let total = 1 + self.counts().iter().map(|count| count.total).sum::<usize>();
+
+        loaded as f32 / total as f32
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.pending() == 0
+    }
+}
+
+#[allow(clippy::type_complexity)]
+#[derive(SystemParam)]
+struct ChildProgress<'w, 's, P>
+where
+    P: SpawnChildren,
+    <P as AsAssetId>::Asset: LdtkAsset,
+    ChildAsset<P>: LdtkAsset,
+{
+    parent_query: Query<
+        'w,
+        's,
+        (
+            &'static P,
+            Option<&'static Children>,
+            Option<&'static ShieldtankComponentFilter>,
+            Option<&'static ShieldtankStreamedLevels>,
+        ),
+    >,
+    child_query: Query<'w, 's, &'static <P as SpawnChildren>::Child, With<ShieldtankIid>>,
+    assets: Res<'w, Assets<<P as AsAssetId>::Asset>>,
+    child_assets: Res<'w, Assets<ChildAsset<P>>>,
+}
+
+impl<P> ChildProgress<'_, '_, P>
+where
+    P: SpawnChildren,
+    <P as AsAssetId>::Asset: LdtkAsset,
+    ChildAsset<P>: LdtkAsset,
+{
+    /// Counts the wanted children of `parents`, returning the ones which are loaded.
+    fn count(&self, parents: &[Entity], count: &mut ShieldtankLoadCount) -> Vec<Entity> {
+        parents
+            .iter()
+            .filter_map(|parent| self.parent_query.get(*parent).ok())
+            .filter_map(|(component, children, filter, streamed)| {
+                Some((
+                    component,
+                    children,
+                    filter,
+                    streamed,
+                    self.assets.get(component.as_asset_id())?,
+                ))
+            })
+            .flat_map(|(component, children, filter, streamed, asset)| {
+                let wanted_ids: HashSet<AssetId<ChildAsset<P>>> = component
+                    .wanted_children(asset, &self.child_assets, filter, streamed)
+                    .iter()
+                    .map(|handle| handle.id())
+                    .collect();
+
+                count.total += wanted_ids.len();
+
+                let loaded: Vec<Entity> = children
+                    .into_iter()
+                    .flatten()
+                    .copied()
+                    .filter(|child| {
+                        self.child_query
+                            .get(*child)
+                            .is_ok_and(|child| wanted_ids.contains(&child.as_asset_id()))
+                    })
+                    .collect();
+
+                count.loaded += loaded.len();
+
+                loaded
+            })
+            .collect()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn load_progress_system(
+    mut query: Query<(Entity, &LdtkProject, &mut ShieldtankLoadProgress)>,
+    mut reload_diffs: MessageReader<ShieldtankReloadDiff>,
+    project_assets: Res<Assets<Project>>,
+    worlds: ChildProgress<LdtkProject>,
+    levels: ChildProgress<ShieldtankWorld>,
+    layers: ChildProgress<ShieldtankLevel>,
+    entities: ChildProgress<ShieldtankLayer>,
+    layer_tiles_query: Query<(Option<&LdtkLayerTiles>, Has<Sprite>)>,
+    tileset_definition_query: Query<&ShieldtankTilesetDefinition>,
+    tileset_definitions: Res<Assets<TilesetDefinitionAsset>>,
+    images: Res<Assets<Image>>,
+) {
+    let reconciled = reload_diffs.read().count() > 0;
+
+    query
+        .iter_mut()
+        .filter(|(_, _, load_progress)| reconciled || !load_progress.is_complete())
+        .for_each(|(entity, component, mut load_progress)| {
+            let mut progress = ShieldtankLoadProgress {
+                project_loaded: project_assets.contains(component.as_asset_id()),
+                ..Default::default()
+            };
+
+            let world_entities = worlds.count(&[entity], &mut progress.worlds);
+            let level_entities = levels.count(&world_entities, &mut progress.levels);
+            let layer_entities = layers.count(&level_entities, &mut progress.layers);
+            let entity_entities = entities.count(&layer_entities, &mut progress.entities);
+
+            let mut image_ids: HashSet<AssetId<Image>> = HashSet::new();
+
+            layer_entities
+                .iter()
+                .filter_map(|layer| layer_tiles_query.get(*layer).ok())
+                .filter_map(|(layer_tiles, sprite)| Some((layer_tiles?, sprite)))
+                .for_each(|(layer_tiles, sprite)| {
+                    image_ids.insert(layer_tiles.as_asset_id());
+
+                    progress.layer_bakes.total += 1;
+                    progress.layer_bakes.loaded += usize::from(sprite);
+                });
+
+            entity_entities
+                .iter()
+                .filter_map(|entity| tileset_definition_query.get(*entity).ok())
+                .for_each(|tileset_definition| {
+                    match tileset_definitions.get(tileset_definition.as_asset_id()) {
+                        Some(asset) => {
+                            image_ids.extend(asset.tileset_image.as_ref().map(|image| image.id()));
+                        }
+                        // The image isn't known until its definition loads.
+                        None => progress.tileset_images.total += 1,
+                    }
+                });
+
+            progress.tileset_images.total += image_ids.len();
+            progress.tileset_images.loaded += image_ids
+                .iter()
+                .filter(|image_id| images.contains(**image_id))
+                .count();
+
+            load_progress.set_if_neq(progress);
+        });
+}
+
+pub struct LoadProgressPlugin;
+impl Plugin for LoadProgressPlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.register_type::<ShieldtankLoadCount>();
+        app.register_type::<ShieldtankLoadProgress>();
+        app.add_systems(ShieldtankComponentSystemSet, load_progress_system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::App;
+    use bevy_asset::{AssetApp, Handle};
+    use bevy_ecs::bundle::Bundle;
+    use bevy_ecs::hierarchy::ChildOf;
+    use bevy_ldtk_asset::iid::Iid;
+    use bevy_ldtk_asset::level::Level as LevelAsset;
+    use bevy_ldtk_asset::world::{World as WorldAsset, WorldLayout};
+    use bevy_math::I64Vec2;
+
+    use crate::component::shieldtank_component::ShieldtankComponent;
+    use crate::test::{level_asset, test_app};
+
+    use super::*;
+
+    struct Loading {
+        app: App,
+        project: Entity,
+        world: Handle<WorldAsset>,
+        levels: Vec<Handle<LevelAsset>>,
+    }
+
+    /// A project with one world of two levels, with progress tracked when `tracked`.
+    fn loading(tracked: bool) -> Loading {
+        let mut app = test_app();
+        app.init_asset::<Image>();
+        app.add_message::<ShieldtankReloadDiff>();
+        app.add_plugins(LoadProgressPlugin);
+
+        let levels: Vec<_> = (1..=2)
+            .map(|iid| {
+                let level = level_asset(iid, I64Vec2::ZERO, I64Vec2::splat(16));
+                app.world_mut()
+                    .resource_mut::<Assets<LevelAsset>>()
+                    .add(level)
+            })
+            .collect();
+
+        let world = app
+            .world_mut()
+            .resource_mut::<Assets<WorldAsset>>()
+            .add(WorldAsset {
+                identifier: "World".to_string(),
+                iid: Iid::from_u128(10),
+                world_layout: WorldLayout::Free,
+                levels: levels
+                    .iter()
+                    .enumerate()
+                    .map(|(index, handle)| (Iid::from_u128(index as u128 + 1), handle.clone()))
+                    .collect(),
+            });
+
+        let project = app
+            .world_mut()
+            .resource_mut::<Assets<Project>>()
+            .add(Project {
+                iid: Iid::from_u128(20),
+                ldtk_version: "1.5.3".to_string(),
+                worlds: [(Iid::from_u128(10), world.clone())].into_iter().collect(),
+            });
+
+        let mut project = app.world_mut().spawn(LdtkProject { handle: project });
+        if tracked {
+            project.insert(ShieldtankLoadProgress::default());
+        }
+        let project = project.id();
+
+        Loading {
+            app,
+            project,
+            world,
+            levels,
+        }
+    }
+
+    impl Loading {
+        fn spawn_child(&mut self, parent: Entity, child: impl Bundle, iid: u128) -> Entity {
+            self.app
+                .world_mut()
+                .spawn((
+                    child,
+                    ShieldtankIid::new(Iid::from_u128(iid)),
+                    ChildOf(parent),
+                ))
+                .id()
+        }
+
+        fn progress(&mut self) -> ShieldtankLoadProgress {
+            self.app.update();
+            self.app
+                .world()
+                .get::<ShieldtankLoadProgress>(self.project)
+                .cloned()
+                .unwrap()
+        }
+    }
+
+    fn count(loaded: usize, total: usize) -> ShieldtankLoadCount {
+        ShieldtankLoadCount { loaded, total }
+    }
+
+    #[test]
+    fn counts_children_as_they_spawn() {
+        let mut loading = loading(true);
+
+        let progress = loading.progress();
+        assert!(progress.project_loaded);
+        assert_eq!(progress.worlds, count(0, 1));
+        assert_eq!(progress.levels, count(0, 0));
+        assert_eq!(progress.fraction(), 0.5);
+
+        let world = ShieldtankWorld::new(loading.world.clone());
+        let world = loading.spawn_child(loading.project, world, 10);
+        let progress = loading.progress();
+        assert_eq!(progress.worlds, count(1, 1));
+        assert_eq!(progress.levels, count(0, 2));
+        assert_eq!(progress.pending(), 2);
+
+        for (iid, handle) in loading.levels.clone().into_iter().enumerate() {
+            loading.spawn_child(world, ShieldtankLevel::new(handle), iid as u128 + 1);
+        }
+        let progress = loading.progress();
+        assert_eq!(progress.levels, count(2, 2));
+        assert!(progress.is_complete());
+        assert_eq!(progress.fraction(), 1.0);
+    }
+
+    #[test]
+    fn completed_progress_is_counted_again_after_a_reconcile() {
+        let mut loading = loading(true);
+
+        let world = ShieldtankWorld::new(loading.world.clone());
+        let world = loading.spawn_child(loading.project, world, 10);
+        let level = ShieldtankLevel::new(loading.levels[0].clone());
+        let level = loading.spawn_child(world, level, 1);
+        let other_level = ShieldtankLevel::new(loading.levels[1].clone());
+        loading.spawn_child(world, other_level, 2);
+        assert!(loading.progress().is_complete());
+
+        // Without a reconcile pass the complete hierarchy isn't walked again.
+        loading.app.world_mut().entity_mut(level).despawn();
+        assert!(loading.progress().is_complete());
+
+        loading.app.world_mut().write_message(ShieldtankReloadDiff {
+            parent: world,
+            added: vec![],
+            removed: vec![Iid::from_u128(1)],
+            updated: vec![],
+        });
+        assert_eq!(loading.progress().levels, count(1, 2));
+    }
+
+    #[test]
+    fn only_tracks_projects_which_ask_for_it() {
+        let mut loading = loading(false);
+        loading.app.update();
+
+        assert!(
+            loading
+                .app
+                .world()
+                .get::<ShieldtankLoadProgress>(loading.project)
+                .is_none()
+        );
+    }
+}
@@ -1,15 +1,32 @@
+pub mod auto_rules;
+pub mod colliders;
 pub mod entity;
 pub mod entity_definition;
+pub mod entity_ref;
+pub mod entity_registry;
 pub mod field_instances;
 pub mod filter;
+pub mod grid_coords;
+pub mod grid_edits;
 pub mod grid_values;
 pub mod iid;
+pub mod int_grid_cell;
+pub mod int_grid_rendering;
+pub mod kinematic;
 pub mod layer;
 pub mod layer_definition;
 pub mod layer_tiles;
+pub mod ldtk_fields;
 pub mod level;
 pub mod level_background;
+pub mod level_spawner;
+pub mod level_streaming;
+pub mod level_transition;
+pub mod lifecycle;
+pub mod load_progress;
+pub mod navigation;
 pub mod project;
+pub mod save_state;
 pub mod shieldtank_component;
 pub mod spawn_children;
 pub mod tags;
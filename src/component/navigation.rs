@@ -0,0 +1,586 @@
+//! A* pathfinding over IntGrid layers.
+//!
+//! Add [ShieldtankNavSettings] to a layer to build a [ShieldtankNavGraph] from its
+//! [ShieldtankGridValues]. The graph is rebuilt whenever the grid values or settings change, and
+//! [NavGridQuery] finds paths through it.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use bevy_app::Plugin;
+use bevy_ecs::component::Component;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::lifecycle::RemovedComponents;
+use bevy_ecs::query::{Changed, Or};
+use bevy_ecs::schedule::IntoScheduleConfigs;
+use bevy_ecs::system::{Commands, Query, SystemParam};
+use bevy_log::warn;
+use bevy_math::{I64Vec2, Vec2};
+use bevy_platform::collections::{HashMap, HashSet};
+use bevy_reflect::Reflect;
+
+use crate::query::layer_grid::LayerGridQuery;
+
+use super::grid_values::{
+    IntGridSelector, ShieldtankGridValue, ShieldtankGridValues, grid_values_system,
+};
+use super::shieldtank_component::ShieldtankComponentSystemSet;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum NavConnectivity {
+    #[default]
+    Four,
+    Eight,
+}
+
+/// Whether diagonal moves may pass the corner of an unwalkable cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum NavCornerCutting {
+    /// Both cells beside the diagonal must be walkable.
+    #[default]
+    Never,
+    /// One of the cells beside the diagonal must be walkable.
+    IfEitherWalkable,
+    Always,
+}
+
+/// The cost of entering the cells matching `selector`, or `None` when they aren't walkable.
+#[derive(Clone, Debug, PartialEq, Reflect)]
+pub struct NavCostRule {
+    pub selector: IntGridSelector,
+    pub cost: Option<f32>,
+}
+
+/// The cost non-positive costs are raised to.
+const MIN_COST: f32 = 0.001;
+
+/// Raises a non-positive cost to [MIN_COST], and blocks NaN or infinite costs, with a warning.
+fn validate_cost(cost: Option<f32>, name: impl std::fmt::Display) -> Option<f32> {
+    let cost = cost?;
+
+    if cost.is_nan() || cost == f32::INFINITY {
+        warn!("Nav cost of {name} is {cost}, treating it as blocked");
+        None
+    } else if cost <= 0.0 {
+        warn!("Nav cost of {name} is {cost}, raising it to {MIN_COST}");
+        Some(MIN_COST)
+    } else {
+        Some(cost)
+    }
+}
+
+/// Add to an IntGrid layer to navigate it.
+///
+/// Costs must be positive. When the graph is built, non-positive costs are raised to a small
+/// minimum and NaN or infinite costs block their cells, with a warning.
+#[derive(Clone, Debug, Component, Reflect)]
+pub struct ShieldtankNavSettings {
+    /// Checked in order, the first matching rule applies.
+    pub rules: Vec<NavCostRule>,
+    /// The cost of cells without an IntGrid value.
+    pub empty_cost: Option<f32>,
+    /// The cost of cells whose value matches no rule.
+    pub default_cost: Option<f32>,
+    pub connectivity: NavConnectivity,
+    pub corner_cutting: NavCornerCutting,
+}
+
+impl Default for ShieldtankNavSettings {
+    fn default() -> Self {
+        Self {
+            rules: vec![],
+            empty_cost: Some(1.0),
+            default_cost: None,
+            connectivity: NavConnectivity::default(),
+            corner_cutting: NavCornerCutting::default(),
+        }
+    }
+}
+
+impl ShieldtankNavSettings {
+    pub fn with_cost(mut self, selector: impl Into<IntGridSelector>, cost: f32) -> Self {
+        self.rules.push(NavCostRule {
+            selector: selector.into(),
+            cost: Some(cost),
+        });
+        self
+    }
+
+    pub fn with_blocked(mut self, selector: impl Into<IntGridSelector>) -> Self {
+        self.rules.push(NavCostRule {
+            selector: selector.into(),
+            cost: None,
+        });
+        self
+    }
+
+    pub fn with_empty_cost(mut self, cost: Option<f32>) -> Self {
+        self.empty_cost = cost;
+        self
+    }
+
+    pub fn with_default_cost(mut self, cost: Option<f32>) -> Self {
+        self.default_cost = cost;
+        self
+    }
+
+    pub fn with_connectivity(mut self, connectivity: NavConnectivity) -> Self {
+        self.connectivity = connectivity;
+        self
+    }
+
+    pub fn with_corner_cutting(mut self, corner_cutting: NavCornerCutting) -> Self {
+        self.corner_cutting = corner_cutting;
+        self
+    }
+
+    pub fn cost(&self, grid_value: Option<&ShieldtankGridValue>) -> Option<f32> {
+        let Some(grid_value) = grid_value else {
+            return self.empty_cost;
+        };
+
+        self.rules
+            .iter()
+            .find(|rule| rule.selector.matches(grid_value))
+            .map_or(self.default_cost, |rule| rule.cost)
+    }
+
+    /// A copy with every cost passed through [validate_cost].
+    fn validated(&self) -> Self {
+        let rules = self
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| NavCostRule {
+                selector: rule.selector.clone(),
+                cost: validate_cost(rule.cost, format_args!("rule {index}")),
+            })
+            .collect();
+
+        Self {
+            rules,
+            empty_cost: validate_cost(self.empty_cost, "empty cells"),
+            default_cost: validate_cost(self.default_cost, "unmatched cells"),
+            ..self.clone()
+        }
+    }
+}
+
+/// The cost of every cell of a layer, built from its [ShieldtankNavSettings].
+#[derive(Clone, Debug, Component, Reflect)]
+pub struct ShieldtankNavGraph {
+    size: I64Vec2,
+    costs: Vec<Option<f32>>,
+    connectivity: NavConnectivity,
+    corner_cutting: NavCornerCutting,
+    min_cost: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct OpenCell {
+    estimate: f32,
+    grid: I64Vec2,
+}
+
+impl PartialEq for OpenCell {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OpenCell {}
+
+impl PartialOrd for OpenCell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenCell {
+    // Reversed, so the heap pops the lowest estimate first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl ShieldtankNavGraph {
+    pub fn new(grid_values: &ShieldtankGridValues, settings: &ShieldtankNavSettings) -> Self {
+        let size = grid_values.size();
+        let settings = settings.validated();
+
+        let costs: Vec<Option<f32>> = (0..size.y)
+            .flat_map(|y| (0..size.x).map(move |x| I64Vec2::new(x, y)))
+            .map(|grid| settings.cost(grid_values.get(grid)))
+            .collect();
+
+        let min_cost = costs
+            .iter()
+            .flatten()
+            .copied()
+            .reduce(f32::min)
+            .unwrap_or(1.0);
+
+        Self {
+            size,
+            costs,
+            connectivity: settings.connectivity,
+            corner_cutting: settings.corner_cutting,
+            min_cost,
+        }
+    }
+
+    pub fn size(&self) -> I64Vec2 {
+        self.size
+    }
+
+    /// The cost of entering a cell, or `None` when it isn't walkable or is outside the layer.
+    pub fn cost(&self, grid: I64Vec2) -> Option<f32> {
+        if grid.cmplt(I64Vec2::ZERO).any() || grid.cmpge(self.size).any() {
+            return None;
+        }
+
+        self.costs[(grid.y * self.size.x + grid.x) as usize]
+    }
+
+    pub fn is_walkable(&self, grid: I64Vec2) -> bool {
+        self.cost(grid).is_some()
+    }
+
+    /// The walkable neighbours of a cell, along with the length of the step to them.
+    fn neighbours(&self, grid: I64Vec2) -> impl Iterator<Item = (I64Vec2, f32)> {
+        const ORTHOGONAL: [I64Vec2; 4] = [I64Vec2::X, I64Vec2::NEG_X, I64Vec2::Y, I64Vec2::NEG_Y];
+        const DIAGONAL: [I64Vec2; 4] = [
+            I64Vec2::new(1, 1),
+            I64Vec2::new(1, -1),
+            I64Vec2::new(-1, 1),
+            I64Vec2::new(-1, -1),
+        ];
+
+        let orthogonal = ORTHOGONAL.into_iter().map(move |step| (grid + step, 1.0));
+
+        let diagonal = DIAGONAL
+            .into_iter()
+            .filter(move |_| self.connectivity == NavConnectivity::Eight)
+            .filter(move |step| {
+                let beside_x = self.is_walkable(grid + I64Vec2::new(step.x, 0));
+                let beside_y = self.is_walkable(grid + I64Vec2::new(0, step.y));
+
+                match self.corner_cutting {
+                    NavCornerCutting::Never => beside_x && beside_y,
+                    NavCornerCutting::IfEitherWalkable => beside_x || beside_y,
+                    NavCornerCutting::Always => true,
+                }
+            })
+            .map(move |step| (grid + step, std::f32::consts::SQRT_2));
+
+        orthogonal
+            .chain(diagonal)
+            .filter(|(next, _)| self.is_walkable(*next))
+    }
+
+    fn heuristic(&self, from: I64Vec2, to: I64Vec2) -> f32 {
+        let delta = (to - from).abs().as_vec2();
+
+        let distance = match self.connectivity {
+            NavConnectivity::Four => delta.x + delta.y,
+            NavConnectivity::Eight => {
+                delta.max_element() + (std::f32::consts::SQRT_2 - 1.0) * delta.min_element()
+            }
+        };
+
+        distance * self.min_cost
+    }
+
+    /// The cheapest path from `start` to `goal`, including both, along with its cost.
+    pub fn find_path(&self, start: I64Vec2, goal: I64Vec2) -> Option<(Vec<I64Vec2>, f32)> {
+        if !self.is_walkable(start) || !self.is_walkable(goal) {
+            return None;
+        }
+
+        let mut open = BinaryHeap::from([OpenCell {
+            estimate: self.heuristic(start, goal),
+            grid: start,
+        }]);
+        let mut closed: HashSet<I64Vec2> = HashSet::new();
+        let mut came_from: HashMap<I64Vec2, I64Vec2> = HashMap::new();
+        let mut costs: HashMap<I64Vec2, f32> = HashMap::from_iter([(start, 0.0)]);
+
+        while let Some(OpenCell { grid, .. }) = open.pop() {
+            if grid == goal {
+                let mut path = vec![goal];
+                while let Some(previous) = came_from.get(path.last()?) {
+                    path.push(*previous);
+                }
+                path.reverse();
+
+                return Some((path, costs[&goal]));
+            }
+
+            if !closed.insert(grid) {
+                continue;
+            }
+
+            let cost = costs[&grid];
+
+            self.neighbours(grid).for_each(|(next, step)| {
+                let Some(next_cost) = self.cost(next) else {
+                    return;
+                };
+
+                let tentative = cost + step * next_cost;
+
+                if costs.get(&next).is_none_or(|known| tentative < *known) {
+                    costs.insert(next, tentative);
+                    came_from.insert(next, grid);
+                    open.push(OpenCell {
+                        estimate: tentative + self.heuristic(next, goal),
+                        grid: next,
+                    });
+                }
+            });
+        }
+
+        None
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NavPath {
+    pub cells: Vec<I64Vec2>,
+    /// The world position of the center of each cell.
+    pub points: Vec<Vec2>,
+    pub cost: f32,
+}
+
+/// Finds paths through layers with a [ShieldtankNavGraph].
+#[derive(SystemParam)]
+pub struct NavGridQuery<'w, 's> {
+    graph_query: Query<'w, 's, &'static ShieldtankNavGraph>,
+    layer_grids: LayerGridQuery<'w, 's>,
+}
+
+impl NavGridQuery<'_, '_> {
+    pub fn graph(&self, layer: Entity) -> Option<&ShieldtankNavGraph> {
+        self.graph_query.get(layer).ok()
+    }
+
+    pub fn find_path(&self, layer: Entity, start: I64Vec2, goal: I64Vec2) -> Option<NavPath> {
+        let layer_grid = self.layer_grids.get(layer)?;
+        let (cells, cost) = self.graph(layer)?.find_path(start, goal)?;
+
+        let points = cells
+            .iter()
+            .map(|grid| layer_grid.grid_to_world(*grid))
+            .collect();
+
+        Some(NavPath {
+            cells,
+            points,
+            cost,
+        })
+    }
+
+    /// Like [NavGridQuery::find_path], between the cells under two world positions.
+    pub fn find_path_world(&self, layer: Entity, start: Vec2, goal: Vec2) -> Option<NavPath> {
+        let layer_grid = self.layer_grids.get(layer)?;
+        self.find_path(
+            layer,
+            layer_grid.world_to_grid(start),
+            layer_grid.world_to_grid(goal),
+        )
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn nav_graph_system(
+    query: Query<
+        (Entity, &ShieldtankGridValues, &ShieldtankNavSettings),
+        Or<(
+            Changed<ShieldtankGridValues>,
+            Changed<ShieldtankNavSettings>,
+        )>,
+    >,
+    mut commands: Commands,
+) {
+    query.iter().for_each(|(entity, grid_values, settings)| {
+        let graph = ShieldtankNavGraph::new(grid_values, settings);
+        commands.entity(entity).insert(graph);
+    });
+}
+
+fn nav_settings_removed_system(
+    mut removed: RemovedComponents<ShieldtankNavSettings>,
+    mut commands: Commands,
+) {
+    removed.read().for_each(|entity| {
+        if let Ok(mut entity_commands) = commands.get_entity(entity) {
+            entity_commands.remove::<ShieldtankNavGraph>();
+        }
+    });
+}
+
+pub struct NavigationPlugin;
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.register_type::<NavConnectivity>();
+        app.register_type::<NavCornerCutting>();
+        app.register_type::<NavCostRule>();
+        app.register_type::<ShieldtankNavSettings>();
+        app.register_type::<ShieldtankNavGraph>();
+        app.add_systems(
+            ShieldtankComponentSystemSet,
+            (
+                nav_graph_system.after(grid_values_system),
+                nav_settings_removed_system,
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::SQRT_2;
+
+    use super::*;
+
+    const WALL: i64 = 1;
+    const WATER: i64 = 2;
+
+    /// `#` is a wall, `~` is water, and anything else is empty.
+    fn grid_values(rows: &[&str]) -> ShieldtankGridValues {
+        let rows: Vec<Vec<i64>> = rows
+            .iter()
+            .map(|row| {
+                row.chars()
+                    .map(|cell| match cell {
+                        '#' => WALL,
+                        '~' => WATER,
+                        _ => 0,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        ShieldtankGridValues::from_rows(&rows)
+    }
+
+    fn settings() -> ShieldtankNavSettings {
+        ShieldtankNavSettings::default()
+            .with_blocked(WALL)
+            .with_cost(WATER, 5.0)
+    }
+
+    fn path(
+        rows: &[&str],
+        settings: &ShieldtankNavSettings,
+        start: (i64, i64),
+        goal: (i64, i64),
+    ) -> Option<(Vec<I64Vec2>, f32)> {
+        ShieldtankNavGraph::new(&grid_values(rows), settings)
+            .find_path(I64Vec2::new(start.0, start.1), I64Vec2::new(goal.0, goal.1))
+    }
+
+    fn cells(cells: &[(i64, i64)]) -> Vec<I64Vec2> {
+        cells.iter().map(|(x, y)| I64Vec2::new(*x, *y)).collect()
+    }
+
+    #[test]
+    fn four_connectivity_goes_around_walls() {
+        let rows = [".....", ".###.", "....."];
+
+        let (path, cost) = path(&rows, &settings(), (0, 1), (4, 1)).unwrap();
+
+        assert_eq!(cost, 6.0);
+        assert_eq!(path.len(), 7);
+        assert_eq!(path.first(), Some(&I64Vec2::new(0, 1)));
+        assert_eq!(path.last(), Some(&I64Vec2::new(4, 1)));
+        path.windows(2).for_each(|step| {
+            assert_eq!((step[1] - step[0]).abs().element_sum(), 1);
+            assert!(rows[step[1].y as usize].as_bytes()[step[1].x as usize] != b'#');
+        });
+    }
+
+    #[test]
+    fn eight_connectivity_takes_diagonals() {
+        let rows = ["....", "....", "....", "...."];
+        let settings = settings().with_connectivity(NavConnectivity::Eight);
+
+        let (path, cost) = path(&rows, &settings, (0, 0), (3, 3)).unwrap();
+
+        assert_eq!(path, cells(&[(0, 0), (1, 1), (2, 2), (3, 3)]));
+        assert!((cost - 3.0 * SQRT_2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn corner_cutting_past_one_wall() {
+        let rows = [".#", ".."];
+        let settings = settings().with_connectivity(NavConnectivity::Eight);
+
+        let never = settings
+            .clone()
+            .with_corner_cutting(NavCornerCutting::Never);
+        let (path_never, cost) = path(&rows, &never, (0, 0), (1, 1)).unwrap();
+        assert_eq!(path_never, cells(&[(0, 0), (0, 1), (1, 1)]));
+        assert_eq!(cost, 2.0);
+
+        let either = settings.with_corner_cutting(NavCornerCutting::IfEitherWalkable);
+        let (path_either, cost) = path(&rows, &either, (0, 0), (1, 1)).unwrap();
+        assert_eq!(path_either, cells(&[(0, 0), (1, 1)]));
+        assert!((cost - SQRT_2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn corner_cutting_between_two_walls() {
+        let rows = [".#", "#."];
+        let settings = settings().with_connectivity(NavConnectivity::Eight);
+
+        [NavCornerCutting::Never, NavCornerCutting::IfEitherWalkable]
+            .into_iter()
+            .for_each(|corner_cutting| {
+                let settings = settings.clone().with_corner_cutting(corner_cutting);
+                assert_eq!(path(&rows, &settings, (0, 0), (1, 1)), None);
+            });
+
+        let always = settings.with_corner_cutting(NavCornerCutting::Always);
+        let (path_always, _) = path(&rows, &always, (0, 0), (1, 1)).unwrap();
+        assert_eq!(path_always, cells(&[(0, 0), (1, 1)]));
+    }
+
+    #[test]
+    fn weighted_cells_are_avoided_when_cheaper() {
+        let rows = [".~.", "..."];
+
+        let (around, cost) = path(&rows, &settings(), (0, 0), (2, 0)).unwrap();
+        assert_eq!(around, cells(&[(0, 0), (0, 1), (1, 1), (2, 1), (2, 0)]));
+        assert_eq!(cost, 4.0);
+
+        let shallow = ShieldtankNavSettings::default().with_cost(WATER, 1.5);
+        let (through, cost) = path(&rows, &shallow, (0, 0), (2, 0)).unwrap();
+        assert_eq!(through, cells(&[(0, 0), (1, 0), (2, 0)]));
+        assert_eq!(cost, 2.5);
+    }
+
+    #[test]
+    fn blocked_start_or_goal_has_no_path() {
+        let rows = ["#..", "..#"];
+
+        assert_eq!(path(&rows, &settings(), (0, 0), (1, 0)), None);
+        assert_eq!(path(&rows, &settings(), (1, 0), (2, 1)), None);
+        assert_eq!(path(&rows, &settings(), (1, 0), (5, 5)), None);
+    }
+
+    #[test]
+    fn invalid_costs_are_raised_or_blocked() {
+        let rows = ["~#.", "..."];
+        let settings = ShieldtankNavSettings::default()
+            .with_cost(WATER, 0.0)
+            .with_cost(WALL, f32::NAN)
+            .with_empty_cost(Some(-1.0));
+
+        let graph = ShieldtankNavGraph::new(&grid_values(&rows), &settings);
+
+        assert_eq!(graph.cost(I64Vec2::new(0, 0)), Some(MIN_COST));
+        assert_eq!(graph.cost(I64Vec2::new(1, 0)), None);
+        assert_eq!(graph.cost(I64Vec2::new(2, 0)), Some(MIN_COST));
+    }
+}
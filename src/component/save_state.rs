@@ -0,0 +1,602 @@
+//! Records how LDtk entities and IntGrid layers differ from their assets, keyed by [Iid], so the
+//! differences can be saved and reapplied when the level spawns again.
+//!
+//! [ShieldtankSaveState] is kept up to date as entities move, fields change and grid values are
+//! edited.
+//! Entities despawned with [SaveStateEntityCommandsExt::despawn_persistent] stay despawned.
+//! Replacing the resource, for instance with [ShieldtankSaveState::load], only affects entities
+//! and layers spawned afterwards.
+
+use std::path::{Path, PathBuf};
+
+use bevy_app::Plugin;
+use bevy_asset::prelude::AssetChanged;
+use bevy_asset::{AsAssetId, Assets, Handle};
+use bevy_color::Color;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::query::{Added, Changed, Or};
+use bevy_ecs::resource::Resource;
+use bevy_ecs::schedule::IntoScheduleConfigs;
+use bevy_ecs::system::{Commands, EntityCommands, Query, Res, ResMut};
+use bevy_ecs::world::EntityWorldMut;
+use bevy_ldtk_asset::entity::EntityInstance;
+use bevy_ldtk_asset::enum_definition::EnumDefinition;
+use bevy_ldtk_asset::field_instance::{EntityRef, FieldInstance, FieldInstanceType};
+use bevy_ldtk_asset::iid::Iid;
+use bevy_ldtk_asset::layer::LayerInstance;
+use bevy_ldtk_asset::prelude::LdtkAsset;
+use bevy_ldtk_asset::tileset_rectangle::TilesetRectangle;
+use bevy_math::{I64Vec2, Vec2};
+use bevy_platform::collections::HashMap;
+use bevy_reflect::serde::{TypedReflectDeserializer, TypedReflectSerializer};
+use bevy_reflect::{FromReflect, PartialReflect, Reflect, Struct, TypeRegistry};
+use bevy_transform::components::Transform;
+use serde::de::DeserializeSeed;
+
+use crate::result::ShieldtankResult;
+use crate::shieldtank_error;
+
+use super::entity::{ShieldtankEntity, entity_insert_components_system};
+use super::field_instances::ShieldtankFieldInstances;
+use super::grid_edits::ShieldtankGridEdits;
+use super::grid_values::grid_values_system;
+use super::iid::ShieldtankIid;
+use super::layer::ShieldtankLayer;
+use super::ldtk_fields::{enum_value_str, new_enum_value};
+use super::shieldtank_component::ShieldtankComponentSystemSet;
+
+/// The corner and size of a tile in a field, without its tileset.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct ShieldtankSavedTile {
+    pub corner: I64Vec2,
+    pub size: I64Vec2,
+}
+
+impl ShieldtankSavedTile {
+    fn new(tile: &TilesetRectangle) -> Self {
+        Self {
+            corner: tile.corner,
+            size: tile.size,
+        }
+    }
+}
+
+/// The value of a saved field. Unlike [FieldInstanceType] it holds no asset handles, so it can be
+/// serialized. Enum definitions and tilesets are taken from the asset's field when reapplied.
+#[derive(Clone, Debug, Reflect)]
+pub enum ShieldtankSavedField {
+    ArrayInt(Vec<i64>),
+    ArrayEnum(Vec<String>),
+    ArrayString(Vec<String>),
+    ArrayPoint(Vec<I64Vec2>),
+    ArrayTile(Vec<ShieldtankSavedTile>),
+    Bool(bool),
+    Color(Color),
+    EntityRef(EntityRef),
+    Enum(String),
+    FilePath(PathBuf),
+    Float(f64),
+    Int(i64),
+    Point(I64Vec2),
+    String(String),
+    Tile(ShieldtankSavedTile),
+}
+
+impl ShieldtankSavedField {
+    pub fn new(field: &FieldInstance) -> Option<Self> {
+        let saved = match &field.field_instance_type {
+            FieldInstanceType::ArrayInt(values) => Self::ArrayInt(values.clone()),
+            FieldInstanceType::ArrayEnum(values) => Self::ArrayEnum(
+                values
+                    .iter()
+                    .map(|value| enum_value_str(value).map(str::to_string))
+                    .collect::<Option<_>>()?,
+            ),
+            FieldInstanceType::ArrayString(values) => Self::ArrayString(values.clone()),
+            FieldInstanceType::ArrayPoint(values) => Self::ArrayPoint(values.clone()),
+            FieldInstanceType::ArrayTile(tiles) => {
+                Self::ArrayTile(tiles.iter().map(ShieldtankSavedTile::new).collect())
+            }
+            FieldInstanceType::Bool(value) => Self::Bool(*value),
+            FieldInstanceType::Color(value) => Self::Color(*value),
+            FieldInstanceType::EntityRef(value) => Self::EntityRef(*value),
+            FieldInstanceType::Enum(value) => Self::Enum(enum_value_str(value)?.to_string()),
+            FieldInstanceType::FilePath(value) => Self::FilePath(value.clone()),
+            FieldInstanceType::Float(value) => Self::Float(*value),
+            FieldInstanceType::Int(value) => Self::Int(*value),
+            FieldInstanceType::Point(value) => Self::Point(*value),
+            FieldInstanceType::String(value) => Self::String(value.clone()),
+            FieldInstanceType::Tile(tile) => Self::Tile(ShieldtankSavedTile::new(tile)),
+        };
+
+        Some(saved)
+    }
+
+    /// Rebuilds the field, taking its definition and handles from `original`, the field of the
+    /// same name on the asset.
+    pub fn to_field_instance(&self, original: Option<&FieldInstance>) -> Option<FieldInstance> {
+        let original_type = original.map(|original| &original.field_instance_type);
+
+        let enum_definition = match original_type {
+            Some(FieldInstanceType::Enum(value)) => value.field("enum_definition"),
+            Some(FieldInstanceType::ArrayEnum(values)) => values
+                .first()
+                .and_then(|value| value.field("enum_definition")),
+            _ => None,
+        }
+        .and_then(|handle| handle.try_downcast_ref::<Handle<EnumDefinition>>())
+        .cloned()
+        .unwrap_or_default();
+
+        let tileset_definition = match original_type {
+            Some(FieldInstanceType::Tile(tile)) => Some(tile),
+            Some(FieldInstanceType::ArrayTile(tiles)) => tiles.first(),
+            _ => None,
+        }
+        .map(|tile| tile.tileset_definition.clone())
+        .unwrap_or_default();
+
+        let tile = |tile: &ShieldtankSavedTile| TilesetRectangle {
+            corner: tile.corner,
+            size: tile.size,
+            tileset_definition: tileset_definition.clone(),
+        };
+
+        let field_instance_type = match self {
+            Self::ArrayInt(values) => FieldInstanceType::ArrayInt(values.clone()),
+            Self::ArrayEnum(values) => FieldInstanceType::ArrayEnum(
+                values
+                    .iter()
+                    .map(|value| new_enum_value(value, enum_definition.clone()))
+                    .collect::<Option<_>>()?,
+            ),
+            Self::ArrayString(values) => FieldInstanceType::ArrayString(values.clone()),
+            Self::ArrayPoint(values) => FieldInstanceType::ArrayPoint(values.clone()),
+            Self::ArrayTile(tiles) => {
+                FieldInstanceType::ArrayTile(tiles.iter().map(tile).collect())
+            }
+            Self::Bool(value) => FieldInstanceType::Bool(*value),
+            Self::Color(value) => FieldInstanceType::Color(*value),
+            Self::EntityRef(value) => FieldInstanceType::EntityRef(*value),
+            Self::Enum(value) => FieldInstanceType::Enum(new_enum_value(value, enum_definition)?),
+            Self::FilePath(value) => FieldInstanceType::FilePath(value.clone()),
+            Self::Float(value) => FieldInstanceType::Float(*value),
+            Self::Int(value) => FieldInstanceType::Int(*value),
+            Self::Point(value) => FieldInstanceType::Point(*value),
+            Self::String(value) => FieldInstanceType::String(value.clone()),
+            Self::Tile(value) => FieldInstanceType::Tile(tile(value)),
+        };
+
+        Some(FieldInstance {
+            tileset_rectangle: original.and_then(|original| original.tileset_rectangle.clone()),
+            field_instance_type,
+            def_uid: original
+                .map(|original| original.def_uid)
+                .unwrap_or_default(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, Reflect)]
+pub struct ShieldtankEntityDelta {
+    pub transform: Option<Transform>,
+    pub despawned: bool,
+    /// Only the field instances which differ from the asset.
+    pub field_instances: HashMap<String, ShieldtankSavedField>,
+}
+
+impl ShieldtankEntityDelta {
+    fn is_empty(&self) -> bool {
+        self.transform.is_none() && !self.despawned && self.field_instances.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, Default, Resource, Reflect)]
+pub struct ShieldtankSaveState {
+    pub entities: HashMap<Iid, ShieldtankEntityDelta>,
+    /// The [ShieldtankGridEdits] of each layer, with `0` for a cleared cell.
+    pub grid_values: HashMap<Iid, HashMap<I64Vec2, i64>>,
+}
+
+impl ShieldtankSaveState {
+    pub fn to_ron(&self, registry: &TypeRegistry) -> ShieldtankResult<String> {
+        let serializer = TypedReflectSerializer::new(self, registry);
+        let ron = ron::ser::to_string_pretty(&serializer, ron::ser::PrettyConfig::default())?;
+        Ok(ron)
+    }
+
+    pub fn from_ron(ron: &str, registry: &TypeRegistry) -> ShieldtankResult<Self> {
+        let mut deserializer = ron::Deserializer::from_str(ron)?;
+        let reflected =
+            TypedReflectDeserializer::of::<Self>(registry).deserialize(&mut deserializer)?;
+
+        Self::from_reflect(reflected.as_partial_reflect()).ok_or(shieldtank_error!(
+            "Could not build save state from reflected data!"
+        ))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>, registry: &TypeRegistry) -> ShieldtankResult<()> {
+        std::fs::write(path, self.to_ron(registry)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>, registry: &TypeRegistry) -> ShieldtankResult<Self> {
+        Self::from_ron(&std::fs::read_to_string(path)?, registry)
+    }
+}
+
+pub trait SaveStateEntityCommandsExt {
+    /// Despawns an LDtk entity, and records it so it isn't spawned again.
+    fn despawn_persistent(&mut self);
+}
+
+impl SaveStateEntityCommandsExt for EntityCommands<'_> {
+    fn despawn_persistent(&mut self) {
+        self.queue(|mut entity: EntityWorldMut| {
+            if let Some(iid) = entity.get::<ShieldtankIid>().copied() {
+                entity.world_scope(|world| {
+                    world
+                        .get_resource_or_init::<ShieldtankSaveState>()
+                        .entities
+                        .entry(*iid)
+                        .or_default()
+                        .despawned = true;
+                });
+            }
+
+            entity.despawn();
+        });
+    }
+}
+
+fn original_transform(asset: &EntityInstance) -> Transform {
+    let location = Vec2::new(1.0, -1.0) * asset.location.as_vec2();
+    Transform::from_translation(location.extend(0.0))
+}
+
+#[allow(clippy::type_complexity)]
+fn entity_apply_system(
+    mut query: Query<
+        (
+            Entity,
+            &ShieldtankEntity,
+            &mut Transform,
+            &mut ShieldtankFieldInstances,
+        ),
+        Or<(Changed<ShieldtankEntity>, AssetChanged<ShieldtankEntity>)>,
+    >,
+    assets: Res<Assets<EntityInstance>>,
+    save_state: Res<ShieldtankSaveState>,
+    mut commands: Commands,
+) {
+    query
+        .iter_mut()
+        .filter_map(|(entity, component, transform, field_instances)| {
+            let asset = assets.get(component.as_asset_id())?;
+            let delta = save_state.entities.get(&asset.get_iid())?;
+            Some((entity, transform, field_instances, asset, delta))
+        })
+        .for_each(
+            |(entity, mut transform, mut field_instances, asset, delta)| {
+                if delta.despawned {
+                    commands.entity(entity).despawn();
+                    return;
+                }
+
+                if let Some(delta_transform) = delta.transform {
+                    *transform = delta_transform;
+                }
+
+                if !delta.field_instances.is_empty() {
+                    field_instances.field_instances.extend(
+                        delta
+                            .field_instances
+                            .iter()
+                            .filter_map(|(identifier, saved)| {
+                                let original = asset.field_instances.get(identifier);
+                                Some((identifier.clone(), saved.to_field_instance(original)?))
+                            }),
+                    );
+                }
+            },
+        );
+}
+
+#[allow(clippy::type_complexity)]
+fn entity_capture_system(
+    query: Query<
+        (&ShieldtankEntity, &Transform, &ShieldtankFieldInstances),
+        Or<(Changed<Transform>, Changed<ShieldtankFieldInstances>)>,
+    >,
+    assets: Res<Assets<EntityInstance>>,
+    mut save_state: ResMut<ShieldtankSaveState>,
+) {
+    query
+        .iter()
+        .filter_map(|(component, transform, field_instances)| {
+            Some((
+                assets.get(component.as_asset_id())?,
+                transform,
+                field_instances,
+            ))
+        })
+        .for_each(|(asset, transform, field_instances)| {
+            let iid = asset.get_iid();
+
+            let transform = (*transform != original_transform(asset)).then_some(*transform);
+
+            let field_instances: HashMap<String, ShieldtankSavedField> = field_instances
+                .iter()
+                .filter(|(identifier, field)| {
+                    asset
+                        .field_instances
+                        .get(*identifier)
+                        .and_then(|original| original.reflect_partial_eq(*field))
+                        != Some(true)
+                })
+                .filter_map(|(identifier, field)| {
+                    Some((identifier.clone(), ShieldtankSavedField::new(field)?))
+                })
+                .collect();
+
+            let despawned = save_state
+                .entities
+                .get(&iid)
+                .is_some_and(|delta| delta.despawned);
+
+            let delta = ShieldtankEntityDelta {
+                transform,
+                despawned,
+                field_instances,
+            };
+
+            match delta.is_empty() {
+                true => save_state.entities.remove(&iid),
+                false => save_state.entities.insert(iid, delta),
+            };
+        });
+}
+
+#[allow(clippy::type_complexity)]
+fn grid_edits_restore_system(
+    mut query: Query<
+        (&ShieldtankLayer, &mut ShieldtankGridEdits),
+        Or<(
+            Changed<ShieldtankLayer>,
+            AssetChanged<ShieldtankLayer>,
+            Added<ShieldtankGridEdits>,
+        )>,
+    >,
+    assets: Res<Assets<LayerInstance>>,
+    save_state: Res<ShieldtankSaveState>,
+) {
+    query
+        .iter_mut()
+        .filter_map(|(component, edits)| {
+            let asset = assets.get(component.as_asset_id())?;
+            let saved = save_state.grid_values.get(&asset.get_iid())?;
+            Some((edits, saved))
+        })
+        .for_each(|(mut edits, saved)| {
+            edits.replace(saved.clone());
+        });
+}
+
+fn grid_edits_capture_system(
+    query: Query<(&ShieldtankLayer, &ShieldtankGridEdits), Changed<ShieldtankGridEdits>>,
+    assets: Res<Assets<LayerInstance>>,
+    mut save_state: ResMut<ShieldtankSaveState>,
+) {
+    query
+        .iter()
+        .filter_map(|(component, edits)| {
+            Some((assets.get(component.as_asset_id())?.get_iid(), edits))
+        })
+        .for_each(|(iid, edits)| {
+            match edits.is_empty() {
+                true => save_state.grid_values.remove(&iid),
+                false => save_state.grid_values.insert(iid, edits.edits().clone()),
+            };
+        });
+}
+
+pub struct SaveStatePlugin;
+impl Plugin for SaveStatePlugin {
+    fn build(&self, app: &mut bevy_app::App) {
+        app.register_type::<ShieldtankSavedTile>();
+        app.register_type::<ShieldtankSavedField>();
+        app.register_type::<ShieldtankEntityDelta>();
+        app.register_type::<ShieldtankSaveState>();
+        app.init_resource::<ShieldtankSaveState>();
+        app.add_systems(
+            ShieldtankComponentSystemSet,
+            (
+                entity_apply_system.after(entity_insert_components_system),
+                entity_capture_system,
+            )
+                .chain(),
+        );
+        app.add_systems(
+            ShieldtankComponentSystemSet,
+            (
+                grid_edits_restore_system.before(grid_values_system),
+                grid_edits_capture_system,
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use bevy_app::Update;
+    use bevy_ecs::reflect::AppTypeRegistry;
+    use bevy_ldtk_asset::iid::Iid;
+    use bevy_math::Vec3;
+
+    use crate::component::shieldtank_component::ShieldtankComponent;
+    use crate::test::{entity_asset, entity_ref, enum_value, field_instance, test_app};
+
+    use super::*;
+
+    fn app() -> bevy_app::App {
+        let mut app = test_app();
+        app.add_plugins(SaveStatePlugin);
+        app.add_systems(Update, (entity_apply_system, entity_capture_system).chain());
+        app
+    }
+
+    fn enum_definition() -> Handle<EnumDefinition> {
+        Handle::Uuid(Iid::from_u128(7), PhantomData)
+    }
+
+    fn mood(value: &str) -> FieldInstance {
+        let value = new_enum_value(value, enum_definition()).unwrap();
+        field_instance(FieldInstanceType::Enum(value))
+    }
+
+    fn spawn(app: &mut bevy_app::App, handle: &Handle<EntityInstance>) -> Entity {
+        let asset = app
+            .world()
+            .resource::<Assets<EntityInstance>>()
+            .get(handle)
+            .unwrap();
+        let bundle = (
+            ShieldtankEntity::new(handle.clone()),
+            ShieldtankIid::new(asset.iid),
+            original_transform(asset),
+            ShieldtankFieldInstances::new(asset.field_instances.clone()),
+        );
+
+        app.world_mut().spawn(bundle).id()
+    }
+
+    #[test]
+    fn saved_fields_rebuild_with_the_handles_of_the_asset() {
+        let original = mood("Calm");
+        let saved = ShieldtankSavedField::new(&mood("Angry")).unwrap();
+
+        let rebuilt = saved.to_field_instance(Some(&original)).unwrap();
+        let value = rebuilt.get_enum().unwrap();
+
+        assert_eq!(enum_value_str(value), Some("Angry"));
+        assert_eq!(
+            value
+                .field("enum_definition")
+                .and_then(|handle| handle.try_downcast_ref::<Handle<EnumDefinition>>()),
+            Some(&enum_definition())
+        );
+
+        let saved = ShieldtankSavedField::new(&field_instance(FieldInstanceType::EntityRef(
+            entity_ref(1, 2),
+        )))
+        .unwrap();
+        let rebuilt = saved.to_field_instance(None).unwrap();
+        assert_eq!(
+            rebuilt
+                .get_array_entity_ref()
+                .map(|entity_ref| entity_ref.entity_iid),
+            Some(Iid::from_u128(1))
+        );
+    }
+
+    #[test]
+    fn deltas_survive_a_save_and_load() {
+        let mut app = app();
+
+        let field_instances = HashMap::from_iter([
+            (
+                "health".to_string(),
+                field_instance(FieldInstanceType::Int(3)),
+            ),
+            ("mood".to_string(), mood("Calm")),
+            (
+                "tags".to_string(),
+                field_instance(FieldInstanceType::ArrayEnum(vec![enum_value("A")])),
+            ),
+        ]);
+        let (player, enemy) = {
+            let mut assets = app.world_mut().resource_mut::<Assets<EntityInstance>>();
+            (
+                assets.add(entity_asset(
+                    "Player",
+                    1,
+                    I64Vec2::new(16, 32),
+                    field_instances,
+                )),
+                assets.add(entity_asset("Enemy", 2, I64Vec2::ZERO, HashMap::default())),
+            )
+        };
+
+        let player_entity = spawn(&mut app, &player);
+        let enemy_entity = spawn(&mut app, &enemy);
+        app.update();
+        assert!(
+            app.world()
+                .resource::<ShieldtankSaveState>()
+                .entities
+                .is_empty()
+        );
+
+        let moved = Transform::from_translation(Vec3::new(100.0, 0.0, 0.0));
+        let mut entity = app.world_mut().entity_mut(player_entity);
+        *entity.get_mut::<Transform>().unwrap() = moved;
+        let mut field_instances = entity.get_mut::<ShieldtankFieldInstances>().unwrap();
+        field_instances.field_instances.insert(
+            "health".to_string(),
+            field_instance(FieldInstanceType::Int(1)),
+        );
+        field_instances
+            .field_instances
+            .insert("mood".to_string(), mood("Angry"));
+        app.world_mut()
+            .commands()
+            .entity(enemy_entity)
+            .despawn_persistent();
+        app.update();
+
+        let mut save_state = app.world().resource::<ShieldtankSaveState>().clone();
+        save_state.grid_values.insert(
+            Iid::from_u128(3),
+            HashMap::from_iter([(I64Vec2::new(1, 2), 5)]),
+        );
+
+        let registry = app.world().resource::<AppTypeRegistry>().clone();
+        let ron = save_state.to_ron(&registry.read()).unwrap();
+        let loaded = ShieldtankSaveState::from_ron(&ron, &registry.read()).unwrap();
+
+        assert_eq!(loaded.entities.len(), 2);
+        assert_eq!(loaded.entities[&Iid::from_u128(1)].field_instances.len(), 2);
+        assert!(loaded.entities[&Iid::from_u128(2)].despawned);
+        assert_eq!(loaded.grid_values, save_state.grid_values);
+
+        app.world_mut().entity_mut(player_entity).despawn();
+        app.world_mut().insert_resource(loaded);
+
+        let player_entity = spawn(&mut app, &player);
+        let enemy_entity = spawn(&mut app, &enemy);
+        app.update();
+
+        assert!(app.world().get_entity(enemy_entity).is_err());
+
+        let entity = app.world().entity(player_entity);
+        assert_eq!(entity.get::<Transform>(), Some(&moved));
+
+        let field_instances = entity.get::<ShieldtankFieldInstances>().unwrap();
+        assert_eq!(field_instances.get_field::<i64>("health").unwrap(), 1);
+        assert_eq!(
+            field_instances.get_field::<String>("mood").unwrap(),
+            "Angry"
+        );
+        assert_eq!(
+            field_instances.get_field::<Vec<String>>("tags").unwrap(),
+            vec!["A"]
+        );
+
+        assert_eq!(
+            app.world().resource::<ShieldtankSaveState>().entities.len(),
+            2
+        );
+    }
+}
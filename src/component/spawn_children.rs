@@ -1,21 +1,26 @@
-use std::borrow::Cow;
-
 use bevy_app::{Plugin, PostUpdate};
 use bevy_asset::prelude::AssetChanged;
-use bevy_asset::{AsAssetId, Assets, Handle};
+use bevy_asset::{AsAssetId, AssetId, Assets, Handle};
+use bevy_ecs::change_detection::{DetectChanges, Ref};
 use bevy_ecs::entity::Entity;
 use bevy_ecs::hierarchy::Children;
-use bevy_ecs::query::{Changed, Or};
+use bevy_ecs::lifecycle::RemovedComponents;
+use bevy_ecs::message::{Message, MessageWriter};
+use bevy_ecs::query::{Changed, Or, With};
 use bevy_ecs::schedule::IntoScheduleConfigs;
 use bevy_ecs::system::Commands;
 use bevy_ecs::system::{Query, Res};
+use bevy_ldtk_asset::iid::Iid;
 use bevy_ldtk_asset::prelude::LdtkAsset;
 use bevy_log::debug;
+use bevy_platform::collections::{HashMap, HashSet};
 
-use crate::component::filter::ShieldtankComponentFilter;
+use crate::component::filter::{FilterSubject, ShieldtankComponentFilter};
 
+use super::iid::ShieldtankIid;
 use super::layer::ShieldtankLayer;
 use super::level::ShieldtankLevel;
+use super::level_streaming::ShieldtankStreamedLevels;
 use super::project::LdtkProject;
 use super::shieldtank_component::ShieldtankComponent;
 use super::world::ShieldtankWorld;
@@ -23,6 +28,28 @@ use super::world::ShieldtankWorld;
 #[allow(non_upper_case_globals)]
 pub(crate) const ChildSystemSet: PostUpdate = PostUpdate;
 
+/// Written whenever a reconcile pass changes the spawned children of a project, world, level or
+/// layer entity.
+#[derive(Clone, Debug, Message)]
+pub struct ShieldtankReloadDiff {
+    /// The parent entity whose children were reconciled.
+    pub parent: Entity,
+    /// Children which were spawned by this pass.
+    pub added: Vec<Iid>,
+    /// Children which were despawned, either because they left the asset or were filtered out.
+    pub removed: Vec<Iid>,
+    /// Children which were kept, but whose asset changed.
+    pub updated: Vec<Iid>,
+}
+
+impl ShieldtankReloadDiff {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+}
+
+pub(crate) type ChildAsset<T> = <<T as SpawnChildren>::Child as AsAssetId>::Asset;
+
 pub(crate) trait SpawnChildren: ShieldtankComponent + Sized + std::fmt::Debug
 where
     <Self as AsAssetId>::Asset: LdtkAsset,
@@ -35,71 +62,171 @@ where
         asset: &<Self as AsAssetId>::Asset,
     ) -> impl Iterator<Item = Handle<<Self::Child as AsAssetId>::Asset>>;
 
+    /// Describes a child asset to [ShieldtankComponentFilter].
+    fn filter_subject(child_asset: &ChildAsset<Self>) -> FilterSubject<'_> {
+        FilterSubject {
+            identifier: Some(child_asset.get_identifier()),
+            iid: Some(child_asset.get_iid()),
+            ..Default::default()
+        }
+    }
+
+    /// The children of `asset` which pass `filter`, and are streamed in when `streamed` is given,
+    /// and so should be spawned.
+    fn wanted_children(
+        &self,
+        asset: &<Self as AsAssetId>::Asset,
+        child_assets: &Assets<ChildAsset<Self>>,
+        filter: Option<&ShieldtankComponentFilter>,
+        streamed: Option<&ShieldtankStreamedLevels>,
+    ) -> Vec<Handle<<Self::Child as AsAssetId>::Asset>> {
+        if filter.is_none() && streamed.is_none() {
+            return self.get_children(asset).collect();
+        }
+
+        self.get_children(asset)
+            .filter(|child_handle| {
+                let child_asset = child_assets.get(child_handle.id());
+
+                if let Some(streamed) = streamed
+                    && !child_asset
+                        .is_some_and(|child_asset| streamed.contains(child_asset.get_iid()))
+                {
+                    return false;
+                }
+
+                let Some(filter) = filter else {
+                    return true;
+                };
+
+                let label = child_handle.path().and_then(|path| path.label());
+
+                let subject = match child_asset {
+                    Some(child_asset) => FilterSubject {
+                        label,
+                        ..Self::filter_subject(child_asset)
+                    },
+                    None => FilterSubject {
+                        label,
+                        ..Default::default()
+                    },
+                };
+
+                filter.matches(&subject)
+            })
+            .collect()
+    }
+
     #[allow(clippy::type_complexity)]
+    #[allow(clippy::too_many_arguments)]
     fn child_spawn_system(
         assets: Res<Assets<<Self as AsAssetId>::Asset>>,
-        query: Query<
+        child_assets: Res<Assets<ChildAsset<Self>>>,
+        changed_query: Query<
+            Entity,
             (
-                Entity,
-                &Self,
-                Option<&Children>,
-                Option<&ShieldtankComponentFilter>,
+                With<Self>,
+                Or<(
+                    Changed<ShieldtankComponentFilter>,
+                    Changed<ShieldtankStreamedLevels>,
+                    Changed<Self>,
+                    AssetChanged<Self>,
+                )>,
             ),
-            Or<(
-                Changed<ShieldtankComponentFilter>,
-                Changed<Self>,
-                AssetChanged<Self>,
-            )>,
         >,
-        children_query: Query<&Self::Child>,
+        query: Query<(
+            &Self,
+            Option<&Children>,
+            Option<&ShieldtankComponentFilter>,
+            Option<&ShieldtankStreamedLevels>,
+        )>,
+        children_query: Query<(&Self::Child, Option<&ShieldtankIid>)>,
+        updated_children_query: Query<Ref<Self::Child>, AssetChanged<Self::Child>>,
+        mut removed_filters: RemovedComponents<ShieldtankComponentFilter>,
+        mut removed_streamed: RemovedComponents<ShieldtankStreamedLevels>,
+        mut reload_diffs: MessageWriter<ShieldtankReloadDiff>,
         mut commands: Commands,
     ) {
-        query
+        let parents: HashSet<Entity> = changed_query
             .iter()
-            .for_each(|(entity, component, children, filter)| {
-                let spawned_children = match children {
-                    Some(children) => children
-                        .into_iter()
-                        .copied()
-                        .filter_map(|entity| children_query.get(entity).ok())
-                        .map(|child| child.as_asset_id())
-                        .collect(),
-                    None => vec![],
-                };
+            .chain(removed_filters.read())
+            .chain(removed_streamed.read())
+            .collect();
 
-                let Some(asset) = assets.get(component.as_asset_id()) else {
-                    debug!("asset not ready?");
-                    return;
-                };
+        parents.into_iter().for_each(|entity| {
+            let Ok((component, children, filter, streamed)) = query.get(entity) else {
+                return;
+            };
+
+            let Some(asset) = assets.get(component.as_asset_id()) else {
+                debug!("asset not ready?");
+                return;
+            };
+
+            let wanted_children = component.wanted_children(asset, &child_assets, filter, streamed);
+
+            let wanted_ids: HashSet<AssetId<ChildAsset<Self>>> =
+                wanted_children.iter().map(Handle::id).collect();
+
+            let mut diff = ShieldtankReloadDiff {
+                parent: entity,
+                added: vec![],
+                removed: vec![],
+                updated: vec![],
+            };
 
-                let filter: Cow<ShieldtankComponentFilter> = filter
-                    .map(Cow::Borrowed)
-                    .unwrap_or_else(|| Cow::Owned(ShieldtankComponentFilter::default()));
-
-                component
-                    .get_children(asset)
-                    .filter(|child_handle| {
-                        child_handle
-                            .path()
-                            .and_then(|path| path.label())
-                            .map(|label| filter.should_load(label))
-                            .unwrap_or(false)
-                    })
-                    .for_each(|child_handle| {
-                        if !spawned_children.contains(&child_handle.id()) {
-                            let child_component = Self::Child::new(child_handle.clone());
-                            let child_id = commands.spawn(child_component).id();
-                            commands.entity(entity).add_child(child_id);
-                            debug!("Spawning new child: {child_handle:?}");
-                        }
-                    });
-            });
+            let mut spawned_children: HashMap<AssetId<ChildAsset<Self>>, Entity> = HashMap::new();
+
+            children
+                .into_iter()
+                .flatten()
+                .copied()
+                .filter_map(|child| Some((child, children_query.get(child).ok()?)))
+                .for_each(|(child, (child_component, iid))| {
+                    let child_id = child_component.as_asset_id();
+
+                    if !wanted_ids.contains(&child_id)
+                        || spawned_children.insert(child_id, child).is_some()
+                    {
+                        debug!("Despawning stale child: {child_id:?}");
+                        diff.removed.extend(iid.map(|iid| **iid));
+                        commands.entity(child).despawn();
+                    } else if updated_children_query
+                        .get(child)
+                        // Children spawned by the last pass see their new asset as a change.
+                        .is_ok_and(|child_component| !child_component.is_added())
+                    {
+                        diff.updated.extend(iid.map(|iid| **iid));
+                    }
+                });
+
+            wanted_children
+                .into_iter()
+                .filter(|child_handle| !spawned_children.contains_key(&child_handle.id()))
+                .for_each(|child_handle| {
+                    diff.added.extend(
+                        child_assets
+                            .get(child_handle.id())
+                            .map(|child_asset| child_asset.get_iid()),
+                    );
+
+                    debug!("Spawning new child: {child_handle:?}");
+                    let child_component = Self::Child::new(child_handle);
+                    let child_id = commands.spawn(child_component).id();
+                    commands.entity(entity).add_child(child_id);
+                });
+
+            if !diff.is_empty() {
+                reload_diffs.write(diff);
+            }
+        });
     }
 }
 
 pub struct SpawnChildrenPlugin;
 impl Plugin for SpawnChildrenPlugin {
     fn build(&self, app: &mut bevy_app::App) {
+        app.add_message::<ShieldtankReloadDiff>();
         app.add_systems(
             ChildSystemSet,
             (
@@ -112,3 +239,179 @@ impl Plugin for SpawnChildrenPlugin {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::Update;
+    use bevy_ecs::message::Messages;
+    use bevy_ldtk_asset::level::Level as LevelAsset;
+    use bevy_ldtk_asset::world::{World as WorldAsset, WorldLayout};
+    use bevy_math::I64Vec2;
+    use itertools::Itertools;
+
+    use crate::test::{level_asset, test_app};
+
+    use super::*;
+
+    struct Reconcile {
+        app: bevy_app::App,
+        world: Entity,
+        world_asset: Handle<WorldAsset>,
+        levels: Vec<Handle<LevelAsset>>,
+    }
+
+    /// A spawned world with two levels, whose Iids are 1 and 2.
+    fn reconcile() -> Reconcile {
+        let mut app = test_app();
+        app.add_message::<ShieldtankReloadDiff>();
+        app.add_systems(
+            Update,
+            (
+                <ShieldtankWorld as SpawnChildren>::child_spawn_system,
+                <ShieldtankLevel as ShieldtankComponent>::add_basic_components_system,
+            )
+                .chain(),
+        );
+
+        let levels: Vec<_> = [1, 2]
+            .into_iter()
+            .map(|iid| {
+                let level = level_asset(iid, I64Vec2::ZERO, I64Vec2::splat(100));
+                app.world_mut()
+                    .resource_mut::<Assets<LevelAsset>>()
+                    .add(level)
+            })
+            .collect();
+
+        let world_asset = WorldAsset {
+            identifier: "World".to_string(),
+            iid: Iid::nil(),
+            world_layout: WorldLayout::Free,
+            levels: levels
+                .iter()
+                .enumerate()
+                .map(|(index, handle)| (Iid::from_u128(index as u128 + 1), handle.clone()))
+                .collect(),
+        };
+        let world_asset = app
+            .world_mut()
+            .resource_mut::<Assets<WorldAsset>>()
+            .add(world_asset);
+
+        let world = app
+            .world_mut()
+            .spawn(ShieldtankWorld::new(world_asset.clone()))
+            .id();
+
+        Reconcile {
+            app,
+            world,
+            world_asset,
+            levels,
+        }
+    }
+
+    fn iids(iids: &[Iid]) -> Vec<u128> {
+        iids.iter().map(Iid::as_u128).sorted().collect()
+    }
+
+    impl Reconcile {
+        /// Runs two frames, since `Update` systems only see an asset change the frame after it's
+        /// made, and returns the diffs written as `(added, removed, updated)` Iids.
+        fn update(&mut self) -> Vec<(Vec<u128>, Vec<u128>, Vec<u128>)> {
+            self.app.update();
+            self.app.update();
+            self.app
+                .world_mut()
+                .resource_mut::<Messages<ShieldtankReloadDiff>>()
+                .drain()
+                .inspect(|diff| assert_eq!(diff.parent, self.world))
+                .map(|diff| (iids(&diff.added), iids(&diff.removed), iids(&diff.updated)))
+                .collect()
+        }
+
+        fn spawned_levels(&self) -> Vec<u128> {
+            let world = self.app.world();
+            world
+                .get::<Children>(self.world)
+                .into_iter()
+                .flatten()
+                .filter_map(|child| world.get::<ShieldtankIid>(*child))
+                .map(|iid| iid.as_u128())
+                .sorted()
+                .collect()
+        }
+    }
+
+    #[test]
+    fn spawning_adds_every_child() {
+        let mut reconcile = reconcile();
+
+        assert_eq!(reconcile.update(), vec![(vec![1, 2], vec![], vec![])]);
+        assert_eq!(reconcile.spawned_levels(), vec![1, 2]);
+        assert!(reconcile.update().is_empty());
+    }
+
+    #[test]
+    fn filter_changes_remove_and_add_children() {
+        let mut reconcile = reconcile();
+        reconcile.update();
+
+        let world = reconcile.world;
+        reconcile
+            .app
+            .world_mut()
+            .entity_mut(world)
+            .insert(ShieldtankComponentFilter::iids([Iid::from_u128(1)]));
+        assert_eq!(reconcile.update(), vec![(vec![], vec![2], vec![])]);
+        assert_eq!(reconcile.spawned_levels(), vec![1]);
+
+        reconcile
+            .app
+            .world_mut()
+            .entity_mut(world)
+            .remove::<ShieldtankComponentFilter>();
+        assert_eq!(reconcile.update(), vec![(vec![2], vec![], vec![])]);
+        assert_eq!(reconcile.spawned_levels(), vec![1, 2]);
+    }
+
+    #[test]
+    fn reloads_report_removed_and_updated_children() {
+        let mut reconcile = reconcile();
+        reconcile.update();
+
+        reconcile
+            .app
+            .world_mut()
+            .resource_mut::<Assets<WorldAsset>>()
+            .get_mut(&reconcile.world_asset)
+            .unwrap()
+            .levels
+            .remove(&Iid::from_u128(2));
+        reconcile
+            .app
+            .world_mut()
+            .resource_mut::<Assets<LevelAsset>>()
+            .get_mut(&reconcile.levels[0])
+            .unwrap()
+            .world_depth = 1;
+
+        assert_eq!(reconcile.update(), vec![(vec![], vec![2], vec![1])]);
+        assert_eq!(reconcile.spawned_levels(), vec![1]);
+    }
+
+    #[test]
+    fn reloads_without_changes_write_no_diff() {
+        let mut reconcile = reconcile();
+        reconcile.update();
+
+        reconcile
+            .app
+            .world_mut()
+            .resource_mut::<Assets<WorldAsset>>()
+            .get_mut(&reconcile.world_asset);
+
+        assert!(reconcile.update().is_empty());
+        assert_eq!(reconcile.spawned_levels(), vec![1, 2]);
+    }
+}
@@ -3,10 +3,12 @@ use bevy_asset::{AsAssetId, Handle};
 use bevy_camera::visibility::Visibility;
 use bevy_ecs::component::Component;
 use bevy_ldtk_asset::level::Level as LevelAsset;
+use bevy_ldtk_asset::prelude::LdtkAsset;
 use bevy_ldtk_asset::world::World as WorldAsset;
 use bevy_reflect::Reflect;
 use bevy_transform::components::{GlobalTransform, Transform};
 
+use super::filter::FilterSubject;
 use super::level::ShieldtankLevel;
 use super::shieldtank_component::{ShieldtankComponent, ShieldtankComponentSystemSet};
 use super::spawn_children::SpawnChildren;
@@ -40,6 +42,16 @@ impl SpawnChildren for ShieldtankWorld {
     fn get_children(&self, asset: &WorldAsset) -> impl Iterator<Item = Handle<LevelAsset>> {
         asset.levels.values().cloned()
     }
+
+    fn filter_subject(child_asset: &LevelAsset) -> FilterSubject<'_> {
+        FilterSubject {
+            identifier: Some(child_asset.get_identifier()),
+            iid: Some(child_asset.get_iid()),
+            world_depth: Some(child_asset.world_depth),
+            field_instances: Some(&child_asset.field_instances),
+            ..Default::default()
+        }
+    }
 }
 
 pub struct ShieldtankWorldPlugin;
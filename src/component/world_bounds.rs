@@ -1,10 +1,20 @@
 use bevy_app::Plugin;
 use bevy_derive::Deref;
 use bevy_ecs::component::Component;
-use bevy_math::{Rect, Vec2};
+use bevy_ecs::entity::{Entity, EntityHashMap, EntityHashSet};
+use bevy_ecs::lifecycle::HookContext;
+use bevy_ecs::resource::Resource;
+use bevy_ecs::world::DeferredWorld;
+use bevy_math::{I64Vec2, IVec2, Rect, Vec2};
+use bevy_platform::collections::HashMap;
 use bevy_reflect::Reflect;
 
+/// The bounds of a spawned project, world, level, layer or entity, in world space.
+///
+/// This is an immutable component, so [ShieldtankSpatialIndex] sees every change. To change the
+/// bounds, insert a new value rather than mutating it through a query.
 #[derive(Clone, Debug, Deref, Component, Reflect)]
+#[component(immutable, on_insert = world_bounds_on_insert, on_replace = world_bounds_on_replace)]
 pub struct ShieldtankWorldBounds {
     #[deref]
     bounds: Rect,
@@ -32,9 +42,426 @@ impl From<Rect> for ShieldtankWorldBounds {
     }
 }
 
+fn world_bounds_on_insert(mut world: DeferredWorld, context: HookContext) {
+    let Some(bounds) = world.get::<ShieldtankWorldBounds>(context.entity) else {
+        return;
+    };
+
+    let bounds = bounds.bounds();
+
+    if let Some(mut spatial_index) = world.get_resource_mut::<ShieldtankSpatialIndex>() {
+        spatial_index.insert(context.entity, bounds);
+    }
+}
+
+fn world_bounds_on_replace(mut world: DeferredWorld, context: HookContext) {
+    if let Some(mut spatial_index) = world.get_resource_mut::<ShieldtankSpatialIndex>() {
+        spatial_index.remove(context.entity);
+    }
+}
+
+/// Where a ray enters a [ShieldtankWorldBounds].
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct ShieldtankRayHit {
+    pub entity: Entity,
+    /// Distance along the ray, or `0.0` when the ray starts inside the bounds.
+    pub distance: f32,
+    pub point: Vec2,
+    /// The normal of the side the ray entered through, or zero when it starts inside.
+    pub normal: Vec2,
+}
+
+/// Where a ray from `origin` along the normalized `direction` enters `bounds`, as the distance and
+/// the normal of the side it crosses.
+fn ray_entry(bounds: Rect, origin: Vec2, direction: Vec2) -> Option<(f32, Vec2)> {
+    if bounds.contains(origin) {
+        return Some((0.0, Vec2::ZERO));
+    }
+
+    let inverse = direction.recip();
+    let t0 = (bounds.min - origin) * inverse;
+    let t1 = (bounds.max - origin) * inverse;
+
+    // A ray parallel to an axis is within that axis' slab everywhere or nowhere. Its slab
+    // distances are NaN when the origin lies on an edge, from `0.0 * inf`, so they're replaced.
+    let parallel = direction.cmpeq(Vec2::ZERO);
+    let in_slab = origin.cmpge(bounds.min) & origin.cmple(bounds.max);
+    if (parallel & !in_slab).any() {
+        return None;
+    }
+
+    let near = Vec2::select(parallel, Vec2::NEG_INFINITY, t0.min(t1));
+    let far = Vec2::select(parallel, Vec2::INFINITY, t0.max(t1));
+
+    let entry = near.x.max(near.y);
+    let exit = far.x.min(far.y);
+
+    if entry > exit || exit < 0.0 {
+        return None;
+    }
+
+    let normal = match near.x > near.y {
+        true => Vec2::new(-direction.x.signum(), 0.0),
+        false => Vec2::new(0.0, -direction.y.signum()),
+    };
+
+    Some((entry, normal))
+}
+
+/// A uniform grid over every [ShieldtankWorldBounds], kept up to date as they are inserted and
+/// removed.
+///
+/// Replace the resource with [ShieldtankSpatialIndex::new] before anything spawns to change the
+/// cell size.
+#[derive(Debug, Resource)]
+pub struct ShieldtankSpatialIndex {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<Entity>>,
+    /// Bounds covering too many cells, which are checked on every query instead.
+    large: EntityHashSet,
+    bounds: EntityHashMap<Rect>,
+}
+
+impl Default for ShieldtankSpatialIndex {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CELL_SIZE)
+    }
+}
+
+impl ShieldtankSpatialIndex {
+    pub const DEFAULT_CELL_SIZE: f32 = 256.0;
+    const MAX_CELLS: i64 = 64;
+
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+            large: EntityHashSet::default(),
+            bounds: EntityHashMap::default(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn bounds(&self, entity: Entity) -> Option<Rect> {
+        self.bounds.get(&entity).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.bounds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bounds.is_empty()
+    }
+
+    /// The entities whose bounds contain `point`.
+    pub fn at_point(&self, point: Vec2) -> EntityHashSet {
+        self.candidates(Rect::from_center_size(point, Vec2::ZERO))
+            .filter(|entity| self.bounds[entity].contains(point))
+            .collect()
+    }
+
+    /// The entities whose bounds overlap `rect`, including those only touching its edges.
+    pub fn overlapping_rect(&self, rect: Rect) -> EntityHashSet {
+        self.candidates(rect)
+            .filter(|entity| {
+                let bounds = self.bounds[entity];
+                bounds.min.cmple(rect.max).all() && rect.min.cmple(bounds.max).all()
+            })
+            .collect()
+    }
+
+    /// The entities whose bounds come within `radius` of `center`.
+    pub fn within_radius(&self, center: Vec2, radius: f32) -> EntityHashSet {
+        self.candidates(Rect::from_center_half_size(center, Vec2::splat(radius)))
+            .filter(|entity| {
+                let bounds = self.bounds[entity];
+                center.clamp(bounds.min, bounds.max).distance(center) <= radius
+            })
+            .collect()
+    }
+
+    /// The distance from `point` to the bounds of `entity`, or `0.0` when inside them.
+    pub fn distance(&self, entity: Entity, point: Vec2) -> Option<f32> {
+        let bounds = self.bounds.get(&entity)?;
+        Some(point.clamp(bounds.min, bounds.max).distance(point))
+    }
+
+    /// Every bounds hit by the ray within `max_distance`, nearest first.
+    pub fn raycast(
+        &self,
+        origin: Vec2,
+        direction: Vec2,
+        max_distance: f32,
+    ) -> Vec<ShieldtankRayHit> {
+        let direction = direction.normalize_or_zero();
+        if direction == Vec2::ZERO {
+            return vec![];
+        }
+
+        let end = origin + direction * max_distance;
+
+        let mut hits: Vec<ShieldtankRayHit> = self
+            .candidates(Rect::from_corners(origin, end))
+            .filter_map(|entity| {
+                let (distance, normal) = ray_entry(self.bounds[&entity], origin, direction)?;
+                (distance <= max_distance).then_some(ShieldtankRayHit {
+                    entity,
+                    distance,
+                    point: origin + direction * distance,
+                    normal,
+                })
+            })
+            .collect();
+
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+
+        hits
+    }
+
+    /// The entities in the cells covered by `rect`, unfiltered.
+    fn candidates(&self, rect: Rect) -> impl Iterator<Item = Entity> {
+        let (min, max) = self.cell_range(rect);
+        let cell_count = Self::cell_count(min, max);
+
+        // Large areas are cheaper to check against the occupied cells.
+        let cells: Box<dyn Iterator<Item = &Vec<Entity>>> =
+            match cell_count > self.cells.len() as i64 {
+                true => Box::new(
+                    self.cells
+                        .iter()
+                        .filter(move |(cell, _)| cell.cmpge(min).all() && cell.cmple(max).all())
+                        .map(|(_, entities)| entities),
+                ),
+                false => Box::new(
+                    (min.y..=max.y)
+                        .flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+                        .filter_map(|cell| self.cells.get(&cell)),
+                ),
+            };
+
+        let candidates: EntityHashSet = cells
+            .flatten()
+            .copied()
+            .chain(self.large.iter().copied())
+            .collect();
+        candidates.into_iter()
+    }
+
+    fn cell_range(&self, rect: Rect) -> (IVec2, IVec2) {
+        let min = (rect.min / self.cell_size).floor().as_ivec2();
+        let max = (rect.max / self.cell_size).floor().as_ivec2();
+        (min, max)
+    }
+
+    fn cell_count(min: IVec2, max: IVec2) -> i64 {
+        let size = max.as_i64vec2() - min.as_i64vec2() + I64Vec2::ONE;
+        size.x.saturating_mul(size.y)
+    }
+
+    fn insert(&mut self, entity: Entity, bounds: Rect) {
+        self.remove(entity);
+        self.bounds.insert(entity, bounds);
+
+        let (min, max) = self.cell_range(bounds);
+
+        if Self::cell_count(min, max) > Self::MAX_CELLS {
+            self.large.insert(entity);
+            return;
+        }
+
+        (min.y..=max.y)
+            .flat_map(|y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+            .for_each(|cell| self.cells.entry(cell).or_default().push(entity));
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        let Some(bounds) = self.bounds.remove(&entity) else {
+            return;
+        };
+
+        if self.large.remove(&entity) {
+            return;
+        }
+
+        let (min, max) = self.cell_range(bounds);
+
+        (min.y..=max.y)
+            .flat_map(|y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+            .for_each(|cell| {
+                if let Some(entities) = self.cells.get_mut(&cell) {
+                    entities.retain(|cell_entity| *cell_entity != entity);
+                    if entities.is_empty() {
+                        self.cells.remove(&cell);
+                    }
+                }
+            });
+    }
+}
+
 pub struct GlobalBoundsPlugin;
 impl Plugin for GlobalBoundsPlugin {
     fn build(&self, app: &mut bevy_app::App) {
         app.register_type::<ShieldtankWorldBounds>();
+        app.register_type::<ShieldtankRayHit>();
+        app.init_resource::<ShieldtankSpatialIndex>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test::test_app;
+
+    use super::*;
+
+    fn app() -> bevy_app::App {
+        let mut app = test_app();
+        app.init_resource::<ShieldtankSpatialIndex>();
+        app
+    }
+
+    fn spawn(app: &mut bevy_app::App, min: Vec2, max: Vec2) -> Entity {
+        app.world_mut()
+            .spawn(ShieldtankWorldBounds::new(min, max))
+            .id()
+    }
+
+    fn spatial_index(app: &bevy_app::App) -> &ShieldtankSpatialIndex {
+        app.world().resource::<ShieldtankSpatialIndex>()
+    }
+
+    fn set(entities: impl IntoIterator<Item = Entity>) -> EntityHashSet {
+        entities.into_iter().collect()
+    }
+
+    #[test]
+    fn follows_inserted_replaced_and_removed_bounds() {
+        let mut app = app();
+        let entity = spawn(&mut app, Vec2::ZERO, Vec2::splat(10.0));
+
+        assert_eq!(spatial_index(&app).len(), 1);
+        assert_eq!(
+            spatial_index(&app).at_point(Vec2::splat(5.0)),
+            set([entity])
+        );
+
+        app.world_mut()
+            .entity_mut(entity)
+            .insert(ShieldtankWorldBounds::new(
+                Vec2::splat(500.0),
+                Vec2::splat(510.0),
+            ));
+        assert!(spatial_index(&app).at_point(Vec2::splat(5.0)).is_empty());
+        assert_eq!(
+            spatial_index(&app).at_point(Vec2::splat(505.0)),
+            set([entity])
+        );
+
+        app.world_mut().entity_mut(entity).despawn();
+        assert!(spatial_index(&app).is_empty());
+        assert!(spatial_index(&app).at_point(Vec2::splat(505.0)).is_empty());
+    }
+
+    #[test]
+    fn finds_bounds_by_region() {
+        let mut app = app();
+        let near = spawn(&mut app, Vec2::ZERO, Vec2::splat(10.0));
+        let far = spawn(&mut app, Vec2::splat(1000.0), Vec2::splat(1010.0));
+        // Covers more cells than are indexed, so it's checked on every query.
+        let large = spawn(&mut app, Vec2::splat(-5000.0), Vec2::splat(5000.0));
+
+        let spatial_index = spatial_index(&app);
+        assert!(spatial_index.large.contains(&large));
+
+        assert_eq!(spatial_index.at_point(Vec2::splat(5.0)), set([near, large]));
+        assert_eq!(
+            spatial_index.overlapping_rect(Rect::new(10.0, 10.0, 1000.0, 1000.0)),
+            set([near, far, large])
+        );
+        assert_eq!(
+            spatial_index.overlapping_rect(Rect::new(20.0, 20.0, 900.0, 900.0)),
+            set([large])
+        );
+        assert_eq!(
+            spatial_index.within_radius(Vec2::new(13.0, 14.0), 5.0),
+            set([near, large])
+        );
+        assert_eq!(
+            spatial_index.within_radius(Vec2::new(13.0, 14.0), 4.9),
+            set([large])
+        );
+        assert_eq!(
+            spatial_index.distance(near, Vec2::new(13.0, 14.0)),
+            Some(5.0)
+        );
+        assert_eq!(spatial_index.distance(large, Vec2::ZERO), Some(0.0));
+    }
+
+    #[test]
+    fn raycasts_return_hits_nearest_first() {
+        let mut app = app();
+        let first = spawn(&mut app, Vec2::new(10.0, -5.0), Vec2::new(20.0, 5.0));
+        let second = spawn(&mut app, Vec2::new(300.0, -5.0), Vec2::new(310.0, 5.0));
+        spawn(&mut app, Vec2::new(10.0, 50.0), Vec2::new(20.0, 60.0));
+
+        let hits = spatial_index(&app).raycast(Vec2::ZERO, Vec2::X, 1000.0);
+
+        assert_eq!(
+            hits.iter().map(|hit| hit.entity).collect::<Vec<_>>(),
+            vec![first, second]
+        );
+        assert_eq!(hits[0].distance, 10.0);
+        assert_eq!(hits[0].point, Vec2::new(10.0, 0.0));
+        assert_eq!(hits[0].normal, Vec2::NEG_X);
+
+        assert_eq!(
+            spatial_index(&app)
+                .raycast(Vec2::ZERO, Vec2::X, 100.0)
+                .len(),
+            1
+        );
+        assert!(
+            spatial_index(&app)
+                .raycast(Vec2::ZERO, Vec2::ZERO, 100.0)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn ray_entry_of_bounds() {
+        let bounds = Rect::new(10.0, 10.0, 20.0, 20.0);
+
+        assert_eq!(
+            ray_entry(bounds, Vec2::new(15.0, 15.0), Vec2::X),
+            Some((0.0, Vec2::ZERO))
+        );
+        assert_eq!(
+            ray_entry(bounds, Vec2::new(15.0, 30.0), Vec2::NEG_Y),
+            Some((10.0, Vec2::Y))
+        );
+        assert_eq!(
+            ray_entry(bounds, Vec2::ZERO, Vec2::ONE.normalize()).map(|(_, normal)| normal),
+            Some(Vec2::NEG_Y)
+        );
+        assert_eq!(ray_entry(bounds, Vec2::new(0.0, 15.0), Vec2::NEG_X), None);
+        assert_eq!(ray_entry(bounds, Vec2::new(0.0, 30.0), Vec2::X), None);
+    }
+
+    #[test]
+    fn rays_along_an_edge_enter_the_bounds() {
+        let bounds = Rect::new(10.0, 10.0, 20.0, 20.0);
+
+        // The origin's y is on the bottom edge, and the ray has no y component.
+        assert_eq!(
+            ray_entry(bounds, Vec2::new(0.0, 10.0), Vec2::X),
+            Some((10.0, Vec2::NEG_X))
+        );
+        assert_eq!(
+            ray_entry(bounds, Vec2::new(20.0, 0.0), Vec2::Y),
+            Some((10.0, Vec2::NEG_Y))
+        );
     }
 }
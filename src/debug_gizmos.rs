@@ -89,17 +89,20 @@ fn debug_gizmos_system(
 
     if debug_gizmos.grid_values_query {
         for layer in layer_with_grid_values_query.iter() {
-            grid_values_query
-                .enumerate_layer(layer)
-                .for_each(|(rect, grid_value)| {
-                    let color = debug_gizmos
-                        .grid_values_color_override
-                        .unwrap_or(grid_value.color);
-
-                    let center = rect.center();
-                    let size = rect.size();
-                    gizmos.rect_2d(center, size, color);
-                });
+            // Layers without bounds yet are skipped.
+            let Ok(cells) = grid_values_query.enumerate_layer(layer) else {
+                continue;
+            };
+
+            cells.for_each(|(rect, grid_value)| {
+                let color = debug_gizmos
+                    .grid_values_color_override
+                    .unwrap_or(grid_value.color);
+
+                let center = rect.center();
+                let size = rect.size();
+                gizmos.rect_2d(center, size, color);
+            });
         }
     }
 
@@ -1,3 +1,7 @@
+use std::borrow::Cow;
+
+use bevy_ecs::query::QueryEntityError;
+use bevy_ldtk_asset::iid::Iid;
 use bevy_math::Vec2;
 use itertools::ExactlyOneError;
 
@@ -9,6 +13,12 @@ pub enum ShieldtankError {
     #[error(transparent)]
     IoError(#[from] std::io::Error),
 
+    #[error(transparent)]
+    ReadAssetBytesError(#[from] bevy_asset::ReadAssetBytesError),
+
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+
     #[error(transparent)]
     IntoDynamicImageError(#[from] bevy_image::IntoDynamicImageError),
 
@@ -18,6 +28,18 @@ pub enum ShieldtankError {
     #[error("{0:?}")]
     SingleError(SingleError),
 
+    #[error(transparent)]
+    LdtkFieldError(#[from] LdtkFieldError),
+
+    #[error(transparent)]
+    IidError(#[from] IidError),
+
+    #[error(transparent)]
+    RonError(#[from] ron::Error),
+
+    #[error(transparent)]
+    RonSpannedError(#[from] ron::error::SpannedError),
+
     #[error("ShieldtankError! {0}")]
     ShieldtankError(String),
 }
@@ -40,6 +62,31 @@ pub enum SingleError {
     MultipleItems(Vec2),
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum LdtkFieldError {
+    #[error("missing LDtk field `{identifier}`")]
+    Missing { identifier: String },
+
+    #[error("LDtk field `{identifier}`: expected {expected}, found {found}")]
+    TypeMismatch {
+        identifier: String,
+        expected: Cow<'static, str>,
+        found: String,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum IidError {
+    #[error("no entity with Iid {0}")]
+    NotFound(Iid),
+
+    #[error("Iid {0} is spawned under more than one root")]
+    Ambiguous(Iid),
+
+    #[error("entity with Iid {iid} does not match the query: {error}")]
+    Query { iid: Iid, error: QueryEntityError },
+}
+
 #[macro_export]
 macro_rules! shieldtank_error {
     ($($args:tt)*) => {
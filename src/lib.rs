@@ -9,6 +9,9 @@ pub mod debug_gizmos;
 
 pub use bevy_ldtk_asset;
 
+#[cfg(test)]
+pub(crate) mod test;
+
 pub mod result {
     pub type ShieldtankResult<T> = std::result::Result<T, crate::error::ShieldtankError>;
 }
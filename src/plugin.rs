@@ -1,17 +1,34 @@
 use bevy_app::{PluginGroup, PluginGroupBuilder};
 use bevy_ldtk_asset::plugin::BevyLdtkAssetPlugin;
 
+use crate::component::auto_rules::AutoRulesPlugin;
+use crate::component::colliders::CollidersPlugin;
 use crate::component::entity::ShieldtankEntityPlugin;
 use crate::component::entity_definition::EntityDefinitionPlugin;
+use crate::component::entity_ref::EntityRefPlugin;
+use crate::component::entity_registry::LdtkEntityRegistryPlugin;
 use crate::component::field_instances::FieldInstancesPlugin;
+use crate::component::filter::FilterPlugin;
+use crate::component::grid_coords::GridCoordsPlugin;
+use crate::component::grid_edits::GridEditsPlugin;
 use crate::component::grid_values::GridValuesPlugin;
 use crate::component::iid::IidPlugin;
+use crate::component::int_grid_cell::IntGridCellPlugin;
+use crate::component::int_grid_rendering::IntGridRenderingPlugin;
+use crate::component::kinematic::KinematicPlugin;
 use crate::component::layer::ShieldtankLayerPlugin;
 use crate::component::layer_definition::LayerDefinitionPlugin;
 use crate::component::layer_tiles::LayerTilePlugin;
 use crate::component::level::ShieldtankLevelPlugin;
 use crate::component::level_background::LevelBackgroundPlugin;
+use crate::component::level_spawner::LevelSpawnerPlugin;
+use crate::component::level_streaming::LevelStreamingPlugin;
+use crate::component::level_transition::LevelTransitionPlugin;
+use crate::component::lifecycle::LifecyclePlugin;
+use crate::component::load_progress::LoadProgressPlugin;
+use crate::component::navigation::NavigationPlugin;
 use crate::component::project::LdtkProjectPlugin;
+use crate::component::save_state::SaveStatePlugin;
 use crate::component::spawn_children::SpawnChildrenPlugin;
 use crate::component::tags::TagsPlugin;
 use crate::component::tile::TilePlugin;
@@ -32,7 +49,16 @@ impl PluginGroup for ShieldtankPlugins {
             .add(ShieldtankLevelPlugin)
             .add(ShieldtankLayerPlugin)
             .add(ShieldtankEntityPlugin)
+            .add(LdtkEntityRegistryPlugin)
+            .add(EntityRefPlugin)
             .add(SpawnChildrenPlugin)
+            .add(FilterPlugin)
+            .add(LevelSpawnerPlugin)
+            .add(LevelStreamingPlugin)
+            .add(LevelTransitionPlugin)
+            .add(LifecyclePlugin)
+            .add(LoadProgressPlugin)
+            .add(SaveStatePlugin)
             // LDtk definitions
             .add(EntityDefinitionPlugin)
             .add(LayerDefinitionPlugin)
@@ -44,6 +70,14 @@ impl PluginGroup for ShieldtankPlugins {
             .add(LevelBackgroundPlugin)
             .add(GlobalBoundsPlugin)
             .add(GridValuesPlugin)
+            .add(GridEditsPlugin)
+            .add(AutoRulesPlugin)
+            .add(GridCoordsPlugin)
+            .add(NavigationPlugin)
+            .add(CollidersPlugin)
+            .add(KinematicPlugin)
+            .add(IntGridCellPlugin)
+            .add(IntGridRenderingPlugin)
             .add(TagsPlugin)
             .add(TilePlugin);
 
@@ -1,16 +1,62 @@
 pub use crate::component::entity::{ShieldtankEntity, ShieldtankEntityPlugin};
+pub use crate::component::entity_ref::{
+    LdtkEntityRefs, LdtkReferenceOf, LdtkReferencedBy, LdtkReferences, LdtkRefersTo,
+    ShieldtankEntityRef,
+};
+pub use crate::component::entity_registry::{LdtkEntity, LdtkEntityAppExt};
 pub use crate::component::layer::{ShieldtankLayer, ShieldtankLayerPlugin};
 pub use crate::component::level::{ShieldtankLevel, ShieldtankLevelPlugin};
 pub use crate::component::world::{ShieldtankWorld, ShieldtankWorldPlugin};
 
+pub use crate::component::auto_rules::ShieldtankAutoRules;
+pub use crate::component::auto_rules::asset::LdtkAutoRules;
+pub use crate::component::auto_rules::rule::{
+    ANY_VALUE, AutoRule, AutoRuleChecker, AutoRuleGroup, AutoRuleTile,
+};
+pub use crate::component::colliders::{
+    ColliderGeometry, ColliderGroupRule, ColliderShape, ShieldtankCollider,
+    ShieldtankColliderSettings, ShieldtankColliders,
+};
 pub use crate::component::field_instances::ShieldtankFieldInstances;
-pub use crate::component::iid::ShieldtankIid;
+pub use crate::component::filter::{FieldComparison, FieldValue, ShieldtankComponentFilter};
+pub use crate::component::grid_coords::GridCoords;
+pub use crate::component::grid_edits::{GridValueChanged, GridValuesEditor, ShieldtankGridEdits};
+pub use crate::component::grid_values::{
+    IntGridSelector, ShieldtankGridValue, ShieldtankGridValues,
+};
+pub use crate::component::iid::{IidRegistry, ShieldtankIid};
+pub use crate::component::int_grid_cell::{IntGridCellAppExt, LdtkIntGridCell};
+pub use crate::component::int_grid_rendering::ShieldtankIntGridRendering;
+pub use crate::component::kinematic::{
+    KinematicContacts, KinematicStepped, ShieldtankKinematicBody, ShieldtankKinematicTerrain,
+};
+pub use crate::component::ldtk_fields::{FromLdtkField, LdtkFields, LdtkFieldsAppExt};
+pub use crate::component::level_spawner::{LevelSelector, SpawnLdtkLevelCommandsExt};
+pub use crate::component::level_streaming::{
+    ShieldtankLevelStreaming, ShieldtankStreamedLevels, ShieldtankStreamingFocus,
+};
+pub use crate::component::level_transition::{LevelEntered, LevelExited, ShieldtankLevelTracker};
+pub use crate::component::lifecycle::{
+    EntityDespawned, EntitySpawned, LayerDespawned, LayerSpawned, LevelDespawned, LevelFullyReady,
+    LevelSpawned, ProjectDespawned, ProjectSpawned, WorldDespawned, WorldSpawned,
+};
+pub use crate::component::load_progress::{ShieldtankLoadCount, ShieldtankLoadProgress};
+pub use crate::component::navigation::{
+    NavConnectivity, NavCornerCutting, NavCostRule, NavGridQuery, NavPath, ShieldtankNavGraph,
+    ShieldtankNavSettings,
+};
+pub use crate::component::save_state::{SaveStateEntityCommandsExt, ShieldtankSaveState};
+pub use crate::component::spawn_children::ShieldtankReloadDiff;
 pub use crate::component::tile::ShieldtankTile;
-pub use crate::component::world_bounds::ShieldtankWorldBounds;
+pub use crate::component::world_bounds::{
+    ShieldtankRayHit, ShieldtankSpatialIndex, ShieldtankWorldBounds,
+};
 
-pub use crate::query::by_global_bounds::QueryByGlobalBounds;
-pub use crate::query::by_iid::SingleByIid;
-pub use crate::query::grid_value::GridValueQuery;
+pub use crate::query::by_global_bounds::{BoundsOrder, QueryByGlobalBounds};
+pub use crate::query::by_iid::{QueryByIid, SingleByIid};
+pub use crate::query::grid_value::{GridRayHit, GridValueQuery};
+pub use crate::query::layer_grid::{LayerGrid, LayerGridQuery};
+pub use crate::query::level_neighbours::LevelNeighbours;
 pub use crate::query::location::{
     ShieldtankWorldLocation, ShieldtankWorldLocationChanged, ShieldtankWorldLocationMut,
 };
@@ -18,3 +64,5 @@ pub use crate::query::location::{
 pub use crate::plugin::ShieldtankPlugins;
 
 pub use bevy_ldtk_asset::iid::{Iid, iid};
+
+pub use shieldtank_derive::{LdtkEnum, LdtkFields};
@@ -1,27 +1,49 @@
+use bevy_ecs::entity::{Entity, EntityHashSet};
 use bevy_ecs::query::{QueryData, QueryFilter};
-use bevy_ecs::system::{Query, SystemParam};
-use bevy_math::Vec2;
+use bevy_ecs::system::{Query, Res, SystemParam};
+use bevy_math::{Rect, Vec2};
+use bevy_transform::components::GlobalTransform;
 use itertools::Itertools;
 
-use crate::component::world_bounds::ShieldtankWorldBounds;
+use crate::component::world_bounds::{
+    ShieldtankRayHit, ShieldtankSpatialIndex, ShieldtankWorldBounds,
+};
 use crate::error::ShieldtankError;
 use crate::result::ShieldtankResult;
 
 #[derive(SystemParam)]
 pub struct QueryByGlobalBounds<'w, 's, D: QueryData + 'static, F: QueryFilter + 'static = ()> {
-    pub(crate) inner_query: Query<'w, 's, (&'static ShieldtankWorldBounds, D), F>,
+    pub(crate) inner_query: Query<
+        'w,
+        's,
+        (
+            &'static ShieldtankWorldBounds,
+            Option<&'static GlobalTransform>,
+            D,
+        ),
+        F,
+    >,
+    pub(crate) spatial_index: Res<'w, ShieldtankSpatialIndex>,
 }
 
 macro_rules! by_location_closure {
     ($self:expr, $iter:tt, $location:expr) => {
         $self
             .inner_query
-            .$iter()
-            .filter(move |(global_bounds, _)| global_bounds.contains($location))
-            .map(|(_, data)| data)
+            .$iter($self.spatial_index.at_point($location))
+            .map(|(_, _, data)| data)
     };
 }
 
+/// The order of results from region queries, such as [QueryByGlobalBounds::overlapping_rect].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BoundsOrder {
+    #[default]
+    Unordered,
+    /// Highest Z first, the way [GridValueQuery](super::grid_value::GridValueQuery) orders layers.
+    TopmostFirst,
+}
+
 impl<'w, 's, D, F> QueryByGlobalBounds<'w, 's, D, F>
 where
     D: QueryData<ReadOnly = D> + 'static,
@@ -30,7 +52,7 @@ where
 {
     #[inline]
     pub fn by_location(&'w self, location: Vec2) -> impl Iterator<Item = D::Item<'w, 's>> {
-        by_location_closure!(self, iter, location)
+        by_location_closure!(self, iter_many_unique, location)
     }
 
     #[inline]
@@ -42,9 +64,106 @@ where
 
     #[inline]
     pub fn any(&'w self, location: Vec2) -> bool {
-        self.inner_query
-            .iter()
-            .any(|(global_bounds, _)| global_bounds.contains(location))
+        self.by_location(location).next().is_some()
+    }
+
+    fn ordered(
+        &'w self,
+        entities: EntityHashSet,
+        order: BoundsOrder,
+    ) -> impl Iterator<Item = D::Item<'w, 's>> {
+        let mut items = self
+            .inner_query
+            .iter_many_unique(entities)
+            .map(|(_, global_transform, data)| {
+                let z = global_transform
+                    .map_or(0.0, |global_transform| global_transform.translation().z);
+                (z, data)
+            })
+            .collect::<Vec<_>>();
+
+        if order == BoundsOrder::TopmostFirst {
+            items.sort_by(|(z_a, _), (z_b, _)| z_b.total_cmp(z_a));
+        }
+
+        items.into_iter().map(|(_, data)| data)
+    }
+
+    /// Everything whose bounds overlap `rect`, including bounds only touching its edges.
+    pub fn overlapping_rect(
+        &'w self,
+        rect: Rect,
+        order: BoundsOrder,
+    ) -> impl Iterator<Item = D::Item<'w, 's>> {
+        self.ordered(self.spatial_index.overlapping_rect(rect), order)
+    }
+
+    /// Everything whose bounds come within `radius` of `center`.
+    pub fn within_radius(
+        &'w self,
+        center: Vec2,
+        radius: f32,
+        order: BoundsOrder,
+    ) -> impl Iterator<Item = D::Item<'w, 's>> {
+        self.ordered(self.spatial_index.within_radius(center, radius), order)
+    }
+
+    /// The closest bounds to `point`, along with the distance to them.
+    pub fn nearest(&'w self, point: Vec2) -> Option<(f32, D::Item<'w, 's>)> {
+        self.k_nearest(point, 1).into_iter().next()
+    }
+
+    /// Up to `k` of the closest bounds to `point`, nearest first, along with the distance to them.
+    ///
+    /// Bounds containing `point` are at distance `0.0`.
+    pub fn k_nearest(&'w self, point: Vec2, k: usize) -> Vec<(f32, D::Item<'w, 's>)> {
+        if k == 0 {
+            return vec![];
+        }
+
+        let mut radius = self.spatial_index.cell_size();
+
+        loop {
+            let candidates = self.spatial_index.within_radius(point, radius);
+            let exhausted = candidates.len() == self.spatial_index.len();
+
+            let mut found: Vec<(f32, Entity)> = candidates
+                .into_iter()
+                .filter(|entity| self.inner_query.contains(*entity))
+                .filter_map(|entity| Some((self.spatial_index.distance(entity, point)?, entity)))
+                .collect();
+
+            if found.len() >= k || exhausted {
+                found.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+                found.truncate(k);
+
+                return found
+                    .into_iter()
+                    .filter_map(|(distance, entity)| {
+                        let (_, _, data) = self.inner_query.get(entity).ok()?;
+                        Some((distance, data))
+                    })
+                    .collect();
+            }
+
+            radius *= 2.0;
+        }
+    }
+
+    /// Everything hit by the ray within `max_distance`, nearest first.
+    pub fn raycast(
+        &'w self,
+        origin: Vec2,
+        direction: Vec2,
+        max_distance: f32,
+    ) -> impl Iterator<Item = (ShieldtankRayHit, D::Item<'w, 's>)> {
+        self.spatial_index
+            .raycast(origin, direction, max_distance)
+            .into_iter()
+            .filter_map(|hit| {
+                let (_, _, data) = self.inner_query.get(hit.entity).ok()?;
+                Some((hit, data))
+            })
     }
 }
 
@@ -61,7 +180,7 @@ where
     where
         'w: 's,
     {
-        by_location_closure!(self, iter_mut, location)
+        by_location_closure!(self, iter_many_unique_mut, location)
     }
 
     #[inline]
@@ -77,3 +196,148 @@ where
             .map_err(|e| ShieldtankError::from_exactly_one(e, location))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::component::Component;
+    use bevy_ecs::query::With;
+    use bevy_ecs::system::RunSystemOnce;
+    use bevy_transform::components::Transform;
+
+    use crate::test::test_app;
+
+    use super::*;
+
+    #[derive(Component)]
+    struct Marked;
+
+    struct Bounds {
+        app: bevy_app::App,
+        entities: Vec<Entity>,
+    }
+
+    impl Bounds {
+        /// Unit squares at x = 0, 10, 20 and 1000, with the second and last marked, and rising Z.
+        fn new() -> Self {
+            let mut app = test_app();
+            app.init_resource::<ShieldtankSpatialIndex>();
+
+            let entities = [0.0, 10.0, 20.0, 1000.0]
+                .into_iter()
+                .enumerate()
+                .map(|(index, x)| {
+                    let bounds = ShieldtankWorldBounds::from(Rect::new(x, 0.0, x + 1.0, 1.0));
+                    let transform = Transform::from_xyz(x, 0.0, index as f32);
+                    let mut entity = app
+                        .world_mut()
+                        .spawn((bounds, GlobalTransform::from(transform)));
+                    if index % 2 == 1 {
+                        entity.insert(Marked);
+                    }
+                    entity.id()
+                })
+                .collect();
+
+            Self { app, entities }
+        }
+    }
+
+    #[test]
+    fn k_nearest_returns_the_closest_bounds_in_order() {
+        let Bounds { mut app, entities } = Bounds::new();
+
+        let nearest = app
+            .world_mut()
+            .run_system_once(|query: QueryByGlobalBounds<Entity>| {
+                query.k_nearest(Vec2::new(9.0, 0.5), 3)
+            })
+            .unwrap();
+
+        assert_eq!(
+            nearest,
+            vec![(1.0, entities[1]), (8.0, entities[0]), (11.0, entities[2])]
+        );
+    }
+
+    #[test]
+    fn k_nearest_grows_its_search_until_it_finds_enough() {
+        let Bounds { mut app, entities } = Bounds::new();
+
+        let (all, marked, none) = app
+            .world_mut()
+            .run_system_once(
+                |query: QueryByGlobalBounds<Entity>,
+                 marked_query: QueryByGlobalBounds<Entity, With<Marked>>| {
+                    (
+                        query.k_nearest(Vec2::ZERO, 10),
+                        marked_query.k_nearest(Vec2::new(20.0, 0.0), 2),
+                        query.k_nearest(Vec2::ZERO, 0),
+                    )
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            all.iter().map(|(_, entity)| *entity).collect::<Vec<_>>(),
+            entities
+        );
+        assert_eq!(
+            marked.iter().map(|(_, entity)| *entity).collect::<Vec<_>>(),
+            vec![entities[1], entities[3]]
+        );
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn nearest_is_zero_inside_bounds() {
+        let Bounds { mut app, entities } = Bounds::new();
+
+        let nearest = app
+            .world_mut()
+            .run_system_once(|query: QueryByGlobalBounds<Entity>| {
+                query.nearest(Vec2::new(20.5, 0.5))
+            })
+            .unwrap();
+
+        assert_eq!(nearest, Some((0.0, entities[2])));
+    }
+
+    #[test]
+    fn region_queries_order_topmost_first() {
+        let Bounds { mut app, entities } = Bounds::new();
+
+        let (overlapping, within_radius) = app
+            .world_mut()
+            .run_system_once(|query: QueryByGlobalBounds<Entity>| {
+                (
+                    query
+                        .overlapping_rect(Rect::new(0.0, 0.0, 20.0, 1.0), BoundsOrder::TopmostFirst)
+                        .collect::<Vec<_>>(),
+                    query
+                        .within_radius(Vec2::new(10.5, 0.5), 9.5, BoundsOrder::TopmostFirst)
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .unwrap();
+
+        assert_eq!(overlapping, vec![entities[2], entities[1], entities[0]]);
+        assert_eq!(within_radius, vec![entities[2], entities[1], entities[0]]);
+    }
+
+    #[test]
+    fn raycast_skips_bounds_outside_the_query() {
+        let Bounds { mut app, entities } = Bounds::new();
+
+        let hits = app
+            .world_mut()
+            .run_system_once(|query: QueryByGlobalBounds<Entity, With<Marked>>| {
+                query
+                    .raycast(Vec2::new(-5.0, 0.5), Vec2::X, 2000.0)
+                    .map(|(hit, entity)| (hit.distance, entity))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap();
+
+        assert_eq!(hits, vec![(15.0, entities[1]), (1005.0, entities[3])]);
+    }
+}
@@ -3,17 +3,21 @@ use std::ops::Deref;
 use std::ops::DerefMut;
 
 use bevy_ecs::change_detection::Tick;
+use bevy_ecs::entity::Entity;
 use bevy_ecs::query::ReadOnlyQueryData;
 use bevy_ecs::query::{FilteredAccessSet, QueryState};
-use bevy_ecs::query::{QueryData, QueryFilter};
+use bevy_ecs::query::{QueryData, QueryFilter, ROQueryItem};
 use bevy_ecs::system::ReadOnlySystemParam;
-use bevy_ecs::system::{Query, SystemParam};
+use bevy_ecs::system::{Query, Res, SystemParam};
 use bevy_ecs::system::{SystemMeta, SystemParamValidationError};
 use bevy_ecs::world::World;
 use bevy_ecs::world::unsafe_world_cell::UnsafeWorldCell;
 
+use itertools::Itertools;
+
 use crate::bevy_ldtk_asset::iid::Iid;
 use crate::component::iid::IidRegistry;
+use crate::error::IidError;
 
 #[derive(Debug)]
 pub struct SingleByIid<'w, 's, const IID: u128, D: QueryData, F: QueryFilter = ()> {
@@ -80,9 +84,8 @@ unsafe impl<'w, 's, const IID: u128, D: QueryData + 'static, F: QueryFilter + 's
                 .expect("Couldn't get IidRegistry! Is the plugin loaded?")
         };
 
-        let entity = *registry
-            .registry
-            .get(&iid)
+        let entity = registry
+            .get_unique(iid)
             .expect("Entity not found! This should have been handled in `validate_param`!");
 
         // SAFETY: These shennanigans are dependent on `validate_param` only
@@ -118,12 +121,15 @@ unsafe impl<'w, 's, const IID: u128, D: QueryData + 'static, F: QueryFilter + 's
             state.query_unchecked_with_ticks(world, world.last_change_tick(), world.change_tick())
         };
 
-        let entity = *registry
-            .registry
-            .get(&iid)
-            .ok_or(SystemParamValidationError::skipped::<Self>(
-                "Iid not found. Skipping...",
-            ))?;
+        let entity = registry
+            .get_all(iid)
+            .exactly_one()
+            .map_err(|e| match e.count() {
+                0 => SystemParamValidationError::skipped::<Self>("Iid not found. Skipping..."),
+                _ => SystemParamValidationError::skipped::<Self>(
+                    "Iid found under more than one root. Skipping...",
+                ),
+            })?;
 
         query
             .contains(entity)
@@ -142,3 +148,235 @@ unsafe impl<'a, 'b, const IID: u128, D: ReadOnlyQueryData + 'static, F: QueryFil
     ReadOnlySystemParam for SingleByIid<'a, 'b, IID, D, F>
 {
 }
+
+/// Like [SingleByIid], but for [Iid]s only known at runtime, such as those read from fields or
+/// save files.
+///
+/// Iids spawned under more than one root are reported as [IidError::Ambiguous], unless looked up
+/// in a given root with [QueryByIid::get_in_root] or [QueryByIid::get_mut_in_root].
+#[derive(SystemParam)]
+pub struct QueryByIid<'w, 's, D: QueryData + 'static, F: QueryFilter + 'static = ()> {
+    inner_query: Query<'w, 's, D, F>,
+    registry: Res<'w, IidRegistry>,
+}
+
+impl<'s, D: QueryData + 'static, F: QueryFilter + 'static> QueryByIid<'_, 's, D, F> {
+    pub fn entity(&self, iid: Iid) -> Result<Entity, IidError> {
+        self.registry
+            .get_all(iid)
+            .exactly_one()
+            .map_err(|e| match e.count() {
+                0 => IidError::NotFound(iid),
+                _ => IidError::Ambiguous(iid),
+            })
+    }
+
+    pub fn get(&self, iid: Iid) -> Result<ROQueryItem<'_, 's, D>, IidError> {
+        let entity = self.entity(iid)?;
+        self.inner_query
+            .get(entity)
+            .map_err(|error| IidError::Query { iid, error })
+    }
+
+    pub fn get_mut(&mut self, iid: Iid) -> Result<D::Item<'_, 's>, IidError> {
+        let entity = self.entity(iid)?;
+        self.inner_query
+            .get_mut(entity)
+            .map_err(|error| IidError::Query { iid, error })
+    }
+
+    /// The entity with this [Iid] in the root of `root`, which is either a root itself or any
+    /// entity spawned under one.
+    pub fn entity_in_root(&self, root: Entity, iid: Iid) -> Result<Entity, IidError> {
+        self.registry
+            .get_in_root_of(root, iid)
+            .ok_or(IidError::NotFound(iid))
+    }
+
+    pub fn get_in_root(&self, root: Entity, iid: Iid) -> Result<ROQueryItem<'_, 's, D>, IidError> {
+        let entity = self.entity_in_root(root, iid)?;
+        self.inner_query
+            .get(entity)
+            .map_err(|error| IidError::Query { iid, error })
+    }
+
+    pub fn get_mut_in_root(&mut self, root: Entity, iid: Iid) -> Result<D::Item<'_, 's>, IidError> {
+        let entity = self.entity_in_root(root, iid)?;
+        self.inner_query
+            .get_mut(entity)
+            .map_err(|error| IidError::Query { iid, error })
+    }
+
+    pub fn get_many<const N: usize>(
+        &self,
+        iids: [Iid; N],
+    ) -> Result<[ROQueryItem<'_, 's, D>; N], IidError> {
+        let mut entities = [Entity::PLACEHOLDER; N];
+        for (entity, iid) in entities.iter_mut().zip(iids) {
+            *entity = self.entity(iid)?;
+            if let Err(error) = self.inner_query.get(*entity) {
+                return Err(IidError::Query { iid, error });
+            }
+        }
+
+        Ok(self
+            .inner_query
+            .get_many(entities)
+            .expect("Entities were checked against the query above!"))
+    }
+
+    pub fn contains(&self, iid: Iid) -> bool {
+        self.entity(iid)
+            .is_ok_and(|entity| self.inner_query.contains(entity))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_app::Update;
+    use bevy_ecs::component::Component;
+    use bevy_ecs::hierarchy::ChildOf;
+    use bevy_ecs::resource::Resource;
+    use bevy_ecs::system::{ResMut, RunSystemOnce};
+
+    use crate::component::iid::{IidPlugin, ShieldtankIid};
+    use crate::component::project::LdtkProject;
+    use crate::test::test_app;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Component)]
+    struct Health(i32);
+
+    struct Iids {
+        app: bevy_app::App,
+        roots: [Entity; 2],
+    }
+
+    /// Two roots, each with Iid 2 as a child with [Health]. The first root also has Iid 3, without
+    /// [Health], and Iid 4.
+    fn iids() -> Iids {
+        let mut app = test_app();
+        app.add_plugins(IidPlugin);
+
+        let roots = [1, 2].map(|health| {
+            let root = app
+                .world_mut()
+                .spawn((
+                    LdtkProject::default(),
+                    ShieldtankIid::new(Iid::from_u128(1)),
+                ))
+                .id();
+            app.world_mut().spawn((
+                ShieldtankIid::new(Iid::from_u128(2)),
+                Health(health),
+                ChildOf(root),
+            ));
+            root
+        });
+
+        app.world_mut()
+            .spawn((ShieldtankIid::new(Iid::from_u128(3)), ChildOf(roots[0])));
+        app.world_mut().spawn((
+            ShieldtankIid::new(Iid::from_u128(4)),
+            Health(4),
+            ChildOf(roots[0]),
+        ));
+        app.update();
+
+        Iids { app, roots }
+    }
+
+    #[test]
+    fn gets_unique_iids() {
+        let Iids { mut app, .. } = iids();
+
+        app.world_mut()
+            .run_system_once(|mut query: QueryByIid<&mut Health>| {
+                query.get_mut(Iid::from_u128(4)).unwrap().0 += 1;
+
+                assert_eq!(query.get(Iid::from_u128(4)).unwrap(), &Health(5));
+                assert!(query.contains(Iid::from_u128(4)));
+                assert!(!query.contains(Iid::from_u128(3)));
+                assert!(matches!(
+                    query.get(Iid::from_u128(3)),
+                    Err(IidError::Query { .. })
+                ));
+                assert!(matches!(
+                    query.get(Iid::from_u128(5)),
+                    Err(IidError::NotFound(_))
+                ));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn iids_under_several_roots_are_looked_up_in_a_root() {
+        let Iids { mut app, roots } = iids();
+
+        app.world_mut()
+            .run_system_once(move |query: QueryByIid<&Health>| {
+                let iid = Iid::from_u128(2);
+
+                assert!(matches!(query.get(iid), Err(IidError::Ambiguous(_))));
+                assert!(!query.contains(iid));
+                assert_eq!(query.get_in_root(roots[0], iid).unwrap(), &Health(1));
+                assert_eq!(query.get_in_root(roots[1], iid).unwrap(), &Health(2));
+
+                // Any entity under a root looks up within it.
+                let child = query.entity_in_root(roots[1], iid).unwrap();
+                assert_eq!(query.entity_in_root(child, iid).unwrap(), child);
+                assert!(matches!(
+                    query.entity_in_root(roots[1], Iid::from_u128(4)),
+                    Err(IidError::NotFound(_))
+                ));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn gets_many_iids_at_once() {
+        let Iids { mut app, roots } = iids();
+
+        app.world_mut().entity_mut(roots[1]).despawn();
+        app.update();
+
+        app.world_mut()
+            .run_system_once(|query: QueryByIid<&Health>| {
+                assert_eq!(
+                    query
+                        .get_many([Iid::from_u128(4), Iid::from_u128(2)])
+                        .unwrap(),
+                    [&Health(4), &Health(1)]
+                );
+                assert!(matches!(
+                    query.get_many([Iid::from_u128(4), Iid::from_u128(3)]),
+                    Err(IidError::Query { .. })
+                ));
+            })
+            .unwrap();
+    }
+
+    #[derive(Default, Resource)]
+    struct Seen(Vec<i32>);
+
+    fn single_by_iid_system(health: SingleByIid<2, &Health>, mut seen: ResMut<Seen>) {
+        seen.0.push(health.0);
+    }
+
+    #[test]
+    fn single_by_iid_skips_unless_the_iid_is_unique() {
+        let Iids { mut app, roots } = iids();
+        app.init_resource::<Seen>();
+        app.add_systems(Update, single_by_iid_system);
+
+        app.update();
+        assert!(app.world().resource::<Seen>().0.is_empty());
+
+        app.world_mut().entity_mut(roots[0]).despawn();
+        // The registry only sees the despawn in `PostUpdate`, after the system was skipped again.
+        app.update();
+        app.update();
+        assert_eq!(app.world().resource::<Seen>().0, vec![2]);
+    }
+}
@@ -9,17 +9,34 @@ use bevy_transform::components::GlobalTransform;
 use crate::component::grid_values::{ShieldtankGridValue, ShieldtankGridValues};
 use crate::component::layer::ShieldtankLayer;
 use crate::component::world_bounds::ShieldtankWorldBounds;
+use crate::result::ShieldtankResult;
+
+use super::layer_grid::LayerGridQuery;
 
 #[derive(QueryData)]
 pub struct GridValueQueryData {
+    entity: Entity,
     global_transform: &'static GlobalTransform,
     global_bounds: &'static ShieldtankWorldBounds,
     grid_values: &'static ShieldtankGridValues,
 }
 
+/// Where a ray through an IntGrid layer enters a matching cell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridRayHit {
+    pub layer: Entity,
+    pub grid: I64Vec2,
+    /// Distance along the ray, or `0.0` when the ray starts in the cell.
+    pub distance: f32,
+    pub point: Vec2,
+    /// The normal of the face the ray entered through, or zero when it starts in the cell.
+    pub normal: Vec2,
+}
+
 #[derive(SystemParam)]
 pub struct GridValueQuery<'w, 's> {
     query: Query<'w, 's, GridValueQueryData, With<ShieldtankLayer>>,
+    layer_grids: LayerGridQuery<'w, 's>,
 }
 
 impl GridValueQuery<'_, '_> {
@@ -28,11 +45,11 @@ impl GridValueQuery<'_, '_> {
             .query
             .iter()
             .filter(|data| data.global_bounds.contains(location))
-            .map(|data| (data.global_transform, data.grid_values))
+            .map(|data| (data.entity, data.global_transform, data.grid_values))
             .collect::<Vec<_>>();
 
         // sort with highest Z first
-        layers.sort_by(|(global_transform_a, ..), (global_transform_b, ..)| {
+        layers.sort_by(|(_, global_transform_a, _), (_, global_transform_b, _)| {
             global_transform_b
                 .translation()
                 .z
@@ -40,14 +57,10 @@ impl GridValueQuery<'_, '_> {
                 .unwrap_or(Ordering::Less)
         });
 
-        layers
-            .into_iter()
-            .find_map(|(global_transform, grid_values)| {
-                let local_location = location - global_transform.translation().truncate();
-                let local_location = I64Vec2::new(1, -1) * local_location.as_i64vec2() / 16;
-
-                grid_values.get(local_location)
-            })
+        layers.into_iter().find_map(|(layer, _, grid_values)| {
+            let grid = self.layer_grids.world_to_grid(layer, location)?;
+            grid_values.get(grid)
+        })
     }
 
     pub fn identifier_at(&self, location: Vec2) -> Option<&str> {
@@ -56,20 +69,74 @@ impl GridValueQuery<'_, '_> {
         Some(identifier.as_str())
     }
 
+    /// The first cell of `layer` along the ray whose value matches `predicate`.
+    pub fn raycast(
+        &self,
+        layer: Entity,
+        origin: Vec2,
+        direction: Vec2,
+        max_distance: f32,
+        predicate: impl Fn(&ShieldtankGridValue) -> bool,
+    ) -> Option<(GridRayHit, &ShieldtankGridValue)> {
+        let data = self.query.get(layer).ok()?;
+        let layer_grid = self.layer_grids.get(layer)?;
+        let direction = direction.normalize_or_zero();
+
+        layer_grid
+            .cells_along(origin, direction, max_distance)
+            .find_map(|(grid, distance, normal)| {
+                let grid_value = data
+                    .grid_values
+                    .get(grid)
+                    .filter(|grid_value| predicate(grid_value))?;
+
+                let hit = GridRayHit {
+                    layer,
+                    grid,
+                    distance,
+                    point: origin + direction * distance,
+                    normal,
+                };
+
+                Some((hit, grid_value))
+            })
+    }
+
+    /// Like [GridValueQuery::raycast], from `start` to `end`.
+    pub fn raycast_to(
+        &self,
+        layer: Entity,
+        start: Vec2,
+        end: Vec2,
+        predicate: impl Fn(&ShieldtankGridValue) -> bool,
+    ) -> Option<(GridRayHit, &ShieldtankGridValue)> {
+        let delta = end - start;
+        self.raycast(layer, start, delta, delta.length(), predicate)
+    }
+
+    /// Whether no cell of `layer` between `start` and `end` matches `blocks`.
+    pub fn line_of_sight(
+        &self,
+        layer: Entity,
+        start: Vec2,
+        end: Vec2,
+        blocks: impl Fn(&ShieldtankGridValue) -> bool,
+    ) -> bool {
+        self.raycast_to(layer, start, end, blocks).is_none()
+    }
+
     pub fn enumerate_layer(
         &self,
         layer: Entity,
-    ) -> impl Iterator<Item = (Rect, &ShieldtankGridValue)> {
-        let Ok(data) = self.query.get(layer) else {
-            todo!()
-        };
+    ) -> ShieldtankResult<impl Iterator<Item = (Rect, &ShieldtankGridValue)>> {
+        let data = self.query.get(layer)?;
 
         let grid_cell_size = data.grid_values.grid_cell_size();
         let size = Vec2::new(1.0, -1.0) * Vec2::splat(grid_cell_size);
 
         let global_offset = data.global_transform.translation().truncate();
 
-        data.grid_values.enumerate().map(move |(index, value)| {
+        Ok(data.grid_values.enumerate().map(move |(index, value)| {
             let x = index.x as f32 * grid_cell_size;
             let y = index.y as f32 * grid_cell_size;
 
@@ -78,6 +145,119 @@ impl GridValueQuery<'_, '_> {
             let rect = Rect::from_corners(corner, corner + size);
 
             (rect, value)
-        })
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_asset::Assets;
+    use bevy_ecs::system::RunSystemOnce;
+    use bevy_ldtk_asset::layer::LayerInstance;
+
+    use crate::component::shieldtank_component::ShieldtankComponent;
+    use crate::test::{test_app, tiles_layer};
+
+    use super::*;
+
+    /// A 4 by 2 layer of 16 pixel cells, with its top left corner at the world origin, and a wall
+    /// at `(1, 0)`.
+    fn walled_layer() -> (bevy_app::App, Entity) {
+        let mut app = test_app();
+
+        let layer = tiles_layer("Walls", I64Vec2::new(4, 2), 16, vec![], Default::default());
+        let layer = app
+            .world_mut()
+            .resource_mut::<Assets<LayerInstance>>()
+            .add(layer);
+
+        let layer = app
+            .world_mut()
+            .spawn((
+                ShieldtankLayer::new(layer),
+                GlobalTransform::default(),
+                ShieldtankWorldBounds::from(Rect::new(0.0, -32.0, 64.0, 0.0)),
+                ShieldtankGridValues::from_rows(&[[0, 1, 0, 0], [0, 0, 0, 0]]),
+            ))
+            .id();
+
+        (app, layer)
+    }
+
+    fn is_wall(grid_value: &ShieldtankGridValue) -> bool {
+        grid_value.value == 1
+    }
+
+    #[test]
+    fn raycasts_stop_at_the_first_matching_cell() {
+        let (mut app, layer) = walled_layer();
+
+        let (hit, far, behind) = app
+            .world_mut()
+            .run_system_once(move |query: GridValueQuery| {
+                let hit = query
+                    .raycast(layer, Vec2::new(8.0, -8.0), Vec2::X, 100.0, is_wall)
+                    .map(|(hit, _)| hit);
+                let far = query.raycast(layer, Vec2::new(8.0, -8.0), Vec2::X, 7.0, is_wall);
+                let behind =
+                    query.raycast_to(layer, Vec2::new(40.0, -8.0), Vec2::new(56.0, -8.0), is_wall);
+                (hit, far.is_some(), behind.is_some())
+            })
+            .unwrap();
+
+        assert_eq!(
+            hit,
+            Some(GridRayHit {
+                layer,
+                grid: I64Vec2::new(1, 0),
+                distance: 8.0,
+                point: Vec2::new(16.0, -8.0),
+                normal: Vec2::NEG_X,
+            })
+        );
+        assert!(!far);
+        assert!(!behind);
+    }
+
+    #[test]
+    fn line_of_sight_is_blocked_by_matching_cells() {
+        let (mut app, layer) = walled_layer();
+
+        let (blocked, clear, diagonal, from_inside) = app
+            .world_mut()
+            .run_system_once(move |query: GridValueQuery| {
+                (
+                    query.line_of_sight(
+                        layer,
+                        Vec2::new(8.0, -8.0),
+                        Vec2::new(56.0, -8.0),
+                        is_wall,
+                    ),
+                    query.line_of_sight(
+                        layer,
+                        Vec2::new(8.0, -24.0),
+                        Vec2::new(56.0, -24.0),
+                        is_wall,
+                    ),
+                    query.line_of_sight(
+                        layer,
+                        Vec2::new(8.0, -24.0),
+                        Vec2::new(56.0, -12.0),
+                        is_wall,
+                    ),
+                    query.line_of_sight(
+                        layer,
+                        Vec2::new(24.0, -8.0),
+                        Vec2::new(24.0, -24.0),
+                        is_wall,
+                    ),
+                )
+            })
+            .unwrap();
+
+        assert!(!blocked);
+        assert!(clear);
+        assert!(diagonal);
+        assert!(!from_inside);
     }
 }
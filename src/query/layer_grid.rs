@@ -0,0 +1,342 @@
+use bevy_asset::{AsAssetId, Assets};
+use bevy_ecs::entity::Entity;
+use bevy_ecs::system::{Query, Res, SystemParam};
+use bevy_ldtk_asset::layer::LayerInstance;
+use bevy_math::{I64Vec2, Rect, Vec2};
+use bevy_transform::components::GlobalTransform;
+
+use crate::component::layer::ShieldtankLayer;
+
+/// The grid of a spawned layer, for converting between world positions, level and layer pixels,
+/// and grid cells.
+///
+/// Pixels and cells follow LDtk, with `(0, 0)` at the top left and y pointing down. World
+/// positions have y pointing up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayerGrid {
+    /// The world position of the layer's top left corner.
+    pub origin: Vec2,
+    /// The layer's pixel offset within its level.
+    pub level_offset: Vec2,
+    pub grid_cell_size: f32,
+    pub size: I64Vec2,
+}
+
+const FLIP_Y: Vec2 = Vec2::new(1.0, -1.0);
+
+impl LayerGrid {
+    pub fn world_to_layer_px(&self, world: Vec2) -> Vec2 {
+        (world - self.origin) * FLIP_Y
+    }
+
+    pub fn layer_px_to_world(&self, layer_px: Vec2) -> Vec2 {
+        self.origin + layer_px * FLIP_Y
+    }
+
+    pub fn layer_px_to_level_px(&self, layer_px: Vec2) -> Vec2 {
+        layer_px + self.level_offset
+    }
+
+    pub fn level_px_to_layer_px(&self, level_px: Vec2) -> Vec2 {
+        level_px - self.level_offset
+    }
+
+    pub fn world_to_level_px(&self, world: Vec2) -> Vec2 {
+        self.layer_px_to_level_px(self.world_to_layer_px(world))
+    }
+
+    pub fn level_px_to_world(&self, level_px: Vec2) -> Vec2 {
+        self.layer_px_to_world(self.level_px_to_layer_px(level_px))
+    }
+
+    pub fn layer_px_to_grid(&self, layer_px: Vec2) -> I64Vec2 {
+        (layer_px / self.grid_cell_size).floor().as_i64vec2()
+    }
+
+    /// The cell under a world position. It may lie outside the layer, see [LayerGrid::contains].
+    pub fn world_to_grid(&self, world: Vec2) -> I64Vec2 {
+        self.layer_px_to_grid(self.world_to_layer_px(world))
+    }
+
+    /// The layer pixel at the top left corner of a cell.
+    pub fn grid_to_layer_px(&self, grid: I64Vec2) -> Vec2 {
+        grid.as_vec2() * self.grid_cell_size
+    }
+
+    /// The world position of the center of a cell.
+    pub fn grid_to_world(&self, grid: I64Vec2) -> Vec2 {
+        self.layer_px_to_world((grid.as_vec2() + 0.5) * self.grid_cell_size)
+    }
+
+    pub fn grid_rect(&self, grid: I64Vec2) -> Rect {
+        let corner = self.layer_px_to_world(self.grid_to_layer_px(grid));
+        Rect::from_corners(corner, corner + FLIP_Y * self.grid_cell_size)
+    }
+
+    pub fn contains(&self, grid: I64Vec2) -> bool {
+        grid.cmpge(I64Vec2::ZERO).all() && grid.cmplt(self.size).all()
+    }
+
+    /// The cells crossed by a ray, in order, as DDA visits them.
+    ///
+    /// Yields each cell with the distance at which the ray enters it, and the world normal of the
+    /// face it enters through. The starting cell is at distance `0.0` with a zero normal. Stops
+    /// past `max_distance`, or once the ray has left the layer for good.
+    pub fn cells_along(
+        &self,
+        origin: Vec2,
+        direction: Vec2,
+        max_distance: f32,
+    ) -> impl Iterator<Item = (I64Vec2, f32, Vec2)> {
+        let layer_grid = *self;
+        let origin = self.world_to_layer_px(origin);
+        let direction = direction.normalize_or_zero() * FLIP_Y;
+
+        let mut cell = self.layer_px_to_grid(origin);
+        let sign = |v: f32| match v {
+            v if v > 0.0 => 1,
+            v if v < 0.0 => -1,
+            _ => 0,
+        };
+        let step = I64Vec2::new(sign(direction.x), sign(direction.y));
+
+        let t_delta = self.grid_cell_size / direction.abs();
+        let next_boundary = (cell + step.max(I64Vec2::ZERO)).as_vec2() * self.grid_cell_size;
+        let mut t_max = Vec2::select(
+            direction.cmpne(Vec2::ZERO),
+            (next_boundary - origin) / direction,
+            Vec2::INFINITY,
+        );
+
+        let mut started = false;
+
+        std::iter::from_fn(move || {
+            if direction == Vec2::ZERO {
+                return None;
+            }
+
+            if !started {
+                started = true;
+                return Some((cell, 0.0, Vec2::ZERO));
+            }
+
+            let leaving = |cell: i64, step: i64, size: i64| {
+                (cell < 0 && step <= 0) || (cell >= size && step >= 0)
+            };
+
+            if leaving(cell.x, step.x, layer_grid.size.x)
+                || leaving(cell.y, step.y, layer_grid.size.y)
+            {
+                return None;
+            }
+
+            let (distance, normal) = match t_max.x < t_max.y {
+                true => {
+                    cell.x += step.x;
+                    let distance = t_max.x;
+                    t_max.x += t_delta.x;
+                    (distance, Vec2::new(-step.x as f32, 0.0))
+                }
+                false => {
+                    cell.y += step.y;
+                    let distance = t_max.y;
+                    t_max.y += t_delta.y;
+                    (distance, Vec2::new(0.0, -step.y as f32))
+                }
+            };
+
+            (distance <= max_distance).then_some((cell, distance, normal * FLIP_Y))
+        })
+    }
+}
+
+/// Builds the [LayerGrid] of spawned layers.
+#[derive(SystemParam)]
+pub struct LayerGridQuery<'w, 's> {
+    layer_query: Query<'w, 's, (&'static ShieldtankLayer, &'static GlobalTransform)>,
+    layer_assets: Res<'w, Assets<LayerInstance>>,
+}
+
+impl LayerGridQuery<'_, '_> {
+    pub fn get(&self, layer: Entity) -> Option<LayerGrid> {
+        let (component, global_transform) = self.layer_query.get(layer).ok()?;
+        let asset = self.layer_assets.get(component.as_asset_id())?;
+
+        Some(LayerGrid {
+            origin: global_transform.translation().truncate(),
+            level_offset: asset.location.as_vec2(),
+            grid_cell_size: asset.grid_cell_size as f32,
+            size: asset.grid_size,
+        })
+    }
+
+    /// The layer cell under a world position, if it's within the layer.
+    pub fn world_to_grid(&self, layer: Entity, world: Vec2) -> Option<I64Vec2> {
+        let layer_grid = self.get(layer)?;
+        let grid = layer_grid.world_to_grid(world);
+        layer_grid.contains(grid).then_some(grid)
+    }
+
+    pub fn grid_to_world(&self, layer: Entity, grid: I64Vec2) -> Option<Vec2> {
+        Some(self.get(layer)?.grid_to_world(grid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 4 by 2 layer of 16 pixel cells, with its top left corner at `(100, 50)` in the world, and
+    /// offset by `(8, 16)` in its level.
+    fn layer_grid() -> LayerGrid {
+        LayerGrid {
+            origin: Vec2::new(100.0, 50.0),
+            level_offset: Vec2::new(8.0, 16.0),
+            grid_cell_size: 16.0,
+            size: I64Vec2::new(4, 2),
+        }
+    }
+
+    #[test]
+    fn converts_between_world_and_pixels() {
+        let layer_grid = layer_grid();
+        let world = Vec2::new(116.0, 34.0);
+
+        assert_eq!(layer_grid.world_to_layer_px(world), Vec2::new(16.0, 16.0));
+        assert_eq!(layer_grid.world_to_level_px(world), Vec2::new(24.0, 32.0));
+        assert_eq!(layer_grid.layer_px_to_world(Vec2::new(16.0, 16.0)), world);
+        assert_eq!(layer_grid.level_px_to_world(Vec2::new(24.0, 32.0)), world);
+    }
+
+    #[test]
+    fn converts_between_world_and_grid() {
+        let layer_grid = layer_grid();
+
+        assert_eq!(
+            layer_grid.world_to_grid(Vec2::new(100.0, 50.0)),
+            I64Vec2::ZERO
+        );
+        assert_eq!(
+            layer_grid.world_to_grid(Vec2::new(116.0, 34.0)),
+            I64Vec2::new(1, 1)
+        );
+        assert_eq!(
+            layer_grid.world_to_grid(Vec2::new(99.0, 51.0)),
+            I64Vec2::new(-1, -1)
+        );
+        assert_eq!(
+            layer_grid.grid_to_world(I64Vec2::new(1, 1)),
+            Vec2::new(124.0, 26.0)
+        );
+        assert_eq!(
+            layer_grid.grid_rect(I64Vec2::new(1, 0)),
+            Rect::new(116.0, 34.0, 132.0, 50.0)
+        );
+
+        for y in 0..2 {
+            for x in 0..4 {
+                let grid = I64Vec2::new(x, y);
+                assert_eq!(
+                    layer_grid.world_to_grid(layer_grid.grid_to_world(grid)),
+                    grid
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn contains_only_cells_of_the_layer() {
+        let layer_grid = layer_grid();
+
+        assert!(layer_grid.contains(I64Vec2::ZERO));
+        assert!(layer_grid.contains(I64Vec2::new(3, 1)));
+        assert!(!layer_grid.contains(I64Vec2::new(4, 1)));
+        assert!(!layer_grid.contains(I64Vec2::new(3, 2)));
+        assert!(!layer_grid.contains(I64Vec2::new(-1, 0)));
+    }
+
+    /// The cells along a ray which lie within the layer.
+    fn cells_in_layer(
+        layer_grid: &LayerGrid,
+        origin: Vec2,
+        direction: Vec2,
+        max_distance: f32,
+    ) -> Vec<(I64Vec2, f32, Vec2)> {
+        layer_grid
+            .cells_along(origin, direction, max_distance)
+            .take_while(|(grid, _, _)| layer_grid.contains(*grid))
+            .collect()
+    }
+
+    #[test]
+    fn cells_along_a_row() {
+        let layer_grid = layer_grid();
+        let origin = layer_grid.grid_to_world(I64Vec2::ZERO);
+
+        assert_eq!(
+            cells_in_layer(&layer_grid, origin, Vec2::X, 100.0),
+            vec![
+                (I64Vec2::new(0, 0), 0.0, Vec2::ZERO),
+                (I64Vec2::new(1, 0), 8.0, Vec2::NEG_X),
+                (I64Vec2::new(2, 0), 24.0, Vec2::NEG_X),
+                (I64Vec2::new(3, 0), 40.0, Vec2::NEG_X),
+            ]
+        );
+        assert_eq!(cells_in_layer(&layer_grid, origin, Vec2::X, 30.0).len(), 3);
+    }
+
+    #[test]
+    fn cells_along_a_column_have_world_normals() {
+        let layer_grid = layer_grid();
+        let origin = layer_grid.grid_to_world(I64Vec2::new(2, 0));
+
+        // Down in the world is along +y in the layer, entering through the cell's top face.
+        assert_eq!(
+            cells_in_layer(&layer_grid, origin, Vec2::NEG_Y, 100.0),
+            vec![
+                (I64Vec2::new(2, 0), 0.0, Vec2::ZERO),
+                (I64Vec2::new(2, 1), 8.0, Vec2::Y),
+            ]
+        );
+    }
+
+    #[test]
+    fn cells_along_a_diagonal_are_adjacent() {
+        let layer_grid = layer_grid();
+        let origin = layer_grid.grid_to_world(I64Vec2::ZERO) + Vec2::new(-4.0, 4.0);
+        let cells = cells_in_layer(&layer_grid, origin, Vec2::new(2.0, -1.0), 100.0);
+
+        assert_eq!(cells.first().map(|(grid, _, _)| *grid), Some(I64Vec2::ZERO));
+        assert_eq!(
+            cells.last().map(|(grid, _, _)| *grid),
+            Some(I64Vec2::new(3, 1))
+        );
+        cells.windows(2).for_each(|pair| {
+            let (from, from_distance, _) = pair[0];
+            let (to, to_distance, normal) = pair[1];
+            let step = to - from;
+
+            assert_eq!(step.x.abs() + step.y.abs(), 1, "{from} to {to}");
+            assert!(to_distance >= from_distance);
+            assert_eq!(normal.length(), 1.0);
+        });
+    }
+
+    #[test]
+    fn cells_along_nothing() {
+        let layer_grid = layer_grid();
+        let origin = layer_grid.grid_to_world(I64Vec2::ZERO);
+
+        assert_eq!(layer_grid.cells_along(origin, Vec2::ZERO, 100.0).count(), 0);
+
+        // Starting left of the layer and moving away from it.
+        let outside = origin - Vec2::new(32.0, 0.0);
+        assert_eq!(
+            layer_grid
+                .cells_along(outside, Vec2::NEG_X, 100.0)
+                .map(|(grid, _, _)| grid)
+                .collect::<Vec<_>>(),
+            vec![I64Vec2::new(-2, 0)]
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::VecDeque;
+
+use bevy_asset::{AsAssetId, Assets};
+use bevy_ecs::entity::Entity;
+use bevy_ecs::hierarchy::ChildOf;
+use bevy_ecs::system::{Query, Res, SystemParam};
+use bevy_ldtk_asset::iid::Iid;
+use bevy_ldtk_asset::level::{Level as LevelAsset, NeighbourDir};
+use bevy_ldtk_asset::prelude::LdtkAsset;
+use bevy_ldtk_asset::world::{World as WorldAsset, WorldLayout};
+use bevy_math::I64Vec2;
+use bevy_platform::collections::HashSet;
+
+use crate::component::iid::IidRegistry;
+use crate::component::level::ShieldtankLevel;
+use crate::component::world::ShieldtankWorld;
+
+/// A copy of a [NeighbourDir], which is neither `Clone` nor `Copy`.
+pub fn copy_neighbour_dir(dir: &NeighbourDir) -> NeighbourDir {
+    match dir {
+        NeighbourDir::North => NeighbourDir::North,
+        NeighbourDir::South => NeighbourDir::South,
+        NeighbourDir::East => NeighbourDir::East,
+        NeighbourDir::West => NeighbourDir::West,
+        NeighbourDir::Lower => NeighbourDir::Lower,
+        NeighbourDir::Greater => NeighbourDir::Greater,
+        NeighbourDir::Overlap => NeighbourDir::Overlap,
+        NeighbourDir::NorthWest => NeighbourDir::NorthWest,
+        NeighbourDir::NorthEast => NeighbourDir::NorthEast,
+        NeighbourDir::SouthWest => NeighbourDir::SouthWest,
+        NeighbourDir::SouthEast => NeighbourDir::SouthEast,
+    }
+}
+
+/// Whether two [NeighbourDir]s are the same direction, since it isn't `PartialEq`.
+pub fn same_neighbour_dir(a: &NeighbourDir, b: &NeighbourDir) -> bool {
+    std::mem::discriminant(a) == std::mem::discriminant(b)
+}
+
+/// Neighbours and world layout of spawned levels.
+///
+/// Neighbours are reported by [Iid], since they don't need to be spawned. Use
+/// [LevelNeighbours::level_entity] to find the entity of a spawned one.
+#[derive(SystemParam)]
+pub struct LevelNeighbours<'w, 's> {
+    level_query: Query<'w, 's, (&'static ShieldtankLevel, Option<&'static ChildOf>)>,
+    world_query: Query<'w, 's, &'static ShieldtankWorld>,
+    iid_registry: Res<'w, IidRegistry>,
+    level_assets: Res<'w, Assets<LevelAsset>>,
+    world_assets: Res<'w, Assets<WorldAsset>>,
+}
+
+impl LevelNeighbours<'_, '_> {
+    fn level_asset(&self, level: Entity) -> Option<&LevelAsset> {
+        let (component, _) = self.level_query.get(level).ok()?;
+        self.level_assets.get(component.as_asset_id())
+    }
+
+    /// The world asset of the world entity `level` was spawned under. Levels spawned on their
+    /// own have none.
+    fn world_of(&self, level: Entity) -> Option<&WorldAsset> {
+        let (_, child_of) = self.level_query.get(level).ok()?;
+        let component = self.world_query.get(child_of?.parent()).ok()?;
+        self.world_assets.get(component.as_asset_id())
+    }
+
+    fn level_asset_in(&self, world: Option<&WorldAsset>, level_iid: Iid) -> Option<&LevelAsset> {
+        let level_handle = world?.levels.get(&level_iid)?;
+        self.level_assets.get(level_handle.id())
+    }
+
+    pub fn level_iid(&self, level: Entity) -> Option<Iid> {
+        self.level_asset(level).map(LdtkAsset::get_iid)
+    }
+
+    /// The level with this [Iid] spawned in the same root as `level`, if any.
+    pub fn level_entity(&self, level: Entity, level_iid: Iid) -> Option<Entity> {
+        self.iid_registry
+            .get_in_root_of(level, level_iid)
+            .filter(|entity| self.level_query.contains(*entity))
+    }
+
+    pub fn neighbours(&self, level: Entity) -> impl Iterator<Item = (&NeighbourDir, Iid)> {
+        self.level_asset(level)
+            .into_iter()
+            .flat_map(|asset| asset.neighbours.iter())
+            .map(|neighbour| (&neighbour.dir, neighbour.level_iid))
+    }
+
+    pub fn neighbours_in(&self, level: Entity, dir: NeighbourDir) -> impl Iterator<Item = Iid> {
+        self.neighbours(level)
+            .filter(move |(neighbour_dir, _)| same_neighbour_dir(neighbour_dir, &dir))
+            .map(|(_, level_iid)| level_iid)
+    }
+
+    /// Like [LevelNeighbours::neighbours_in], but only the neighbours which are spawned in the
+    /// same root as `level`.
+    pub fn neighbour_entities_in(
+        &self,
+        level: Entity,
+        dir: NeighbourDir,
+    ) -> impl Iterator<Item = Entity> {
+        self.neighbours_in(level, dir)
+            .filter_map(move |level_iid| self.level_entity(level, level_iid))
+    }
+
+    pub fn world_layout(&self, level: Entity) -> Option<&WorldLayout> {
+        Some(&self.world_of(level)?.world_layout)
+    }
+
+    /// For GridVania worlds, the cell of the world grid holding the level's top left corner.
+    pub fn grid_coords(&self, level: Entity) -> Option<I64Vec2> {
+        let WorldLayout::GridVania(cell_size) = self.world_layout(level)? else {
+            return None;
+        };
+
+        let asset = self.level_asset(level)?;
+
+        Some(asset.location.div_euclid(*cell_size))
+    }
+
+    /// For GridVania worlds, the level covering the given cell of the world grid.
+    pub fn level_at_grid(&self, world: Entity, grid: I64Vec2) -> Option<Iid> {
+        let component = self.world_query.get(world).ok()?;
+        let world_asset = self.world_assets.get(component.as_asset_id())?;
+
+        let WorldLayout::GridVania(cell_size) = world_asset.world_layout else {
+            return None;
+        };
+
+        let location = grid * cell_size;
+
+        world_asset
+            .levels
+            .values()
+            .filter_map(|level_handle| self.level_assets.get(level_handle.id()))
+            .find(|asset| {
+                let min = asset.location;
+                let max = asset.location + asset.size;
+                location.cmpge(min).all() && location.cmplt(max).all()
+            })
+            .map(LdtkAsset::get_iid)
+    }
+
+    /// Walks the neighbour graph breadth first, starting with `level` itself at distance `0`.
+    ///
+    /// Only follows neighbours in the directions accepted by `follow`. Neighbours are looked up
+    /// in the world `level` was spawned under, so a level spawned on its own only reaches its
+    /// direct neighbours.
+    pub fn walk(&self, level: Entity, follow: impl Fn(&NeighbourDir) -> bool) -> Vec<(Iid, usize)> {
+        let Some(start) = self.level_asset(level) else {
+            return vec![];
+        };
+
+        let world = self.world_of(level);
+
+        let start_iid = start.get_iid();
+
+        let mut visited: HashSet<Iid> = HashSet::from_iter([start_iid]);
+        let mut queue: VecDeque<(Iid, usize)> = VecDeque::from([(start_iid, 0)]);
+        let mut walked = vec![];
+
+        while let Some((level_iid, distance)) = queue.pop_front() {
+            walked.push((level_iid, distance));
+
+            let asset = match level_iid == start_iid {
+                true => Some(start),
+                false => self.level_asset_in(world, level_iid),
+            };
+
+            asset
+                .into_iter()
+                .flat_map(|asset| asset.neighbours.iter())
+                .filter(|neighbour| follow(&neighbour.dir))
+                .for_each(|neighbour| {
+                    if visited.insert(neighbour.level_iid) {
+                        queue.push_back((neighbour.level_iid, distance + 1));
+                    }
+                });
+        }
+
+        walked
+    }
+}
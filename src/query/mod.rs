@@ -1,4 +1,6 @@
 pub mod by_global_bounds;
 pub mod by_iid;
 pub mod grid_value;
+pub mod layer_grid;
+pub mod level_neighbours;
 pub mod location;
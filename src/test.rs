@@ -3,12 +3,23 @@ fn do_test() {}
 
 use bevy_app::{App, Startup, TaskPoolPlugin};
 use bevy_asset::io::embedded::GetAssetServer as _;
-use bevy_asset::{AssetPlugin, AssetServer, LoadState};
+use bevy_asset::{AssetPlugin, AssetServer, Handle, LoadState};
+use bevy_color::Color;
 use bevy_ecs::system::{Commands, Res};
+use bevy_ldtk_asset::entity::EntityInstance;
+use bevy_ldtk_asset::field_instance::{EntityRef, EnumValue, FieldInstance, FieldInstanceType};
+use bevy_ldtk_asset::iid::Iid;
+use bevy_ldtk_asset::layer::{LayerInstance, LayerType, TilesLayer};
+use bevy_ldtk_asset::layer_definition::{IntGridValue, LayerDefinition, LayerDefinitionType};
+use bevy_ldtk_asset::level::Level as LevelAsset;
 use bevy_ldtk_asset::plugin::BevyLdtkAssetPlugin;
 use bevy_ldtk_asset::world::World as WorldAsset;
+use bevy_math::{DVec2, I64Vec2};
+use bevy_platform::collections::HashMap;
+use bevy_sprite::Anchor;
 
-use crate::component::ShieldtankComponent;
+use crate::component::ldtk_fields::new_enum_value;
+use crate::component::shieldtank_component::ShieldtankComponent;
 use crate::component::world::ShieldtankWorld;
 
 fn spawn_a_world(asset_server: Res<AssetServer>, mut commands: Commands) {
@@ -40,3 +51,138 @@ fn test_world() {
         Some(LoadState::Loaded)
     ));
 }
+
+/// An app with the LDtk asset types, for tests which add their own assets and systems.
+pub(crate) fn test_app() -> App {
+    let mut app = App::new();
+
+    app.add_plugins(TaskPoolPlugin::default());
+    app.add_plugins(AssetPlugin::default());
+    app.add_plugins(BevyLdtkAssetPlugin);
+
+    app
+}
+
+pub(crate) fn int_grid_value(value: i64, identifier: &str) -> IntGridValue {
+    IntGridValue {
+        color: Color::WHITE,
+        group_uid: 0,
+        identifier: Some(identifier.to_string()),
+        tile: None,
+        value,
+    }
+}
+
+pub(crate) fn layer_definition(
+    layer_definition_type: LayerDefinitionType,
+    grid_cell_size: i64,
+    int_grid_values: impl IntoIterator<Item = IntGridValue>,
+) -> LayerDefinition {
+    LayerDefinition {
+        layer_definition_type,
+        auto_source_layer_def_uid: None,
+        display_opacity: 1.0,
+        grid_cell_size,
+        identifier: "Layer".to_string(),
+        int_grid_values: int_grid_values
+            .into_iter()
+            .map(|int_grid_value| (int_grid_value.value, int_grid_value))
+            .collect(),
+        int_grid_values_groups: Default::default(),
+        parallax_factor: DVec2::ZERO,
+        parallax_scaling: false,
+        offset: I64Vec2::ZERO,
+        tileset_definition: None,
+    }
+}
+
+/// A tiles layer as loaded by bevy_ldtk_asset, which loads IntGrid, AutoLayer and Tiles layers
+/// alike as [LayerType::Tiles].
+pub(crate) fn tiles_layer(
+    identifier: &str,
+    grid_size: I64Vec2,
+    grid_cell_size: i64,
+    int_grid: Vec<i64>,
+    layer_definition: Handle<LayerDefinition>,
+) -> LayerInstance {
+    LayerInstance {
+        grid_size,
+        grid_cell_size,
+        identifier: identifier.to_string(),
+        opacity: 1.0,
+        iid: Iid::nil(),
+        level_id: 0,
+        location: I64Vec2::ZERO,
+        layer_type: LayerType::Tiles(TilesLayer {
+            int_grid,
+            tiles: vec![],
+            tileset_definition: None,
+            tileset_image: None,
+        }),
+        layer_definition,
+        index: 0,
+    }
+}
+
+pub(crate) fn field_instance(field_instance_type: FieldInstanceType) -> FieldInstance {
+    FieldInstance {
+        tileset_rectangle: None,
+        field_instance_type,
+        def_uid: 0,
+    }
+}
+
+/// An enum value without an enum definition.
+pub(crate) fn enum_value(value: &str) -> EnumValue {
+    new_enum_value(value, Handle::default()).expect("EnumValue from its reflected fields")
+}
+
+pub(crate) fn entity_ref(entity_iid: u128, level_iid: u128) -> EntityRef {
+    EntityRef {
+        entity_iid: Iid::from_u128(entity_iid),
+        layer_iid: Iid::nil(),
+        level_iid: Iid::from_u128(level_iid),
+        world_iid: Iid::nil(),
+    }
+}
+
+/// A level without layers or fields, at `location` in LDtk's coordinates.
+pub(crate) fn level_asset(iid: u128, location: I64Vec2, size: I64Vec2) -> LevelAsset {
+    LevelAsset {
+        bg_color: Color::BLACK,
+        neighbours: vec![],
+        background: None,
+        field_instances: Default::default(),
+        identifier: format!("Level_{iid}"),
+        iid: Iid::from_u128(iid),
+        size,
+        uid: 0,
+        world_depth: 0,
+        location,
+        layers: Default::default(),
+        index: 0,
+    }
+}
+
+/// An entity at `location` in its layer, 16 pixels wide and tall.
+pub(crate) fn entity_asset(
+    identifier: &str,
+    iid: u128,
+    location: I64Vec2,
+    field_instances: HashMap<String, FieldInstance>,
+) -> EntityInstance {
+    EntityInstance {
+        identifier: identifier.to_string(),
+        iid: Iid::from_u128(iid),
+        grid: location / 16,
+        anchor: Anchor::TOP_LEFT,
+        smart_color: Color::WHITE,
+        tags: vec![],
+        tile: None,
+        world_location: None,
+        entity_definition: Handle::default(),
+        field_instances,
+        size: I64Vec2::splat(16),
+        location,
+    }
+}
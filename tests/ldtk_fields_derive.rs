@@ -0,0 +1,187 @@
+use bevy_asset::Handle;
+use bevy_ecs::component::Component;
+use bevy_platform::collections::HashMap;
+use bevy_reflect::{DynamicStruct, FromReflect};
+use shieldtank::bevy_ldtk_asset::enum_definition::EnumDefinition;
+use shieldtank::bevy_ldtk_asset::field_instance::{EnumValue, FieldInstance, FieldInstanceType};
+use shieldtank::component::field_instances::ShieldtankFieldInstances;
+use shieldtank::error::LdtkFieldError;
+use shieldtank::prelude::*;
+
+fn field(field_instance_type: FieldInstanceType) -> FieldInstance {
+    FieldInstance {
+        tileset_rectangle: None,
+        field_instance_type,
+        def_uid: 0,
+    }
+}
+
+fn enum_value(value: &str) -> EnumValue {
+    let mut dynamic = DynamicStruct::default();
+    dynamic.insert("value", value.to_string());
+    dynamic.insert("enum_definition", Handle::<EnumDefinition>::default());
+
+    EnumValue::from_reflect(&dynamic).expect("EnumValue from its reflected fields")
+}
+
+fn field_instances(fields: Vec<(&str, FieldInstanceType)>) -> ShieldtankFieldInstances {
+    ShieldtankFieldInstances::new(
+        fields
+            .into_iter()
+            .map(|(identifier, field_instance_type)| {
+                (identifier.to_string(), field(field_instance_type))
+            })
+            .collect::<HashMap<_, _>>(),
+    )
+}
+
+#[derive(Debug, PartialEq, LdtkEnum)]
+enum Team {
+    Red,
+    #[ldtk(rename = "BlueTeam")]
+    Blue,
+}
+
+#[derive(Debug, Component, LdtkFields)]
+#[ldtk(entity = "Player")]
+struct Player {
+    health: i32,
+    #[ldtk(rename = "Name")]
+    name: String,
+    #[ldtk(default = 1.5)]
+    speed: f32,
+    #[ldtk(default)]
+    lives: u8,
+    team: Team,
+    allies: Vec<Team>,
+    title: Option<String>,
+}
+
+fn player_fields() -> Vec<(&'static str, FieldInstanceType)> {
+    vec![
+        ("health", FieldInstanceType::Int(10)),
+        ("Name", FieldInstanceType::String("Ada".to_string())),
+        ("team", FieldInstanceType::Enum(enum_value("Red"))),
+        (
+            "allies",
+            FieldInstanceType::ArrayEnum(vec![enum_value("BlueTeam"), enum_value("Red")]),
+        ),
+    ]
+}
+
+#[test]
+fn reads_every_field_of_the_struct() {
+    let mut fields = player_fields();
+    fields.push(("speed", FieldInstanceType::Float(2.0)));
+    fields.push(("lives", FieldInstanceType::Int(3)));
+    fields.push(("title", FieldInstanceType::String("Captain".to_string())));
+
+    let player = Player::from_field_instances(&field_instances(fields)).unwrap();
+
+    assert_eq!(Player::ENTITY_IDENTIFIER, Some("Player"));
+    assert_eq!(player.health, 10);
+    assert_eq!(player.name, "Ada");
+    assert_eq!(player.speed, 2.0);
+    assert_eq!(player.lives, 3);
+    assert_eq!(player.team, Team::Red);
+    assert_eq!(player.allies, vec![Team::Blue, Team::Red]);
+    assert_eq!(player.title.as_deref(), Some("Captain"));
+}
+
+#[test]
+fn missing_fields_use_their_defaults() {
+    let player = Player::from_field_instances(&field_instances(player_fields())).unwrap();
+
+    assert_eq!(player.speed, 1.5);
+    assert_eq!(player.lives, 0);
+    assert_eq!(player.title, None);
+}
+
+#[test]
+fn missing_required_field_is_an_error() {
+    let fields = player_fields()
+        .into_iter()
+        .filter(|(identifier, _)| *identifier != "health")
+        .collect();
+
+    let error = Player::from_field_instances(&field_instances(fields)).unwrap_err();
+
+    assert!(matches!(
+        error,
+        LdtkFieldError::Missing { identifier } if identifier == "health"
+    ));
+}
+
+#[test]
+fn wrong_type_is_an_error() {
+    let mut fields = player_fields();
+    fields[0] = ("health", FieldInstanceType::String("ten".to_string()));
+
+    let error = Player::from_field_instances(&field_instances(fields)).unwrap_err();
+
+    assert!(matches!(
+        error,
+        LdtkFieldError::TypeMismatch { identifier, .. } if identifier == "health"
+    ));
+}
+
+#[test]
+fn wrong_type_of_option_field_is_an_error() {
+    let mut fields = player_fields();
+    fields.push(("title", FieldInstanceType::Int(1)));
+
+    let error = Player::from_field_instances(&field_instances(fields)).unwrap_err();
+
+    assert!(matches!(
+        error,
+        LdtkFieldError::TypeMismatch { identifier, .. } if identifier == "title"
+    ));
+}
+
+#[test]
+fn unknown_enum_value_is_an_error() {
+    let mut fields = player_fields();
+    fields[2] = ("team", FieldInstanceType::Enum(enum_value("Green")));
+
+    let error = Player::from_field_instances(&field_instances(fields)).unwrap_err();
+
+    assert!(matches!(
+        error,
+        LdtkFieldError::TypeMismatch { identifier, .. } if identifier == "team"
+    ));
+}
+
+#[test]
+fn enum_values_read_as_strings() {
+    let field_instances = field_instances(vec![
+        ("team", FieldInstanceType::Enum(enum_value("Red"))),
+        (
+            "allies",
+            FieldInstanceType::ArrayEnum(vec![enum_value("Red"), enum_value("BlueTeam")]),
+        ),
+    ]);
+
+    assert_eq!(field_instances.get_field::<String>("team").unwrap(), "Red");
+    assert_eq!(
+        field_instances.get_field::<Vec<String>>("allies").unwrap(),
+        vec!["Red", "BlueTeam"]
+    );
+}
+
+#[test]
+fn entity_refs_read_as_iids() {
+    let entity_ref = shieldtank::bevy_ldtk_asset::field_instance::EntityRef {
+        entity_iid: Iid::from_u128(1),
+        layer_iid: Iid::from_u128(2),
+        level_iid: Iid::from_u128(3),
+        world_iid: Iid::from_u128(4),
+    };
+
+    let field_instances =
+        field_instances(vec![("target", FieldInstanceType::EntityRef(entity_ref))]);
+
+    assert_eq!(
+        field_instances.get_field::<Iid>("target").unwrap(),
+        Iid::from_u128(1)
+    );
+}